@@ -1,13 +1,40 @@
-use amethyst::{
-    prelude::*,
-    renderer::{debug_drawing::DebugLinesComponent, palette::Srgba},
-};
+use std::f32::consts::FRAC_PI_2;
+
+use amethyst::ecs::prelude::*;
 
 use crate::{
-    components::{FullColor, Point},
-    resources::{CommandDescBuilder, CommandList, InputDesc},
+    common::GenerationID,
+    components::{
+        bulge_from_heading, chamfer, crossings, end_heading, extend, fillet, find_boundary, offset,
+        trim, Affine, Anchor, Arc, Array, Arrowhead, AttDef, Attribute, Catenary, Chamfer, Circle,
+        Dependent, DimLook, Dimension, Drawable, Ellipse, Feature, Fill, Group, Hatch, Horizontal,
+        Insert, Justify, Leader, LeaderLook, Line, Link, MText, Measure, Parabola, Pattern, Point,
+        Polyline, Preview, Primitive, Spline, Text, Vertex, Vertical,
+    },
+    csv, dxf,
+    resources::{
+        block_named, lettering_in, ActiveDimStyle, ActiveLeaderStyle, ActiveTextStyle, Blocks,
+        CapturedInput, ChamferSettings, CommandContext, CommandDescBuilder, CommandList,
+        CommandResult, DimStyle, DimStyles, HatchPatterns, InputDesc, LastSegment, Layers,
+        LeaderStyle, LeaderStyles, LogHistory, TextStyle, TextStyles, UnitFormat, Units,
+    },
 };
 
+/// the justifications text can be given
+const JUSTIFY_PROMPT: &str = "justification [l/c/r/m/tl/tc/tr/ml/mc/mr/bl/bc/br]";
+
+/// furthest text can lean either way, in degrees
+const MAX_OBLIQUE: f64 = 85.0;
+
+/// why nothing could be found around a picked point
+const NO_BOUNDARY: &str = "no closed boundary around that point";
+
+/// why two lines couldn't be chamfered
+const NO_CHAMFER: &str = "that chamfer doesn't fit there";
+
+/// why a linear or aligned dimension couldn't work out what it measures
+const DIM_SOURCES_FAILED: &str = "expected three points, or an object with two ends and a point";
+
 pub fn register_commands() -> CommandList {
     let mut commands = CommandList::new();
     // quit
@@ -20,63 +47,3597 @@ pub fn register_commands() -> CommandList {
     //line
     let line = CommandDescBuilder::new("line")
         .with_function(Box::new(line_command))
-        .with_input(InputDesc::Point)
-        .with_input(InputDesc::Point)
+        .with_prompted_input(InputDesc::Point, "first point")
+        .with_prompted_input(InputDesc::Point, "next point, enter to finish")
         .build();
     commands.add("line", line);
     commands.alias("l", "line");
 
+    let arc_sce = CommandDescBuilder::new("arc")
+        .with_function(Box::new(arc_sce_command))
+        .with_prompted_input(InputDesc::Point, "start point")
+        .with_prompted_input(InputDesc::Point, "center")
+        .with_prompted_input(InputDesc::Point, "end point")
+        .build();
+    let arc_ser = CommandDescBuilder::new("arc")
+        .with_function(Box::new(arc_ser_command))
+        .with_prompted_input(InputDesc::Point, "start point")
+        .with_prompted_input(InputDesc::Point, "end point")
+        .with_prompted_input(InputDesc::Distance, "radius, negative for the larger arc")
+        .build();
+    let arc_cont = CommandDescBuilder::new("arc")
+        .with_function(Box::new(arc_cont_command))
+        .with_prompted_input(InputDesc::Point, "end point")
+        .build();
     let arc = CommandDescBuilder::new("arc")
         .with_function(Box::new(arc_command))
-        .with_input(InputDesc::Point)
-        .with_input(InputDesc::Point)
-        .with_input(InputDesc::Point)
+        .with_prompted_input(InputDesc::Point, "start point")
+        .with_prompted_input(InputDesc::Point, "second point")
+        .with_prompted_input(InputDesc::Point, "end point")
+        .with_option("sce", arc_sce)
+        .with_option("ser", arc_ser)
+        .with_option("cont", arc_cont)
         .build();
     commands.add("arc", arc);
+    commands.alias("a", "arc");
+
+    let circle_diameter = CommandDescBuilder::new("circle")
+        .with_function(Box::new(circle_diameter_command))
+        .with_prompted_input(InputDesc::Point, "center")
+        .with_prompted_input(InputDesc::Distance, "diameter")
+        .build();
+    let circle_2p = CommandDescBuilder::new("circle")
+        .with_function(Box::new(circle_2p_command))
+        .with_prompted_input(InputDesc::Point, "first end of diameter")
+        .with_prompted_input(InputDesc::Point, "second end of diameter")
+        .build();
+    let circle_3p = CommandDescBuilder::new("circle")
+        .with_function(Box::new(circle_3p_command))
+        .with_prompted_input(InputDesc::Point, "first point")
+        .with_prompted_input(InputDesc::Point, "second point")
+        .with_prompted_input(InputDesc::Point, "third point")
+        .build();
+    let circle_ttr = CommandDescBuilder::new("circle")
+        .with_function(Box::new(circle_ttr_command))
+        .with_prompted_input(InputDesc::Select, "first line or circle to touch")
+        .with_prompted_input(InputDesc::Select, "second line or circle to touch")
+        .with_prompted_input(InputDesc::Distance, "radius")
+        .build();
+    let circle = CommandDescBuilder::new("circle")
+        .with_function(Box::new(circle_command))
+        .with_prompted_input(InputDesc::Point, "center")
+        .with_prompted_input(InputDesc::Distance, "radius")
+        .with_option("d", circle_diameter)
+        .with_option("2p", circle_2p)
+        .with_option("3p", circle_3p)
+        .with_option("ttr", circle_ttr)
+        .build();
+    commands.add("circle", circle);
+    commands.alias("c", "circle");
+
+    let ellipse_center = CommandDescBuilder::new("ellipse")
+        .with_function(Box::new(ellipse_center_command))
+        .with_prompted_input(InputDesc::Point, "center")
+        .with_prompted_input(InputDesc::Point, "end of axis")
+        .with_prompted_input(InputDesc::Distance, "distance to other axis")
+        .build();
+    let ellipse_arc = CommandDescBuilder::new("ellipse")
+        .with_function(Box::new(ellipse_arc_command))
+        .with_prompted_input(InputDesc::Point, "first end of axis")
+        .with_prompted_input(InputDesc::Point, "second end of axis")
+        .with_prompted_input(InputDesc::Distance, "distance to other axis")
+        .with_prompted_input(InputDesc::Point, "start angle")
+        .with_prompted_input(InputDesc::Point, "end angle")
+        .build();
+    let ellipse = CommandDescBuilder::new("ellipse")
+        .with_function(Box::new(ellipse_command))
+        .with_prompted_input(InputDesc::Point, "first end of axis")
+        .with_prompted_input(InputDesc::Point, "second end of axis")
+        .with_prompted_input(InputDesc::Distance, "distance to other axis")
+        .with_option("c", ellipse_center)
+        .with_option("a", ellipse_arc)
+        .build();
+    commands.add("ellipse", ellipse);
+    commands.alias("el", "ellipse");
+
+    let pline = CommandDescBuilder::new("pline")
+        .with_function(Box::new(pline_command))
+        .with_prompted_input(InputDesc::Point, "start point")
+        .build();
+    commands.add("pline", pline);
+    commands.alias("pl", "pline");
+
+    let pedit = CommandDescBuilder::new("pedit")
+        .with_function(Box::new(pedit_command))
+        .with_prompted_input(InputDesc::Select, "polyline or spline to edit")
+        .build();
+    commands.add("pedit", pedit);
+    commands.alias("pe", "pedit");
+
+    let catenary_sag = CommandDescBuilder::new("catenary")
+        .with_function(Box::new(catenary_sag_command))
+        .with_prompted_input(InputDesc::Point, "first support")
+        .with_prompted_input(InputDesc::Point, "second support")
+        .with_prompted_input(InputDesc::Distance, "sag below the middle of the span")
+        .build();
+    let catenary = CommandDescBuilder::new("catenary")
+        .with_function(Box::new(catenary_command))
+        .with_prompted_input(InputDesc::Point, "first support")
+        .with_prompted_input(InputDesc::Point, "second support")
+        .with_prompted_input(InputDesc::Distance, "cable length")
+        .with_option("s", catenary_sag)
+        .build();
+    commands.add("catenary", catenary);
+    commands.alias("cat", "catenary");
+
+    let parabola_3p = CommandDescBuilder::new("parabola")
+        .with_function(Box::new(parabola_3p_command))
+        .with_prompted_input(InputDesc::Point, "start point")
+        .with_prompted_input(InputDesc::Point, "point halfway along")
+        .with_prompted_input(InputDesc::Point, "end point")
+        .build();
+    let parabola = CommandDescBuilder::new("parabola")
+        .with_function(Box::new(parabola_command))
+        .with_prompted_input(InputDesc::Point, "vertex")
+        .with_prompted_input(InputDesc::Point, "focus")
+        .with_prompted_input(InputDesc::Point, "how far across to draw")
+        .with_option("3p", parabola_3p)
+        .build();
+    commands.add("parabola", parabola);
+    commands.alias("par", "parabola");
+
+    let spline_cv = CommandDescBuilder::new("spline")
+        .with_function(Box::new(spline_cv_command))
+        .with_prompted_input(InputDesc::Point, "first control point")
+        .build();
+    let spline = CommandDescBuilder::new("spline")
+        .with_function(Box::new(spline_command))
+        .with_prompted_input(InputDesc::Point, "first fit point")
+        .with_option("cv", spline_cv)
+        .build();
+    commands.add("spline", spline);
+    commands.alias("spl", "spline");
+
+    let text_justify = CommandDescBuilder::new("text")
+        .with_function(Box::new(text_command))
+        .with_prompted_input(InputDesc::Keyword, JUSTIFY_PROMPT)
+        .with_prompted_input(InputDesc::Point, "insertion point")
+        .build();
+    let text = CommandDescBuilder::new("text")
+        .with_function(Box::new(text_command))
+        .with_prompted_input(InputDesc::Point, "insertion point")
+        .with_option("j", text_justify)
+        .build();
+    commands.add("text", text);
+    commands.alias("dt", "text");
+
+    let mtext_justify = CommandDescBuilder::new("mtext")
+        .with_function(Box::new(mtext_command))
+        .with_prompted_input(InputDesc::Keyword, JUSTIFY_PROMPT)
+        .with_prompted_input(InputDesc::Point, "first corner")
+        .with_prompted_input(InputDesc::Point, "opposite corner")
+        .build();
+    let mtext = CommandDescBuilder::new("mtext")
+        .with_function(Box::new(mtext_command))
+        .with_prompted_input(InputDesc::Point, "first corner")
+        .with_prompted_input(InputDesc::Point, "opposite corner")
+        .with_option("j", mtext_justify)
+        .build();
+    commands.add("mtext", mtext);
+    commands.alias("mt", "mtext");
+    commands.alias("t", "mtext");
+
+    let style = CommandDescBuilder::new("style")
+        .with_function(Box::new(style_command))
+        .with_prompted_input(InputDesc::Text, "text style to create or edit")
+        .build();
+    commands.add("style", style);
+    commands.alias("st", "style");
+
+    let dim_points = |builder: CommandDescBuilder| {
+        builder
+            .with_prompted_input(InputDesc::Point, "first extension line origin")
+            .with_prompted_input(InputDesc::Point, "second extension line origin")
+            .with_prompted_input(InputDesc::Point, "dimension line location")
+    };
+    // measuring between the ends of an object, and following it when it changes
+    let dim_object = |builder: CommandDescBuilder| {
+        builder
+            .with_prompted_input(InputDesc::Select, "object to dimension")
+            .with_prompted_input(InputDesc::Point, "dimension line location")
+    };
+    let dimlinear_h = dim_points(
+        CommandDescBuilder::new("dimlinear").with_function(Box::new(dimlinear_h_command)),
+    )
+    .build();
+    let dimlinear_v = dim_points(
+        CommandDescBuilder::new("dimlinear").with_function(Box::new(dimlinear_v_command)),
+    )
+    .build();
+    let dimlinear_r = dim_points(
+        CommandDescBuilder::new("dimlinear")
+            .with_function(Box::new(dimlinear_r_command))
+            .with_prompted_input(InputDesc::Distance, "rotation angle in degrees"),
+    )
+    .build();
+    let dimlinear =
+        dim_points(CommandDescBuilder::new("dimlinear").with_function(Box::new(dimlinear_command)))
+            .with_option("h", dimlinear_h)
+            .with_option("v", dimlinear_v)
+            .with_option("r", dimlinear_r)
+            .with_option(
+                "s",
+                dim_object(
+                    CommandDescBuilder::new("dimlinear").with_function(Box::new(dimlinear_command)),
+                )
+                .build(),
+            )
+            .build();
+    commands.add("dimlinear", dimlinear);
+    commands.alias("dli", "dimlinear");
+
+    let dimaligned_s = dim_object(
+        CommandDescBuilder::new("dimaligned").with_function(Box::new(dimaligned_command)),
+    )
+    .build();
+    let dimaligned = dim_points(
+        CommandDescBuilder::new("dimaligned").with_function(Box::new(dimaligned_command)),
+    )
+    .with_option("s", dimaligned_s)
+    .build();
+    commands.add("dimaligned", dimaligned);
+    commands.alias("dal", "dimaligned");
+
+    let dimangular_3p = CommandDescBuilder::new("dimangular")
+        .with_function(Box::new(dimangular_3p_command))
+        .with_prompted_input(InputDesc::Point, "angle vertex")
+        .with_prompted_input(InputDesc::Point, "first angle endpoint")
+        .with_prompted_input(InputDesc::Point, "second angle endpoint")
+        .with_prompted_input(InputDesc::Point, "dimension arc location")
+        .build();
+    let dimangular = CommandDescBuilder::new("dimangular")
+        .with_function(Box::new(dimangular_command))
+        .with_prompted_input(InputDesc::Select, "first line")
+        .with_prompted_input(InputDesc::Select, "second line")
+        .with_prompted_input(InputDesc::Point, "dimension arc location")
+        .with_option("3p", dimangular_3p)
+        .build();
+    commands.add("dimangular", dimangular);
+    commands.alias("dan", "dimangular");
+
+    let dimradius = CommandDescBuilder::new("dimradius")
+        .with_function(Box::new(dimradius_command))
+        .with_prompted_input(InputDesc::Select, "arc or circle")
+        .with_prompted_input(InputDesc::Point, "dimension line location")
+        .build();
+    commands.add("dimradius", dimradius);
+    commands.alias("dra", "dimradius");
+
+    let dimdiameter = CommandDescBuilder::new("dimdiameter")
+        .with_function(Box::new(dimdiameter_command))
+        .with_prompted_input(InputDesc::Select, "arc or circle")
+        .with_prompted_input(InputDesc::Point, "dimension line location")
+        .build();
+    commands.add("dimdiameter", dimdiameter);
+    commands.alias("ddi", "dimdiameter");
+
+    let dimarc = CommandDescBuilder::new("dimarc")
+        .with_function(Box::new(dimarc_command))
+        .with_prompted_input(InputDesc::Select, "arc")
+        .with_prompted_input(InputDesc::Point, "dimension arc location")
+        .build();
+    commands.add("dimarc", dimarc);
+    commands.alias("dar", "dimarc");
+
+    let dimordinate_s = CommandDescBuilder::new("dimordinate")
+        .with_function(Box::new(dimordinate_command))
+        .with_prompted_input(InputDesc::Select, "object whose center to dimension")
+        .with_prompted_input(InputDesc::Point, "leader endpoint")
+        .build();
+    let dimordinate = CommandDescBuilder::new("dimordinate")
+        .with_function(Box::new(dimordinate_command))
+        .with_prompted_input(InputDesc::Point, "feature location")
+        .with_prompted_input(InputDesc::Point, "leader endpoint")
+        .with_option("s", dimordinate_s)
+        .build();
+    commands.add("dimordinate", dimordinate);
+    commands.alias("dor", "dimordinate");
+
+    let dimjogged = CommandDescBuilder::new("dimjogged")
+        .with_function(Box::new(dimjogged_command))
+        .with_prompted_input(InputDesc::Select, "arc or circle")
+        .with_prompted_input(InputDesc::Point, "center location override")
+        .with_prompted_input(InputDesc::Point, "dimension line location")
+        .with_prompted_input(InputDesc::Point, "jog location")
+        .build();
+    commands.add("dimjogged", dimjogged);
+    commands.alias("djo", "dimjogged");
+
+    let dimstyle = CommandDescBuilder::new("dimstyle")
+        .with_function(Box::new(dimstyle_command))
+        .with_prompted_input(InputDesc::Text, "dimension style to create or edit")
+        .build();
+    commands.add("dimstyle", dimstyle);
+    commands.alias("d", "dimstyle");
+
+    let leader = CommandDescBuilder::new("leader")
+        .with_function(Box::new(leader_command))
+        .with_prompted_input(InputDesc::Point, "arrowhead location")
+        .build();
+    commands.add("leader", leader);
+    commands.alias("le", "leader");
+
+    let mleader = CommandDescBuilder::new("mleader")
+        .with_function(Box::new(mleader_command))
+        .with_prompted_input(InputDesc::Point, "arrowhead location")
+        .build();
+    commands.add("mleader", mleader);
+    commands.alias("mld", "mleader");
+
+    let mleaderstyle = CommandDescBuilder::new("mleaderstyle")
+        .with_function(Box::new(mleaderstyle_command))
+        .with_prompted_input(InputDesc::Text, "leader style to create or edit")
+        .build();
+    commands.add("mleaderstyle", mleaderstyle);
+    commands.alias("mls", "mleaderstyle");
+
+    let hatch_pick = CommandDescBuilder::new("hatch")
+        .with_function(Box::new(hatch_pick_command))
+        .with_prompted_input(InputDesc::Point, "point inside the area")
+        .with_prompted_input(
+            InputDesc::Text,
+            "pattern name, solid, or u for user defined",
+        )
+        .build();
+    let hatch = CommandDescBuilder::new("hatch")
+        .with_function(Box::new(hatch_command))
+        .with_prompted_input(InputDesc::Multiselect, "boundary objects, enter when done")
+        .with_prompted_input(
+            InputDesc::Text,
+            "pattern name, solid, or u for user defined",
+        )
+        .with_option("p", hatch_pick)
+        .build();
+    commands.add("hatch", hatch);
+    commands.alias("h", "hatch");
+
+    let boundary = CommandDescBuilder::new("boundary")
+        .with_function(Box::new(boundary_command))
+        .with_prompted_input(InputDesc::Point, "point inside the area")
+        .build();
+    commands.add("boundary", boundary);
+    commands.alias("bo", "boundary");
+
+    let block = CommandDescBuilder::new("block")
+        .with_function(Box::new(block_command))
+        .with_prompted_input(InputDesc::Text, "block name")
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(
+            InputDesc::Multiselect,
+            "objects in the block, enter when done",
+        )
+        .build();
+    commands.add("block", block);
+    commands.alias("b", "block");
+
+    let insert = CommandDescBuilder::new("insert")
+        .with_function(Box::new(insert_command))
+        .with_prompted_input(InputDesc::Text, "block to insert")
+        .with_prompted_input(InputDesc::Point, "insertion point")
+        .with_prompted_input(InputDesc::Distance, "scale")
+        .with_prompted_input(InputDesc::Distance, "rotation in degrees")
+        .build();
+    commands.add("insert", insert);
+    commands.alias("i", "insert");
+
+    let minsert = CommandDescBuilder::new("minsert")
+        .with_function(Box::new(minsert_command))
+        .with_prompted_input(InputDesc::Text, "block to insert")
+        .with_prompted_input(InputDesc::Point, "insertion point")
+        .with_prompted_input(InputDesc::Distance, "scale")
+        .with_prompted_input(InputDesc::Distance, "rotation in degrees")
+        .with_prompted_input(InputDesc::Distance, "number of rows")
+        .with_prompted_input(InputDesc::Distance, "number of columns")
+        .with_prompted_input(InputDesc::Distance, "distance between rows")
+        .with_prompted_input(InputDesc::Distance, "distance between columns")
+        .build();
+    commands.add("minsert", minsert);
+
+    let wblock = CommandDescBuilder::new("wblock")
+        .with_function(Box::new(wblock_command))
+        .with_prompted_input(InputDesc::Text, "block to write out")
+        .build();
+    commands.add("wblock", wblock);
+    commands.alias("w", "wblock");
+
+    let attdef = CommandDescBuilder::new("attdef")
+        .with_function(Box::new(attdef_command))
+        .with_prompted_input(
+            InputDesc::Keyword,
+            "constant, invisible, both or neither [c/i/ci/n]",
+        )
+        .with_prompted_input(InputDesc::Text, "tag")
+        .with_prompted_input(InputDesc::Point, "insertion point")
+        .build();
+    commands.add("attdef", attdef);
+    commands.alias("att", "attdef");
+
+    let attedit = CommandDescBuilder::new("attedit")
+        .with_function(Box::new(attedit_command))
+        .with_prompted_input(InputDesc::Select, "block reference")
+        .build();
+    commands.add("attedit", attedit);
+    commands.alias("ate", "attedit");
+
+    let attext = CommandDescBuilder::new("attext")
+        .with_function(Box::new(attext_command))
+        .build();
+    commands.add("attext", attext);
+
+    let units = CommandDescBuilder::new("units")
+        .with_function(Box::new(units_command))
+        .with_prompted_input(
+            InputDesc::Keyword,
+            "decimal, fractional or architectural [d/f/a]",
+        )
+        .with_prompted_input(InputDesc::Distance, "precision")
+        .build();
+    commands.add("units", units);
+    commands.alias("un", "units");
+
+    let break_ = CommandDescBuilder::new("break")
+        .with_function(Box::new(break_command))
+        .with_prompted_input(InputDesc::Select, "object to break")
+        .with_prompted_input(InputDesc::Point, "point to break at")
+        .build();
+    commands.add("break", break_);
+    commands.alias("br", "break");
+
+    let fillet_polyline = CommandDescBuilder::new("fillet")
+        .with_function(Box::new(fillet_polyline_command))
+        .with_prompted_input(InputDesc::Select, "polyline to round the corners of")
+        .with_prompted_input(InputDesc::Distance, "radius")
+        .build();
+    let fillet = CommandDescBuilder::new("fillet")
+        .with_function(Box::new(fillet_command))
+        .with_prompted_input(InputDesc::Select, "first line or arc")
+        .with_prompted_input(InputDesc::Select, "second line or arc")
+        .with_prompted_input(InputDesc::Distance, "radius, 0 for a sharp corner")
+        .with_option("p", fillet_polyline)
+        .build();
+    commands.add("fillet", fillet);
+    commands.alias("f", "fillet");
+
+    let chamfer_distances = CommandDescBuilder::new("chamfer")
+        .with_function(Box::new(chamfer_distances_command))
+        .with_prompted_input(InputDesc::Distance, "distance along the first line")
+        .with_prompted_input(InputDesc::Distance, "distance along the second line")
+        .with_prompted_input(InputDesc::Select, "first line or polyline segment")
+        .with_prompted_input(InputDesc::Select, "second line or polyline segment")
+        .build();
+    let chamfer_angle = CommandDescBuilder::new("chamfer")
+        .with_function(Box::new(chamfer_angle_command))
+        .with_prompted_input(InputDesc::Distance, "distance along the first line")
+        .with_prompted_input(InputDesc::Distance, "angle from the first line in degrees")
+        .with_prompted_input(InputDesc::Select, "first line or polyline segment")
+        .with_prompted_input(InputDesc::Select, "second line or polyline segment")
+        .build();
+    let chamfer_trim = CommandDescBuilder::new("chamfer")
+        .with_function(Box::new(chamfer_trim_command))
+        .with_prompted_input(InputDesc::Keyword, "trim the lines back to the cut [t/n]")
+        .with_prompted_input(InputDesc::Select, "first line or polyline segment")
+        .with_prompted_input(InputDesc::Select, "second line or polyline segment")
+        .build();
+    let chamfer_polyline = CommandDescBuilder::new("chamfer")
+        .with_function(Box::new(chamfer_polyline_command))
+        .with_prompted_input(InputDesc::Select, "polyline to cut the corners of")
+        .build();
+    let chamfer = CommandDescBuilder::new("chamfer")
+        .with_function(Box::new(chamfer_command))
+        .with_prompted_input(
+            InputDesc::Select,
+            "first line or polyline segment, or d, a, t or p to change how",
+        )
+        .with_prompted_input(InputDesc::Select, "second line or polyline segment")
+        .with_option("d", chamfer_distances)
+        .with_option("a", chamfer_angle)
+        .with_option("t", chamfer_trim)
+        .with_option("p", chamfer_polyline)
+        .build();
+    commands.add("chamfer", chamfer);
+    commands.alias("cha", "chamfer");
+
+    let trim_fence = CommandDescBuilder::new("trim")
+        .with_function(Box::new(trim_fence_command))
+        .with_prompted_input(
+            InputDesc::Multiselect,
+            "cutting edges, enter with none for everything",
+        )
+        .with_prompted_input(InputDesc::Point, "first point of fence")
+        .with_prompted_input(InputDesc::Point, "second point of fence")
+        .build();
+    let trim = CommandDescBuilder::new("trim")
+        .with_function(Box::new(trim_command))
+        .with_prompted_input(
+            InputDesc::Multiselect,
+            "cutting edges, enter with none for everything",
+        )
+        .with_prompted_input(InputDesc::Select, "side of an object to trim off")
+        .with_option("f", trim_fence)
+        .build();
+    commands.add("trim", trim);
+    commands.alias("tr", "trim");
+
+    let extend = CommandDescBuilder::new("extend")
+        .with_function(Box::new(extend_command))
+        .with_prompted_input(
+            InputDesc::Multiselect,
+            "boundary edges, enter with none for everything",
+        )
+        .with_prompted_input(InputDesc::Select, "end of an object to extend")
+        .build();
+    commands.add("extend", extend);
+    commands.alias("ex", "extend");
+
+    let offset_through = CommandDescBuilder::new("offset")
+        .with_function(Box::new(offset_through_command))
+        .with_prompted_input(InputDesc::Select, "object to offset")
+        .with_prompted_input(InputDesc::Point, "point to pass through")
+        .build();
+    let offset = CommandDescBuilder::new("offset")
+        .with_function(Box::new(offset_command))
+        .with_prompted_input(InputDesc::Distance, "offset distance")
+        .with_prompted_input(InputDesc::Select, "object to offset")
+        .with_prompted_input(InputDesc::Point, "side to offset toward")
+        .with_option("t", offset_through)
+        .build();
+    commands.add("offset", offset);
+    commands.alias("o", "offset");
+
+    let mirror = CommandDescBuilder::new("mirror")
+        .with_function(Box::new(mirror_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "first point of mirror line")
+        .with_prompted_input(InputDesc::Point, "second point of mirror line")
+        .build();
+    commands.add("mirror", mirror);
+    commands.alias("mi", "mirror");
+
+    let move_ = CommandDescBuilder::new("move")
+        .with_function(Box::new(move_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(InputDesc::Point, "point to move to")
+        .build();
+    commands.add("move", move_);
+    commands.alias("m", "move");
+
+    let copy = CommandDescBuilder::new("copy")
+        .with_function(Box::new(copy_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(InputDesc::Point, "point to copy to")
+        .build();
+    commands.add("copy", copy);
+    commands.alias("co", "copy");
+    commands.alias("cp", "copy");
+
+    let rotate_reference = CommandDescBuilder::new("rotate")
+        .with_function(Box::new(rotate_reference_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(
+            InputDesc::Point,
+            "reference angle in degrees, or first point",
+        )
+        .build();
+    let rotate = CommandDescBuilder::new("rotate")
+        .with_function(Box::new(rotate_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(InputDesc::Distance, "rotation in degrees")
+        .with_option("r", rotate_reference)
+        .build();
+    commands.add("rotate", rotate);
+    commands.alias("ro", "rotate");
+
+    let scale_reference = CommandDescBuilder::new("scale")
+        .with_function(Box::new(scale_reference_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(InputDesc::Point, "reference length, or first point")
+        .build();
+    let scale = CommandDescBuilder::new("scale")
+        .with_function(Box::new(scale_command))
+        .with_input(InputDesc::Multiselect)
+        .with_prompted_input(InputDesc::Point, "base point")
+        .with_prompted_input(InputDesc::Distance, "scale factor")
+        .with_option("r", scale_reference)
+        .build();
+    commands.add("scale", scale);
+    commands.alias("sc", "scale");
+
+    let dxfout = CommandDescBuilder::new("dxfout")
+        .with_function(Box::new(dxfout_command))
+        .build();
+    commands.add("dxfout", dxfout);
+
+    let length = CommandDescBuilder::new("length")
+        .with_function(Box::new(length_command))
+        .with_input(InputDesc::Multiselect)
+        .build();
+    commands.add("length", length);
+    commands.alias("len", "length");
 
     commands
 }
 
-fn arc_command(_: &mut World, _: &[InputDesc]) -> SimpleTrans {
-    Trans::Quit
-}
-
-fn quit_command(_: &mut World, _: &[InputDesc]) -> SimpleTrans {
-    Trans::Quit
-}
-
-fn line_command(w: &mut World, _: &[InputDesc]) -> SimpleTrans {
-    use nalgebra::geometry::Point as nPoint;
-    use rand::prelude::*;
-    let mut rng = rand::thread_rng();
-    let a = Point {
-        x: rng.gen_range(0, 600) as f32,
-        y: rng.gen_range(0, 600) as f32,
-    };
-    let b = Point {
-        x: rng.gen_range(0, 600) as f32,
-        y: rng.gen_range(0, 600) as f32,
-    };
-    let c = FullColor {
-        r: 234,
-        g: 65,
-        b: 212,
-    };
-    // let line = Drawable::Line(Line {
-    //     start: a,
-    //     end: b,
-    //     layer: layer,
-    //     color: Color::Full(c),
-    //     scale: 1.0,
-    //     linetype: style,
-    //     weight: 1.0,
-    // });
-    let mut debug_lines = DebugLinesComponent::new();
-    let start: nPoint<f32, nalgebra::base::dimension::U3> = nPoint::from_slice(&[a.x, a.y, 0.0]);
-    let end: nPoint<f32, nalgebra::base::dimension::U3> = nPoint::from_slice(&[b.x, b.y, 0.0]);
-    let color = Srgba::new(c.r as f32, c.g as f32, c.b as f32, 1.0);
-    debug_lines.add_line(start, end, color);
-
-    w.create_entity().with(debug_lines).build();
-    Trans::Pop
+/// makes `change` to the drawables `affected` picks out, leaving the rest
+/// untouched so nothing following them has to be worked out again, and to
+/// the members of block definitions so later inserts of them match
+fn change_drawables(
+    w: &World,
+    affected: impl Fn(&Drawable) -> bool,
+    mut change: impl FnMut(&mut Drawable),
+) {
+    let mut drawables = w.write_storage::<Drawable>();
+    for mut drawable in (&mut drawables.restrict_mut()).join() {
+        if affected(drawable.get_unchecked()) {
+            change(drawable.get_mut_unchecked());
+        }
+    }
+    for block in w.write_resource::<Blocks>().iter_mut() {
+        for member in block.members_mut() {
+            if affected(member) {
+                change(member);
+            }
+        }
+    }
+}
+
+fn to_point(x: f64, y: f64) -> Point {
+    Point::new(x as f32, y as f32)
+}
+
+/// add an arc which was drawn finishing at `end`, so the next line or arc can continue from it
+fn create_arc(w: &mut World, arc: Option<Arc>, end: Point) -> CommandResult {
+    match arc {
+        Some(arc) => {
+            w.write_resource::<LastSegment>().tangent = Some((end, arc.heading_at(end)));
+            w.create_entity().with(Drawable::Arc(arc)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no arc fits those inputs".to_string()),
+    }
+}
+
+fn arc_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Point(cx, cy)] =>
+        {
+            let end = to_point(*cx, *cy);
+            let arc =
+                Arc::through_points(to_point(*ax, *ay), to_point(*bx, *by), end, ctx.properties);
+            create_arc(w, arc, end)
+        }
+        _ => CommandResult::Failed("expected three points".to_string()),
+    }
+}
+
+fn arc_sce_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(sx, sy), CapturedInput::Point(cx, cy), CapturedInput::Point(ex, ey)] =>
+        {
+            let arc = Arc::start_center_end(
+                to_point(*sx, *sy),
+                to_point(*cx, *cy),
+                to_point(*ex, *ey),
+                ctx.properties,
+            );
+            let end = arc
+                .map(|arc| arc.end_point())
+                .unwrap_or_else(|| to_point(*ex, *ey));
+            create_arc(w, arc, end)
+        }
+        _ => CommandResult::Failed("expected start, center and end points".to_string()),
+    }
+}
+
+fn arc_ser_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(sx, sy), CapturedInput::Point(ex, ey), CapturedInput::Distance(r)] => {
+            let end = to_point(*ex, *ey);
+            let arc = Arc::start_end_radius(to_point(*sx, *sy), end, *r as f32, ctx.properties);
+            create_arc(w, arc, end)
+        }
+        _ => CommandResult::Failed("expected start and end points and a radius".to_string()),
+    }
+}
+
+fn arc_cont_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let last = w.read_resource::<LastSegment>().tangent;
+    match (last, inputs) {
+        (Some((start, heading)), [CapturedInput::Point(ex, ey)]) => {
+            let end = to_point(*ex, *ey);
+            let arc = Arc::tangent_from(start, heading, end, ctx.properties);
+            create_arc(w, arc, end)
+        }
+        (None, _) => CommandResult::Failed("no line or arc to continue from".to_string()),
+        _ => CommandResult::Failed("expected an end point".to_string()),
+    }
+}
+
+fn create_circle(w: &mut World, circle: Option<Circle>) -> CommandResult {
+    match circle {
+        Some(circle) => {
+            w.create_entity().with(Drawable::Circle(circle)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no circle fits those inputs".to_string()),
+    }
+}
+
+fn circle_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(x, y), CapturedInput::Distance(r)] => {
+            let circle = Circle::new(to_point(*x, *y), *r as f32, ctx.properties);
+            create_circle(w, circle)
+        }
+        _ => CommandResult::Failed("expected a center and a radius".to_string()),
+    }
+}
+
+fn circle_diameter_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(x, y), CapturedInput::Distance(d)] => {
+            let circle = Circle::new(to_point(*x, *y), *d as f32 / 2.0, ctx.properties);
+            create_circle(w, circle)
+        }
+        _ => CommandResult::Failed("expected a center and a diameter".to_string()),
+    }
+}
+
+fn circle_2p_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] => {
+            let circle = Circle::two_point(to_point(*ax, *ay), to_point(*bx, *by), ctx.properties);
+            create_circle(w, circle)
+        }
+        _ => CommandResult::Failed("expected two points".to_string()),
+    }
+}
+
+fn circle_3p_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Point(cx, cy)] =>
+        {
+            let circle = Circle::three_point(
+                to_point(*ax, *ay),
+                to_point(*bx, *by),
+                to_point(*cx, *cy),
+                ctx.properties,
+            );
+            create_circle(w, circle)
+        }
+        _ => CommandResult::Failed("expected three points".to_string()),
+    }
+}
+
+fn circle_ttr_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(a, ax, ay), CapturedInput::Select(b, bx, by), CapturedInput::Distance(r)] =>
+        {
+            let (first, second) = {
+                let drawables = w.read_storage::<Drawable>();
+                (
+                    drawables.get(*a).and_then(Primitive::of),
+                    drawables.get(*b).and_then(Primitive::of),
+                )
+            };
+            match (first, second) {
+                (Some(first), Some(second)) => {
+                    let circle = Circle::tangent_tangent_radius(
+                        first,
+                        to_point(*ax, *ay),
+                        second,
+                        to_point(*bx, *by),
+                        *r as f32,
+                        ctx.properties,
+                    );
+                    create_circle(w, circle)
+                }
+                _ => CommandResult::Failed("can only touch lines, arcs and circles".to_string()),
+            }
+        }
+        _ => CommandResult::Failed("expected two objects and a radius".to_string()),
+    }
+}
+
+/// the whole ellipse whose first axis runs between `a` and `b`
+fn ellipse_between(a: Point, b: Point, other: f64, ctx: &CommandContext) -> Option<Ellipse> {
+    Ellipse::from_axes((a + b) * 0.5, (b - a) * 0.5, other as f32, ctx.properties)
+}
+
+fn create_ellipse(w: &mut World, ellipse: Option<Ellipse>) -> CommandResult {
+    match ellipse {
+        Some(ellipse) => {
+            w.create_entity().with(Drawable::Ellipse(ellipse)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no ellipse fits those inputs".to_string()),
+    }
+}
+
+fn ellipse_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Distance(d)] => {
+            let ellipse = ellipse_between(to_point(*ax, *ay), to_point(*bx, *by), *d, ctx);
+            create_ellipse(w, ellipse)
+        }
+        _ => CommandResult::Failed("expected both ends of an axis and a distance".to_string()),
+    }
+}
+
+fn ellipse_center_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(cx, cy), CapturedInput::Point(ax, ay), CapturedInput::Distance(d)] => {
+            let center = to_point(*cx, *cy);
+            let axis = to_point(*ax, *ay) - center;
+            let ellipse = Ellipse::from_axes(center, axis, *d as f32, ctx.properties);
+            create_ellipse(w, ellipse)
+        }
+        _ => CommandResult::Failed("expected a center, an axis end and a distance".to_string()),
+    }
+}
+
+fn ellipse_arc_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Distance(d), CapturedInput::Point(sx, sy), CapturedInput::Point(ex, ey)] =>
+        {
+            let ellipse = ellipse_between(to_point(*ax, *ay), to_point(*bx, *by), *d, ctx)
+                .map(|ellipse| ellipse.trimmed(to_point(*sx, *sy), to_point(*ex, *ey)));
+            create_ellipse(w, ellipse)
+        }
+        _ => CommandResult::Failed(
+            "expected both ends of an axis, a distance and two angles".to_string(),
+        ),
+    }
+}
+
+/// degree of splines drawn from control points unless another is asked for
+const SPLINE_DEGREE: usize = 3;
+
+/// Draws a polyline from every input collected so far, asking for more until
+/// it is finished with enter or closed.
+fn pline_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let mut vertices: Vec<Vertex> = vec![];
+    let mut arcs = false;
+    let mut width = (0.0, 0.0);
+    // which of the starting and ending widths is being typed in
+    let mut asking_width: Option<bool> = None;
+    let mut closed = false;
+    let mut finished = false;
+    // carry on smoothly from the last thing drawn if starting where it ended
+    let mut heading = match (w.read_resource::<LastSegment>().tangent, inputs.first()) {
+        (Some((end, heading)), Some(CapturedInput::Point(x, y)))
+            if end.distance(to_point(*x, *y)) <= f32::EPSILON =>
+        {
+            Some(heading)
+        }
+        _ => None,
+    };
+    for input in inputs {
+        let next = match input {
+            CapturedInput::Point(x, y) => Some(to_point(*x, *y)),
+            CapturedInput::Distance(d) => {
+                match asking_width {
+                    Some(true) => {
+                        width = (*d as f32, *d as f32);
+                        asking_width = Some(false);
+                    }
+                    Some(false) => {
+                        width.1 = *d as f32;
+                        asking_width = None;
+                    }
+                    None => (),
+                }
+                None
+            }
+            CapturedInput::Keyword(keyword) => match keyword.as_str() {
+                "a" => {
+                    arcs = true;
+                    None
+                }
+                "l" => {
+                    arcs = false;
+                    None
+                }
+                "w" => {
+                    asking_width = Some(true);
+                    None
+                }
+                "c" if vertices.len() > 1 => {
+                    closed = true;
+                    finished = true;
+                    Some(vertices[0].position)
+                }
+                _ => None,
+            },
+            CapturedInput::Confirm => {
+                finished = true;
+                None
+            }
+            _ => None,
+        };
+        if let Some(p) = next {
+            if let Some(last) = vertices.last_mut() {
+                last.bulge = match heading {
+                    Some(heading) if arcs => bulge_from_heading(last.position, p, heading),
+                    _ => 0.0,
+                };
+                last.start_width = width.0;
+                last.end_width = width.1;
+                heading = Some(end_heading(last.position, p, last.bulge));
+                width.0 = width.1;
+            }
+            if !closed {
+                vertices.push(Vertex::new(p));
+            }
+        }
+    }
+
+    if finished {
+        if vertices.len() < 2 {
+            return CommandResult::Failed("a polyline needs at least two points".to_string());
+        }
+        let polyline = Polyline::new(vertices, closed, ctx.properties);
+        let end = if closed {
+            polyline.vertices[0].position
+        } else {
+            polyline.vertices[polyline.vertices.len() - 1].position
+        };
+        w.write_resource::<LastSegment>().tangent = polyline.end_heading().map(|h| (end, h));
+        w.create_entity().with(Drawable::Polyline(polyline)).build();
+        return CommandResult::Done;
+    }
+    if vertices.len() > 1 {
+        let polyline = Polyline::new(vertices, false, ctx.properties);
+        w.create_entity()
+            .with(Drawable::Polyline(polyline))
+            .with(Preview)
+            .build();
+    }
+    match asking_width {
+        Some(true) => CommandResult::More(InputDesc::Distance, "starting width".to_string()),
+        Some(false) => CommandResult::More(InputDesc::Distance, "ending width".to_string()),
+        None if arcs => CommandResult::More(
+            InputDesc::Point,
+            "arc end point [l/w/c], enter to finish".to_string(),
+        ),
+        None => CommandResult::More(
+            InputDesc::Point,
+            "next point [a/w/c], enter to finish".to_string(),
+        ),
+    }
+}
+
+/// Applies the latest edit to the selected polyline, then asks for another.
+/// A selected spline is turned into a polyline following it once the user
+/// says to, and left alone otherwise.
+fn pedit_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let entity = match inputs.first() {
+        Some(CapturedInput::Select(entity, _, _)) => *entity,
+        _ => return CommandResult::Failed("expected a polyline".to_string()),
+    };
+    let mut drawables = w.write_storage::<Drawable>();
+    let converted = match drawables.get(entity) {
+        Some(Drawable::Spline(spline)) => Some(spline.to_polyline(ctx.view.tolerance())),
+        _ => None,
+    };
+    if let Some(polyline) = converted {
+        match inputs.get(1) {
+            None => {
+                return CommandResult::More(
+                    InputDesc::Keyword,
+                    "convert to polyline? [yes/no]".to_string(),
+                )
+            }
+            Some(CapturedInput::Keyword(answer)) if answer == "y" || answer == "yes" => {
+                drawables
+                    .insert(entity, Drawable::Polyline(polyline))
+                    .expect("edited a dead entity");
+            }
+            _ => return CommandResult::Done,
+        }
+    }
+    let polyline = match drawables.get_mut(entity) {
+        Some(Drawable::Polyline(polyline)) => polyline,
+        _ => return CommandResult::Failed("that isn't a polyline or spline".to_string()),
+    };
+    match inputs {
+        [.., CapturedInput::Confirm] => return CommandResult::Done,
+        [.., CapturedInput::Keyword(keyword), CapturedInput::Point(x, y)] => {
+            let p = to_point(*x, *y);
+            match keyword.as_str() {
+                "a" | "add" => {
+                    polyline.insert_vertex(p, ctx.view.tolerance());
+                }
+                "r" | "remove" if polyline.vertices.len() > 2 => {
+                    if let Some(index) = polyline.nearest_vertex(p) {
+                        polyline.vertices.remove(index);
+                    }
+                }
+                _ => (),
+            }
+        }
+        [.., CapturedInput::Keyword(keyword)] => match keyword.as_str() {
+            "a" | "add" => {
+                return CommandResult::More(InputDesc::Point, "where to add a vertex".to_string())
+            }
+            "r" | "remove" => {
+                return CommandResult::More(InputDesc::Point, "vertex to remove".to_string())
+            }
+            "c" | "close" => polyline.closed = true,
+            "o" | "open" => polyline.closed = false,
+            "s" | "spline" => {
+                // smoothed into a real spline, so there's no polyline left to edit
+                let control = polyline.vertices.iter().map(|v| v.position).collect();
+                let spline = if polyline.closed {
+                    Spline::closed_through_control(SPLINE_DEGREE, control, polyline.props)
+                } else {
+                    Spline::through_control(SPLINE_DEGREE, control, polyline.props)
+                };
+                if let Some(spline) = spline {
+                    drawables
+                        .insert(entity, Drawable::Spline(spline))
+                        .expect("edited a dead entity");
+                }
+                return CommandResult::Done;
+            }
+            _ => (),
+        },
+        _ => (),
+    }
+    CommandResult::More(
+        InputDesc::Keyword,
+        "close/open/add/remove/spline, enter to finish".to_string(),
+    )
+}
+
+fn create_catenary(w: &mut World, catenary: Option<Catenary>) -> CommandResult {
+    match catenary {
+        Some(catenary) => {
+            w.create_entity().with(Drawable::Catenary(catenary)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed(
+            "no cable hangs that way between side by side supports".to_string(),
+        ),
+    }
+}
+
+fn catenary_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Distance(l)] => {
+            let catenary = Catenary::with_length(
+                to_point(*ax, *ay),
+                to_point(*bx, *by),
+                *l as f32,
+                ctx.properties,
+            );
+            create_catenary(w, catenary)
+        }
+        _ => CommandResult::Failed("expected two supports and a length".to_string()),
+    }
+}
+
+fn catenary_sag_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Distance(s)] => {
+            let catenary = Catenary::with_sag(
+                to_point(*ax, *ay),
+                to_point(*bx, *by),
+                *s as f32,
+                ctx.properties,
+            );
+            create_catenary(w, catenary)
+        }
+        _ => CommandResult::Failed("expected two supports and a sag".to_string()),
+    }
+}
+
+fn create_parabola(w: &mut World, parabola: Option<Parabola>) -> CommandResult {
+    match parabola {
+        Some(parabola) => {
+            w.create_entity().with(Drawable::Parabola(parabola)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no parabola fits those inputs".to_string()),
+    }
+}
+
+fn parabola_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(vx, vy), CapturedInput::Point(fx, fy), CapturedInput::Point(ex, ey)] =>
+        {
+            let parabola = Parabola::vertex_focus(
+                to_point(*vx, *vy),
+                to_point(*fx, *fy),
+                to_point(*ex, *ey),
+                ctx.properties,
+            );
+            create_parabola(w, parabola)
+        }
+        _ => CommandResult::Failed("expected a vertex, a focus and an extent".to_string()),
+    }
+}
+
+fn parabola_3p_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Point(cx, cy)] =>
+        {
+            let parabola = Parabola::through_points(
+                to_point(*ax, *ay),
+                to_point(*bx, *by),
+                to_point(*cx, *cy),
+                ctx.properties,
+            );
+            create_parabola(w, parabola)
+        }
+        _ => CommandResult::Failed("expected three points".to_string()),
+    }
+}
+
+/// the points picked before the first enter, and whatever came after it
+fn split_at_confirm(inputs: &[CapturedInput]) -> (Vec<Point>, Option<&[CapturedInput]>) {
+    let confirmed = inputs
+        .iter()
+        .position(|input| matches!(input, CapturedInput::Confirm));
+    let picked = match confirmed {
+        Some(index) => &inputs[..index],
+        None => inputs,
+    };
+    let points = picked
+        .iter()
+        .filter_map(|input| match input {
+            CapturedInput::Point(x, y) => Some(to_point(*x, *y)),
+            _ => None,
+        })
+        .collect();
+    (points, confirmed.map(|index| &inputs[index + 1..]))
+}
+
+fn show_preview(w: &mut World, drawable: Option<Drawable>) {
+    if let Some(drawable) = drawable {
+        w.create_entity().with(drawable).with(Preview).build();
+    }
+}
+
+/// Draws a spline through fit points until enter, then asks for a tangent
+/// at each end.
+fn spline_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (fit, after) = split_at_confirm(inputs);
+    let tangent = |input: &CapturedInput, from: Point| match input {
+        CapturedInput::Point(x, y) => Some(to_point(*x, *y) - from),
+        _ => None,
+    };
+    match after {
+        None => {
+            let preview = Spline::through_fit(&fit, None, None, ctx.properties);
+            show_preview(w, preview.map(Drawable::Spline));
+            CommandResult::More(
+                InputDesc::Point,
+                "next fit point, enter to finish".to_string(),
+            )
+        }
+        Some(_) if fit.len() < 2 => {
+            CommandResult::Failed("a spline needs at least two fit points".to_string())
+        }
+        Some([]) => {
+            let preview = Spline::through_fit(&fit, None, None, ctx.properties);
+            show_preview(w, preview.map(Drawable::Spline));
+            CommandResult::More(
+                InputDesc::Point,
+                "start tangent, enter for none".to_string(),
+            )
+        }
+        Some([start]) => {
+            let start = tangent(start, fit[0]);
+            let preview = Spline::through_fit(&fit, start, None, ctx.properties);
+            show_preview(w, preview.map(Drawable::Spline));
+            CommandResult::More(InputDesc::Point, "end tangent, enter for none".to_string())
+        }
+        Some([start, end, ..]) => {
+            let start = tangent(start, fit[0]);
+            let end = tangent(end, fit[fit.len() - 1]);
+            match Spline::through_fit(&fit, start, end, ctx.properties) {
+                Some(spline) => {
+                    w.create_entity().with(Drawable::Spline(spline)).build();
+                    CommandResult::Done
+                }
+                None => CommandResult::Failed("no spline fits those points".to_string()),
+            }
+        }
+    }
+}
+
+/// Draws a spline pulled towards control points until enter, `d` asking
+/// for its degree and `c` closing it into a loop.
+fn spline_cv_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let mut degree = SPLINE_DEGREE;
+    let mut control = vec![];
+    let mut closed = false;
+    let mut finished = false;
+    for input in inputs {
+        match input {
+            CapturedInput::Point(x, y) => control.push(to_point(*x, *y)),
+            CapturedInput::Distance(d) => degree = d.round().max(1.0) as usize,
+            CapturedInput::Keyword(keyword) if keyword == "c" => {
+                closed = true;
+                finished = true;
+            }
+            CapturedInput::Confirm => finished = true,
+            _ => (),
+        }
+    }
+    let spline = if closed {
+        Spline::closed_through_control(degree, control, ctx.properties)
+    } else {
+        Spline::through_control(degree, control, ctx.properties)
+    };
+    if finished {
+        return match spline {
+            Some(spline) => {
+                w.create_entity().with(Drawable::Spline(spline)).build();
+                CommandResult::Done
+            }
+            None => CommandResult::Failed("not enough control points".to_string()),
+        };
+    }
+    show_preview(w, spline.map(Drawable::Spline));
+    match inputs.last() {
+        Some(CapturedInput::Keyword(keyword)) if keyword == "d" => {
+            CommandResult::More(InputDesc::Distance, "degree".to_string())
+        }
+        _ => CommandResult::More(
+            InputDesc::Point,
+            "next control point [d/c], enter to finish".to_string(),
+        ),
+    }
+}
+
+/// the justification chosen with the `j` option if it was, and the inputs after it
+fn split_justify(
+    inputs: &[CapturedInput],
+    default: Justify,
+) -> Option<(Justify, &[CapturedInput])> {
+    match inputs {
+        [CapturedInput::Keyword(keyword), rest @ ..] => {
+            Some((Justify::from_keyword(keyword)?, rest))
+        }
+        _ => Some((default, inputs)),
+    }
+}
+
+/// the text height fixed by the active style, or else the one collected
+/// next, and the inputs after it
+fn split_height<'a>(
+    ctx: &CommandContext,
+    inputs: &'a [CapturedInput],
+) -> Result<(f32, &'a [CapturedInput]), CommandResult> {
+    match (ctx.text_height, inputs) {
+        (Some(height), inputs) => Ok((height, inputs)),
+        (None, [CapturedInput::Distance(height), rest @ ..]) => Ok((*height as f32, rest)),
+        (None, []) => Err(CommandResult::More(
+            InputDesc::Distance,
+            "text height".to_string(),
+        )),
+        (None, _) => Err(CommandResult::Failed("expected a text height".to_string())),
+    }
+}
+
+/// Places each line of text typed under the one before, until enter is
+/// pressed on an empty line. The height is only asked for when the text
+/// style doesn't fix one.
+fn text_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let baseline = Justify::new(Horizontal::Left, Vertical::Baseline);
+    let (justify, inputs) = match split_justify(inputs, baseline) {
+        Some(found) => found,
+        None => return CommandResult::Failed("unknown justification".to_string()),
+    };
+    let (position, rest) = match inputs {
+        [CapturedInput::Point(x, y), rest @ ..] => (to_point(*x, *y), rest),
+        _ => return CommandResult::Failed("expected an insertion point".to_string()),
+    };
+    let (height, rest) = match split_height(ctx, rest) {
+        Ok(found) => found,
+        Err(result) => return result,
+    };
+    let (direction, typed) = match rest {
+        [CapturedInput::Point(x, y), typed @ ..] => (to_point(*x, *y), typed),
+        [] => return CommandResult::More(InputDesc::Point, "baseline direction".to_string()),
+        _ => return CommandResult::Failed("expected a baseline direction".to_string()),
+    };
+    if typed.is_empty() {
+        return CommandResult::More(InputDesc::Text, "text".to_string());
+    }
+    let rotation = (direction - position).angle();
+    let mut texts = vec![];
+    let mut at = position;
+    let mut finished = false;
+    for input in typed {
+        match input {
+            CapturedInput::Text(content) => {
+                let text = Text::new(
+                    content,
+                    at,
+                    height,
+                    rotation,
+                    justify,
+                    ctx.lettering,
+                    ctx.properties,
+                );
+                if let Some(text) = text {
+                    at = text.next_line();
+                    texts.push(text);
+                }
+            }
+            CapturedInput::Confirm => finished = true,
+            _ => (),
+        }
+    }
+    if finished {
+        if texts.is_empty() {
+            return CommandResult::Failed("no text fits those inputs".to_string());
+        }
+        for text in texts {
+            w.create_entity().with(Drawable::Text(text)).build();
+        }
+        return CommandResult::Done;
+    }
+    for text in texts {
+        show_preview(w, Some(Drawable::Text(text)));
+    }
+    CommandResult::More(InputDesc::Text, "next line, enter to finish".to_string())
+}
+
+/// Fills the box between two corners with paragraphs of text, one typed at
+/// a time until enter is pressed on an empty line, inserted at the corner
+/// or edge its justification names.
+fn mtext_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let top_left = Justify::new(Horizontal::Left, Vertical::Top);
+    let (justify, inputs) = match split_justify(inputs, top_left) {
+        Some(found) => found,
+        None => return CommandResult::Failed("unknown justification".to_string()),
+    };
+    let (a, b, rest) = match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), rest @ ..] => {
+            (to_point(*ax, *ay), to_point(*bx, *by), rest)
+        }
+        _ => return CommandResult::Failed("expected two corners".to_string()),
+    };
+    let (height, typed) = match split_height(ctx, rest) {
+        Ok(found) => found,
+        Err(result) => return result,
+    };
+    if typed.is_empty() {
+        return CommandResult::More(InputDesc::Text, "first paragraph".to_string());
+    }
+    let x = match justify.horizontal {
+        Horizontal::Left => a.x.min(b.x),
+        Horizontal::Center => (a.x + b.x) / 2.0,
+        Horizontal::Right => a.x.max(b.x),
+    };
+    let y = match justify.vertical {
+        Vertical::Top => a.y.max(b.y),
+        Vertical::Middle => (a.y + b.y) / 2.0,
+        Vertical::Bottom | Vertical::Baseline => a.y.min(b.y),
+    };
+    let paragraphs: Vec<&str> = typed
+        .iter()
+        .filter_map(|input| match input {
+            CapturedInput::Text(content) => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    let finished = matches!(typed.last(), Some(CapturedInput::Confirm));
+    let mtext = MText::new(
+        &paragraphs.join("\n"),
+        Point::new(x, y),
+        (b.x - a.x).abs(),
+        height,
+        justify,
+        ctx.lettering,
+        ctx.properties,
+    );
+    if finished {
+        return match mtext {
+            Some(mtext) => {
+                w.create_entity().with(Drawable::MText(mtext)).build();
+                CommandResult::Done
+            }
+            None => CommandResult::Failed("no mtext fits those inputs".to_string()),
+        };
+    }
+    show_preview(w, mtext.map(Drawable::MText));
+    CommandResult::More(
+        InputDesc::Text,
+        "next paragraph, enter to finish".to_string(),
+    )
+}
+
+/// Creates the named text style, or edits it if it already exists, then
+/// makes it current. Text already in the style is updated to match.
+fn style_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (name, edits) = match inputs {
+        [CapturedInput::Text(name), edits @ ..] if !name.trim().is_empty() => (name.trim(), edits),
+        _ => return CommandResult::Failed("expected a style name".to_string()),
+    };
+    let existing = w
+        .read_resource::<TextStyles>()
+        .iter()
+        .find(|(_, style)| style.name.eq_ignore_ascii_case(name))
+        .map(|(id, style)| (id, style.clone()));
+    let mut style = match &existing {
+        Some((_, style)) => style.clone(),
+        None => TextStyle::new(name),
+    };
+    // the setting whose value is being typed in
+    let mut asking = None;
+    let mut finished = false;
+    for input in edits {
+        match (asking.take(), input) {
+            (Some("f"), CapturedInput::Text(font)) => style.font = font.trim().to_string(),
+            (Some("h"), CapturedInput::Distance(height)) => style.height = height.max(0.0) as f32,
+            (Some("w"), CapturedInput::Distance(width)) if *width > 0.0 => {
+                style.width_factor = *width as f32
+            }
+            (Some("o"), CapturedInput::Distance(angle)) => {
+                style.oblique = angle.clamp(-MAX_OBLIQUE, MAX_OBLIQUE).to_radians() as f32
+            }
+            (None, CapturedInput::Keyword(keyword)) => match keyword.as_str() {
+                "f" | "h" | "w" | "o" => asking = Some(keyword.as_str()),
+                "b" => style.backwards = !style.backwards,
+                "u" => style.upside_down = !style.upside_down,
+                _ => (),
+            },
+            (_, CapturedInput::Confirm) => finished = true,
+            _ => (),
+        }
+    }
+    if finished {
+        let id = match existing {
+            Some((id, _)) => {
+                if let Some(old) = w.write_resource::<TextStyles>().get_mut(id) {
+                    *old = style.clone();
+                }
+                change_drawables(
+                    w,
+                    |drawable| drawable.uses_text_style(id),
+                    |drawable| drawable.restyle(id, &style),
+                );
+                id
+            }
+            None => w.write_resource::<TextStyles>().push(style),
+        };
+        w.write_resource::<ActiveTextStyle>().id = id;
+        return CommandResult::Done;
+    }
+    match asking {
+        Some("f") => CommandResult::More(InputDesc::Text, "font file".to_string()),
+        Some("h") => CommandResult::More(
+            InputDesc::Distance,
+            "fixed height, 0 to ask for each text".to_string(),
+        ),
+        Some("w") => CommandResult::More(InputDesc::Distance, "width factor".to_string()),
+        Some(_) => CommandResult::More(InputDesc::Distance, "oblique angle in degrees".to_string()),
+        None => {
+            let flag = |on: bool, name: &str| {
+                if on {
+                    format!(", {}", name)
+                } else {
+                    String::new()
+                }
+            };
+            let summary = format!(
+                "{}: font {}, height {}, width {}, oblique {}{}{}",
+                style.name,
+                style.font,
+                style.height,
+                style.width_factor,
+                style.oblique.to_degrees(),
+                flag(style.backwards, "backwards"),
+                flag(style.upside_down, "upside down"),
+            );
+            CommandResult::More(
+                InputDesc::Keyword,
+                format!("{} [f/h/w/o/b/u], enter to finish", summary),
+            )
+        }
+    }
+}
+
+/// adds the dimension, following whatever `links` lead to
+fn create_dimension(
+    w: &mut World,
+    dimension: Option<Dimension>,
+    links: Vec<Link>,
+) -> CommandResult {
+    match dimension {
+        Some(dimension) => {
+            let builder = w.create_entity().with(Drawable::Dimension(dimension));
+            if links.is_empty() {
+                builder.build();
+            } else {
+                builder.with(Dependent::new(links)).build();
+            }
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no dimension fits those inputs".to_string()),
+    }
+}
+
+/// the two points measured between and where the dimension line goes
+fn dim_points(inputs: &[CapturedInput]) -> Option<(Point, Point, Point)> {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), CapturedInput::Point(lx, ly)] => {
+            Some((to_point(*ax, *ay), to_point(*bx, *by), to_point(*lx, *ly)))
+        }
+        _ => None,
+    }
+}
+
+/// the two points measured between and where the dimension line goes,
+/// either picked or the ends of a picked object which the dimension then follows
+fn dim_sources(w: &World, inputs: &[CapturedInput]) -> Option<(Point, Point, Point, Vec<Link>)> {
+    match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(lx, ly)] => {
+            let drawables = w.read_storage::<Drawable>();
+            let drawable = drawables.get(*entity)?;
+            match (
+                drawable.feature(Feature::Start)?,
+                drawable.feature(Feature::End)?,
+            ) {
+                (Anchor::Point(first), Anchor::Point(second)) => {
+                    let links = vec![
+                        Link::new(*entity, Feature::Start),
+                        Link::new(*entity, Feature::End),
+                    ];
+                    Some((first, second, to_point(*lx, *ly), links))
+                }
+                _ => None,
+            }
+        }
+        _ => {
+            let (first, second, location) = dim_points(inputs)?;
+            Some((first, second, location, vec![]))
+        }
+    }
+}
+
+/// Dimensions the distance between two points along `rotation`, or when
+/// it isn't given horizontally unless the dimension line is placed further
+/// off to the side of the points than above or below them.
+fn create_linear(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+    rotation: Option<f32>,
+) -> CommandResult {
+    let (first, second, location, links) = match dim_sources(w, inputs) {
+        Some(sources) => sources,
+        None => return CommandResult::Failed(DIM_SOURCES_FAILED.to_string()),
+    };
+    let rotation = rotation.unwrap_or_else(|| {
+        let outside = |v: f32, a: f32, b: f32| (a.min(b) - v).max(v - a.max(b)).max(0.0);
+        let beside = outside(location.x, first.x, second.x);
+        let above = outside(location.y, first.y, second.y);
+        if beside > above {
+            FRAC_PI_2
+        } else {
+            0.0
+        }
+    });
+    let measure = Measure::Linear {
+        first,
+        second,
+        rotation,
+    };
+    let dimension = Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+    create_dimension(w, dimension, links)
+}
+
+fn dimlinear_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    create_linear(w, ctx, inputs, None)
+}
+
+fn dimlinear_h_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    create_linear(w, ctx, inputs, Some(0.0))
+}
+
+fn dimlinear_v_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    create_linear(w, ctx, inputs, Some(FRAC_PI_2))
+}
+
+fn dimlinear_r_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Distance(angle), points @ ..] => {
+            create_linear(w, ctx, points, Some((*angle as f32).to_radians()))
+        }
+        _ => CommandResult::Failed("expected an angle and three points".to_string()),
+    }
+}
+
+fn dimaligned_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (first, second, location, links) = match dim_sources(w, inputs) {
+        Some(sources) => sources,
+        None => return CommandResult::Failed(DIM_SOURCES_FAILED.to_string()),
+    };
+    let measure = Measure::Aligned { first, second };
+    let dimension = Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+    create_dimension(w, dimension, links)
+}
+
+/// Dimensions the angle between two lines, around the point they'd meet,
+/// on the side of each line it was picked on.
+fn dimangular_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(a, ax, ay), CapturedInput::Select(b, bx, by), CapturedInput::Point(lx, ly)] =>
+        {
+            let (first, second) = {
+                let drawables = w.read_storage::<Drawable>();
+                (
+                    drawables.get(*a).and_then(Primitive::of),
+                    drawables.get(*b).and_then(Primitive::of),
+                )
+            };
+            match (first, second) {
+                (Some(first @ Primitive::Line { .. }), Some(second @ Primitive::Line { .. })) => {
+                    let vertex = match first.intersect(&second).first() {
+                        Some(vertex) => *vertex,
+                        None => return CommandResult::Failed("the lines are parallel".to_string()),
+                    };
+                    let toward = |line: Primitive, pick: Point| match line {
+                        Primitive::Line { direction, .. } => {
+                            vertex + direction * (pick - vertex).dot(direction)
+                        }
+                        Primitive::Circle { .. } => pick,
+                    };
+                    let measure = Measure::Angular {
+                        vertex,
+                        first: toward(first, to_point(*ax, *ay)),
+                        second: toward(second, to_point(*bx, *by)),
+                    };
+                    let location = to_point(*lx, *ly);
+                    let dimension =
+                        Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+                    let links = vec![Link::new(*a, Feature::Whole), Link::new(*b, Feature::Whole)];
+                    create_dimension(w, dimension, links)
+                }
+                _ => CommandResult::Failed("can only measure between two lines".to_string()),
+            }
+        }
+        _ => CommandResult::Failed("expected two lines and a point".to_string()),
+    }
+}
+
+fn dimangular_3p_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(vx, vy), points @ ..] => {
+            let (first, second, location) = match dim_points(points) {
+                Some(points) => points,
+                None => return CommandResult::Failed("expected four points".to_string()),
+            };
+            let measure = Measure::Angular {
+                vertex: to_point(*vx, *vy),
+                first,
+                second,
+            };
+            let dimension =
+                Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+            create_dimension(w, dimension, vec![])
+        }
+        _ => CommandResult::Failed("expected four points".to_string()),
+    }
+}
+
+/// the center and radius of a selected arc or circle
+fn round_selection(w: &World, entity: Entity) -> Option<(Point, f32)> {
+    match w
+        .read_storage::<Drawable>()
+        .get(entity)
+        .and_then(Primitive::of)
+    {
+        Some(Primitive::Circle { center, radius }) => Some((center, radius)),
+        _ => None,
+    }
+}
+
+fn dimradius_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(lx, ly)] => {
+            match round_selection(w, *entity) {
+                Some((center, radius)) => {
+                    let measure = Measure::Radius { center, radius };
+                    let location = to_point(*lx, *ly);
+                    let dimension =
+                        Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+                    create_dimension(w, dimension, vec![Link::new(*entity, Feature::Whole)])
+                }
+                None => CommandResult::Failed("can only measure arcs and circles".to_string()),
+            }
+        }
+        _ => CommandResult::Failed("expected an arc or circle and a point".to_string()),
+    }
+}
+
+fn dimdiameter_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(lx, ly)] => {
+            match round_selection(w, *entity) {
+                Some((center, radius)) => {
+                    let measure = Measure::Diameter { center, radius };
+                    let location = to_point(*lx, *ly);
+                    let dimension =
+                        Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+                    create_dimension(w, dimension, vec![Link::new(*entity, Feature::Whole)])
+                }
+                None => CommandResult::Failed("can only measure arcs and circles".to_string()),
+            }
+        }
+        _ => CommandResult::Failed("expected an arc or circle and a point".to_string()),
+    }
+}
+
+fn dimarc_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(lx, ly)] => {
+            let arc = match w.read_storage::<Drawable>().get(*entity) {
+                Some(Drawable::Arc(arc)) => *arc,
+                _ => return CommandResult::Failed("can only measure along arcs".to_string()),
+            };
+            let measure = Measure::ArcLength {
+                center: arc.center,
+                radius: arc.radius,
+                start_angle: arc.start_angle,
+                end_angle: arc.end_angle,
+            };
+            let location = to_point(*lx, *ly);
+            let dimension =
+                Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+            create_dimension(w, dimension, vec![Link::new(*entity, Feature::Whole)])
+        }
+        _ => CommandResult::Failed("expected an arc and a point".to_string()),
+    }
+}
+
+/// Dimensions how far a feature is from the origin, across when the leader
+/// is drawn up or down from it and up when the leader is drawn to the side.
+/// A picked object has its center dimensioned, which the dimension follows.
+fn dimordinate_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (feature, location, links) = match inputs {
+        [CapturedInput::Point(fx, fy), CapturedInput::Point(lx, ly)] => {
+            (to_point(*fx, *fy), to_point(*lx, *ly), vec![])
+        }
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(lx, ly)] => {
+            let center = w
+                .read_storage::<Drawable>()
+                .get(*entity)
+                .and_then(|drawable| drawable.feature(Feature::Center));
+            match center {
+                Some(Anchor::Point(center)) => (
+                    center,
+                    to_point(*lx, *ly),
+                    vec![Link::new(*entity, Feature::Center)],
+                ),
+                _ => return CommandResult::Failed("that object has no center".to_string()),
+            }
+        }
+        _ => return CommandResult::Failed("expected two points".to_string()),
+    };
+    let leader = location - feature;
+    let measure = Measure::Ordinate {
+        origin: Point::new(0.0, 0.0),
+        feature,
+        along_x: leader.y.abs() >= leader.x.abs(),
+    };
+    let dimension = Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+    create_dimension(w, dimension, links)
+}
+
+fn dimjogged_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(ox, oy), CapturedInput::Point(lx, ly), CapturedInput::Point(jx, jy)] => {
+            match round_selection(w, *entity) {
+                Some((center, radius)) => {
+                    let measure = Measure::JoggedRadius {
+                        center,
+                        radius,
+                        origin: to_point(*ox, *oy),
+                        jog: to_point(*jx, *jy),
+                    };
+                    let location = to_point(*lx, *ly);
+                    let dimension =
+                        Dimension::new(measure, location, ctx.dim_look, ctx.units, ctx.properties);
+                    create_dimension(w, dimension, vec![Link::new(*entity, Feature::Whole)])
+                }
+                None => CommandResult::Failed("can only measure arcs and circles".to_string()),
+            }
+        }
+        _ => CommandResult::Failed("expected an arc or circle and three points".to_string()),
+    }
+}
+
+/// Creates the named dimension style, or edits it if it already exists,
+/// then makes it current. Dimensions already in the style are updated to match.
+fn dimstyle_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (name, edits) = match inputs {
+        [CapturedInput::Text(name), edits @ ..] if !name.trim().is_empty() => (name.trim(), edits),
+        _ => return CommandResult::Failed("expected a style name".to_string()),
+    };
+    let existing = w
+        .read_resource::<DimStyles>()
+        .iter()
+        .find(|(_, style)| style.name.eq_ignore_ascii_case(name))
+        .map(|(id, style)| (id, style.clone()));
+    let mut style = match &existing {
+        Some((_, style)) => style.clone(),
+        None => DimStyle::new(name, w.read_resource::<ActiveTextStyle>().id),
+    };
+    // the setting whose value is being typed in
+    let mut asking = None;
+    let mut finished = false;
+    for input in edits {
+        match (asking.take(), input) {
+            (Some("s"), CapturedInput::Text(text_style)) => {
+                let found = w
+                    .read_resource::<TextStyles>()
+                    .iter()
+                    .find(|(_, found)| found.name.eq_ignore_ascii_case(text_style.trim()))
+                    .map(|(id, _)| id);
+                match found {
+                    Some(id) => style.text_style = id,
+                    None => {
+                        return CommandResult::Failed(format!(
+                            "no text style named {}",
+                            text_style.trim()
+                        ))
+                    }
+                }
+            }
+            (Some(setting), CapturedInput::Distance(value)) if *value >= 0.0 => {
+                let value = *value as f32;
+                match setting {
+                    "a" => style.arrow_size = value,
+                    "t" => style.text_height = value,
+                    "g" => style.text_gap = value,
+                    "o" => style.extension_offset = value,
+                    _ => style.extension_beyond = value,
+                }
+            }
+            (None, CapturedInput::Keyword(keyword)) => match keyword.as_str() {
+                "a" | "t" | "g" | "o" | "e" | "s" => asking = Some(keyword.as_str()),
+                _ => (),
+            },
+            (_, CapturedInput::Confirm) => finished = true,
+            _ => (),
+        }
+    }
+    if finished {
+        let id = match existing {
+            Some((id, _)) => {
+                if let Some(old) = w.write_resource::<DimStyles>().get_mut(id) {
+                    *old = style.clone();
+                }
+                id
+            }
+            None => w.write_resource::<DimStyles>().push(style.clone()),
+        };
+        w.write_resource::<ActiveDimStyle>().id = id;
+        let look = DimLook::from_style(id, &style, lettering_in(w, style.text_style));
+        change_drawables(
+            w,
+            |drawable| drawable.uses_dim_style(id),
+            |drawable| drawable.restyle_dimension(id, &look),
+        );
+        return CommandResult::Done;
+    }
+    match asking {
+        Some("a") => CommandResult::More(InputDesc::Distance, "arrow size".to_string()),
+        Some("t") => CommandResult::More(InputDesc::Distance, "text height".to_string()),
+        Some("g") => CommandResult::More(
+            InputDesc::Distance,
+            "gap between the dimension line and its text".to_string(),
+        ),
+        Some("o") => CommandResult::More(
+            InputDesc::Distance,
+            "gap between extension lines and what they measure".to_string(),
+        ),
+        Some("e") => CommandResult::More(
+            InputDesc::Distance,
+            "extension past the dimension line".to_string(),
+        ),
+        Some(_) => CommandResult::More(InputDesc::Text, "text style".to_string()),
+        None => {
+            let text_style = w
+                .read_resource::<TextStyles>()
+                .get(style.text_style)
+                .map(|text_style| text_style.name.clone())
+                .unwrap_or_default();
+            let summary = format!(
+                "{}: arrows {}, text {}, gap {}, offset {}, extension {}, style {}",
+                style.name,
+                style.arrow_size,
+                style.text_height,
+                style.text_gap,
+                style.extension_offset,
+                style.extension_beyond,
+                text_style,
+            );
+            CommandResult::More(
+                InputDesc::Keyword,
+                format!("{} [a/t/g/o/e/s], enter to finish", summary),
+            )
+        }
+    }
+}
+
+/// Draws a leader from its arrowhead through each point picked until enter,
+/// then notes it with paragraphs typed until enter on an empty line.
+fn leader_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    create_leader(w, ctx, inputs, false)
+}
+
+/// Draws a leader like `leader` does, then points more arrowheads picked
+/// until enter at the same note.
+fn mleader_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    create_leader(w, ctx, inputs, true)
+}
+
+fn create_leader(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+    shared: bool,
+) -> CommandResult {
+    let (points, after) = split_at_confirm(inputs);
+    // the last point picked is where the arms meet, the rest the first arm
+    let leader_with = |content: &str, tips: &[Point]| {
+        let (anchor, arm) = points.split_last()?;
+        let mut arms = vec![arm.to_vec()];
+        arms.extend(tips.iter().map(|tip| vec![*tip]));
+        Leader::new(arms, *anchor, content, ctx.leader_look, ctx.properties)
+    };
+    let typed = match after {
+        Some(typed) => typed,
+        None => {
+            show_preview(w, leader_with("", &[]).map(Drawable::Leader));
+            let prompt = if points.len() < 2 {
+                "next point"
+            } else {
+                "next point, enter to finish"
+            };
+            return CommandResult::More(InputDesc::Point, prompt.to_string());
+        }
+    };
+    let noted = typed
+        .iter()
+        .position(|input| matches!(input, CapturedInput::Confirm));
+    let paragraphs: Vec<&str> = typed[..noted.unwrap_or(typed.len())]
+        .iter()
+        .filter_map(|input| match input {
+            CapturedInput::Text(content) => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    let content = paragraphs.join("\n");
+    let tips = match noted {
+        None => {
+            show_preview(w, leader_with(&content, &[]).map(Drawable::Leader));
+            let prompt = if paragraphs.is_empty() {
+                "note, enter for none"
+            } else {
+                "next line, enter to finish"
+            };
+            return CommandResult::More(InputDesc::Text, prompt.to_string());
+        }
+        Some(index) if shared => match split_at_confirm(&typed[index + 1..]) {
+            (tips, Some(_)) => tips,
+            (tips, None) => {
+                show_preview(w, leader_with(&content, &tips).map(Drawable::Leader));
+                return CommandResult::More(
+                    InputDesc::Point,
+                    "next arrowhead, enter to finish".to_string(),
+                );
+            }
+        },
+        Some(_) => vec![],
+    };
+    match leader_with(&content, &tips) {
+        Some(leader) => {
+            // arrowheads put on a point of another drawable stay on it
+            let (attached, links): (Vec<usize>, Vec<Link>) = leader
+                .arms
+                .iter()
+                .enumerate()
+                .filter_map(|(i, arm)| Some((i, link_at(w, arm[0], ctx.view.tolerance())?)))
+                .unzip();
+            let leader = leader.with_attached(attached);
+            let builder = w.create_entity().with(Drawable::Leader(leader));
+            if links.is_empty() {
+                builder.build();
+            } else {
+                builder.with(Dependent::new(links)).build();
+            }
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("no leader fits those inputs".to_string()),
+    }
+}
+
+/// Creates the named leader style, or edits it if it already exists, then
+/// makes it current. Leaders already in the style are updated to match.
+fn mleaderstyle_command(
+    w: &mut World,
+    _: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (name, edits) = match inputs {
+        [CapturedInput::Text(name), edits @ ..] if !name.trim().is_empty() => (name.trim(), edits),
+        _ => return CommandResult::Failed("expected a style name".to_string()),
+    };
+    let existing = w
+        .read_resource::<LeaderStyles>()
+        .iter()
+        .find(|(_, style)| style.name.eq_ignore_ascii_case(name))
+        .map(|(id, style)| (id, style.clone()));
+    let mut style = match &existing {
+        Some((_, style)) => style.clone(),
+        None => LeaderStyle::new(name, w.read_resource::<ActiveTextStyle>().id),
+    };
+    // the setting whose value is being typed in
+    let mut asking = None;
+    let mut finished = false;
+    for input in edits {
+        match (asking.take(), input) {
+            (Some("h"), CapturedInput::Keyword(keyword)) => {
+                match Arrowhead::from_keyword(keyword) {
+                    Some(arrowhead) => style.arrowhead = arrowhead,
+                    None => return CommandResult::Failed(format!("unknown arrowhead {}", keyword)),
+                }
+            }
+            (Some("s"), CapturedInput::Text(text_style)) => {
+                let found = w
+                    .read_resource::<TextStyles>()
+                    .iter()
+                    .find(|(_, found)| found.name.eq_ignore_ascii_case(text_style.trim()))
+                    .map(|(id, _)| id);
+                match found {
+                    Some(id) => style.text_style = id,
+                    None => {
+                        return CommandResult::Failed(format!(
+                            "no text style named {}",
+                            text_style.trim()
+                        ))
+                    }
+                }
+            }
+            (Some(setting), CapturedInput::Distance(value)) if *value >= 0.0 => {
+                let value = *value as f32;
+                match setting {
+                    "a" => style.arrow_size = value,
+                    "l" => style.landing = value,
+                    "t" => style.text_height = value,
+                    _ => style.text_gap = value,
+                }
+            }
+            (None, CapturedInput::Keyword(keyword)) => match keyword.as_str() {
+                "h" | "a" | "l" | "t" | "g" | "s" => asking = Some(keyword.as_str()),
+                _ => (),
+            },
+            (_, CapturedInput::Confirm) => finished = true,
+            _ => (),
+        }
+    }
+    if finished {
+        let id = match existing {
+            Some((id, _)) => {
+                if let Some(old) = w.write_resource::<LeaderStyles>().get_mut(id) {
+                    *old = style.clone();
+                }
+                id
+            }
+            None => w.write_resource::<LeaderStyles>().push(style.clone()),
+        };
+        w.write_resource::<ActiveLeaderStyle>().id = id;
+        let look = LeaderLook::from_style(id, &style, lettering_in(w, style.text_style));
+        change_drawables(
+            w,
+            |drawable| drawable.uses_leader_style(id),
+            |drawable| drawable.restyle_leader(id, &look),
+        );
+        return CommandResult::Done;
+    }
+    match asking {
+        Some("h") => CommandResult::More(
+            InputDesc::Keyword,
+            "arrowhead closed filled, open, dot or tick [c/o/d/t]".to_string(),
+        ),
+        Some("a") => CommandResult::More(InputDesc::Distance, "arrow size".to_string()),
+        Some("l") => CommandResult::More(InputDesc::Distance, "landing length".to_string()),
+        Some("t") => CommandResult::More(InputDesc::Distance, "text height".to_string()),
+        Some("g") => CommandResult::More(
+            InputDesc::Distance,
+            "gap between the landing and its note".to_string(),
+        ),
+        Some(_) => CommandResult::More(InputDesc::Text, "text style".to_string()),
+        None => {
+            let text_style = w
+                .read_resource::<TextStyles>()
+                .get(style.text_style)
+                .map(|text_style| text_style.name.clone())
+                .unwrap_or_default();
+            let summary = format!(
+                "{}: {} arrows {}, landing {}, text {}, gap {}, style {}",
+                style.name,
+                style.arrowhead.name(),
+                style.arrow_size,
+                style.landing,
+                style.text_height,
+                style.text_gap,
+                text_style,
+            );
+            CommandResult::More(
+                InputDesc::Keyword,
+                format!("{} [h/a/l/t/g/s], enter to finish", summary),
+            )
+        }
+    }
+}
+
+/// Fills the area inside the closed objects selected, leaving islands where
+/// one is inside another. The hatch follows its boundaries as they change.
+fn hatch_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (entities, name, rest) = match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Text(name), rest @ ..] => {
+            (entities, name.trim(), rest)
+        }
+        _ => return CommandResult::Failed("expected a selection and a pattern".to_string()),
+    };
+    let (fill, scale, angle) = match hatch_fill(w, name, rest) {
+        Ok(fill) => fill,
+        Err(result) => return result,
+    };
+    let (loops, links): (Vec<Polyline>, Vec<Link>) = {
+        let drawables = w.read_storage::<Drawable>();
+        entities
+            .iter()
+            .filter_map(|entity| {
+                let boundary = drawables.get(*entity)?.boundary_loop()?;
+                Some((boundary, Link::new(*entity, Feature::Whole)))
+            })
+            .unzip()
+    };
+    if loops.is_empty() {
+        return CommandResult::Failed("no closed boundaries in the selection".to_string());
+    }
+    let hatch = Hatch::new(loops, fill, scale, angle, ctx.properties);
+    create_hatch(w, hatch, links)
+}
+
+/// Fills the area around a picked point, found the way `boundary` finds it.
+/// There's no one object for the hatch to follow, so it stays as it's made.
+fn hatch_pick_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (pick, name, rest) = match inputs {
+        [CapturedInput::Point(x, y), CapturedInput::Text(name), rest @ ..] => {
+            (to_point(*x, *y), name.trim(), rest)
+        }
+        _ => return CommandResult::Failed("expected a point and a pattern".to_string()),
+    };
+    let (fill, scale, angle) = match hatch_fill(w, name, rest) {
+        Ok(fill) => fill,
+        Err(result) => return result,
+    };
+    match boundary_around(w, ctx, pick) {
+        Some(loops) => {
+            let hatch = Hatch::new(loops, fill, scale, angle, ctx.properties);
+            create_hatch(w, hatch, vec![])
+        }
+        None => CommandResult::Failed(NO_BOUNDARY.to_string()),
+    }
+}
+
+/// the fill, scale and angle a hatch is given by the pattern `name` and the
+/// inputs asked for after it, or what to ask for next
+fn hatch_fill(
+    w: &World,
+    name: &str,
+    rest: &[CapturedInput],
+) -> Result<(Fill, f32, f32), CommandResult> {
+    if name.eq_ignore_ascii_case("solid") {
+        return Ok((Fill::Solid, 1.0, 0.0));
+    }
+    if name.eq_ignore_ascii_case("u") {
+        return match rest {
+            [] => Err(CommandResult::More(
+                InputDesc::Distance,
+                "line spacing".to_string(),
+            )),
+            [CapturedInput::Distance(_)] => Err(CommandResult::More(
+                InputDesc::Distance,
+                "angle in degrees".to_string(),
+            )),
+            [CapturedInput::Distance(_), CapturedInput::Distance(_)] => Err(CommandResult::More(
+                InputDesc::Keyword,
+                "cross hatch [y/n]".to_string(),
+            )),
+            [CapturedInput::Distance(spacing), CapturedInput::Distance(angle), CapturedInput::Keyword(double)] =>
+            {
+                let double = match double.as_str() {
+                    "y" => true,
+                    "n" => false,
+                    _ => return Err(CommandResult::Failed("expected y or n".to_string())),
+                };
+                let pattern = Pattern::user_defined(*spacing as f32, double);
+                Ok((Fill::Pattern(pattern), 1.0, angle.to_radians() as f32))
+            }
+            _ => Err(CommandResult::Failed(
+                "expected a spacing and an angle".to_string(),
+            )),
+        };
+    }
+    let pattern = match w.read_resource::<HatchPatterns>().find(name) {
+        Some(pattern) => pattern.clone(),
+        None => {
+            return Err(CommandResult::Failed(format!(
+                "no hatch pattern named {}",
+                name
+            )))
+        }
+    };
+    match rest {
+        [] => Err(CommandResult::More(
+            InputDesc::Distance,
+            "scale".to_string(),
+        )),
+        [CapturedInput::Distance(_)] => Err(CommandResult::More(
+            InputDesc::Distance,
+            "angle in degrees".to_string(),
+        )),
+        [CapturedInput::Distance(scale), CapturedInput::Distance(angle)] => Ok((
+            Fill::Pattern(pattern),
+            *scale as f32,
+            angle.to_radians() as f32,
+        )),
+        _ => Err(CommandResult::Failed(
+            "expected a scale and an angle".to_string(),
+        )),
+    }
+}
+
+fn create_hatch(w: &mut World, hatch: Option<Hatch>, links: Vec<Link>) -> CommandResult {
+    match hatch {
+        Some(hatch) => {
+            let builder = w.create_entity().with(Drawable::Hatch(hatch));
+            if links.is_empty() {
+                builder.build();
+            } else {
+                builder.with(Dependent::new(links)).build();
+            }
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("the scale must be more than zero".to_string()),
+    }
+}
+
+/// a link to the end, middle or center of a drawable lying at `p`, for
+/// something put there to follow
+fn link_at(w: &World, p: Point, tolerance: f32) -> Option<Link> {
+    let entities = w.entities();
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    let features = [Feature::Start, Feature::End, Feature::Center];
+    for (entity, drawable, _) in (&entities, &drawables, !&previews).join() {
+        for feature in features.iter() {
+            if let Some(Anchor::Point(at)) = drawable.feature(*feature) {
+                if at.distance(p) <= tolerance {
+                    return Some(Link::new(entity, *feature));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// the closed loop around `pick` made by the visible drawables, followed by
+/// the islands inside it
+fn boundary_around(w: &World, ctx: &CommandContext, pick: Point) -> Option<Vec<Polyline>> {
+    let layers = w.read_resource::<Layers>();
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    let visible = (&drawables, !&previews)
+        .join()
+        .map(|(drawable, _)| drawable)
+        .filter(|drawable| {
+            drawable
+                .properties()
+                .and_then(|props| layers.get(props.layer))
+                .map(|layer| !(layer.hidden || layer.frozen))
+                .unwrap_or(true)
+        });
+    find_boundary(visible, pick, ctx.view.tolerance(), ctx.properties)
+}
+
+/// Draws closed polylines around the area a picked point is in, one around
+/// the outside and one around each island inside it.
+fn boundary_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(x, y)] => match boundary_around(w, ctx, to_point(*x, *y)) {
+            Some(loops) => {
+                for boundary in loops {
+                    w.create_entity().with(Drawable::Polyline(boundary)).build();
+                }
+                CommandResult::Done
+            }
+            None => CommandResult::Failed(NO_BOUNDARY.to_string()),
+        },
+        _ => CommandResult::Failed("expected a point".to_string()),
+    }
+}
+
+/// Defines a block from the objects selected, or redefines the block of that
+/// name along with every insert of it. The objects are swapped for an insert
+/// of the block where they were.
+fn block_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (name, base, entities) = match inputs {
+        [CapturedInput::Text(name), CapturedInput::Point(x, y), CapturedInput::Multiselect(entities)]
+            if !name.trim().is_empty() =>
+        {
+            (name.trim(), to_point(*x, *y), entities)
+        }
+        _ => {
+            return CommandResult::Failed(
+                "expected a name, a base point and a selection".to_string(),
+            )
+        }
+    };
+    let members: Vec<Drawable> = {
+        let drawables = w.read_storage::<Drawable>();
+        entities
+            .iter()
+            .filter_map(|e| drawables.get(*e))
+            .cloned()
+            .collect()
+    };
+    if members.len() != entities.len() {
+        return CommandResult::Failed("selection contained erased objects".to_string());
+    }
+    if members.is_empty() {
+        return CommandResult::Failed("nothing selected to make the block from".to_string());
+    }
+    let existing = block_named(w, name).map(|(id, _)| id);
+    if let Some(id) = existing {
+        if draws_block(&w.read_resource::<Blocks>(), &members, id) {
+            return CommandResult::Failed(format!("{} can't be made from itself", name));
+        }
+    }
+    // the selection was all there a moment ago, so nothing is left half done
+    if w.delete_entities(entities).is_err() {
+        return CommandResult::Failed("selection contained erased objects".to_string());
+    }
+    let block = Group::new(name, base, members);
+    let id = match existing {
+        Some(id) => {
+            if let Some(old) = w.write_resource::<Blocks>().get_mut(id) {
+                *old = block.clone();
+            }
+            change_drawables(
+                w,
+                |drawable| drawable.uses_block(id),
+                |drawable| drawable.redefine(id, &block),
+            );
+            id
+        }
+        None => w.write_resource::<Blocks>().push(block.clone()),
+    };
+    let scale = Point::new(1.0, 1.0);
+    let insert = Insert::new(id, block, base, scale, 0.0, ctx.properties);
+    create_insert(w, insert)
+}
+
+/// whether any of `members` draws the block `id`, however deep inside other
+/// blocks, going by how each block is defined now
+fn draws_block(blocks: &Blocks, members: &[Drawable], id: GenerationID<Group>) -> bool {
+    let mut looking: Vec<&Drawable> = members.iter().collect();
+    let mut seen = vec![];
+    while let Some(drawable) = looking.pop() {
+        match drawable {
+            Drawable::Insert(insert) => {
+                if insert.block == id {
+                    return true;
+                }
+                if !seen.contains(&insert.block) {
+                    seen.push(insert.block);
+                    if let Some(block) = blocks.get(insert.block) {
+                        looking.extend(block.members());
+                    }
+                }
+            }
+            Drawable::NamedGroup(group) => looking.extend(group.members()),
+            _ => (),
+        }
+    }
+    false
+}
+
+fn insert_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Text(name), CapturedInput::Point(x, y), CapturedInput::Distance(scale), CapturedInput::Distance(rotation), answers @ ..] =>
+        {
+            let (id, block) = match block_named(w, name.trim()) {
+                Some(block) => block,
+                None => return CommandResult::Failed(format!("no block named {}", name.trim())),
+            };
+            let scale = Point::new(*scale as f32, *scale as f32);
+            let position = to_point(*x, *y);
+            let rotation = rotation.to_radians() as f32;
+            match Insert::new(id, block, position, scale, rotation, ctx.properties) {
+                Some(insert) => match fill_fields(insert, answers) {
+                    Ok(insert) => create_insert(w, Some(insert)),
+                    Err(result) => result,
+                },
+                None => create_insert(w, None),
+            }
+        }
+        _ => CommandResult::Failed("expected a block, a point, a scale and a rotation".to_string()),
+    }
+}
+
+/// Inserts a block as rows and columns of copies, which stay one object.
+fn minsert_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Text(name), CapturedInput::Point(x, y), CapturedInput::Distance(scale), CapturedInput::Distance(rotation), CapturedInput::Distance(rows), CapturedInput::Distance(columns), CapturedInput::Distance(row_spacing), CapturedInput::Distance(column_spacing), answers @ ..] =>
+        {
+            let (id, block) = match block_named(w, name.trim()) {
+                Some(block) => block,
+                None => return CommandResult::Failed(format!("no block named {}", name.trim())),
+            };
+            let scale = Point::new(*scale as f32, *scale as f32);
+            let position = to_point(*x, *y);
+            let rotation = rotation.to_radians() as f32;
+            let array = Array {
+                columns: columns.round().max(0.0) as usize,
+                rows: rows.round().max(0.0) as usize,
+                spacing: to_point(*column_spacing, *row_spacing),
+            };
+            match Insert::new(id, block, position, scale, rotation, ctx.properties) {
+                Some(insert) => match insert.with_array(array) {
+                    Some(insert) => match fill_fields(insert, answers) {
+                        Ok(insert) => create_insert(w, Some(insert)),
+                        Err(result) => result,
+                    },
+                    None => CommandResult::Failed(
+                        "expected at least one row and one column".to_string(),
+                    ),
+                },
+                None => create_insert(w, None),
+            }
+        }
+        _ => CommandResult::Failed(
+            "expected a block, a point, a scale, a rotation, rows and columns".to_string(),
+        ),
+    }
+}
+
+fn create_insert(w: &mut World, insert: Option<Insert>) -> CommandResult {
+    match insert {
+        Some(insert) => {
+            w.create_entity().with(Drawable::Insert(insert)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("the scale can't be zero".to_string()),
+    }
+}
+
+/// Writes a block out as a dxf drawing of its own, named after it, for
+/// other drawings to use.
+fn wblock_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let name = match inputs {
+        [CapturedInput::Text(name)] => name.trim(),
+        _ => return CommandResult::Failed("expected a block name".to_string()),
+    };
+    let block = match block_named(w, name) {
+        Some((_, block)) => block,
+        None => return CommandResult::Failed(format!("no block named {}", name)),
+    };
+    // the name is used as the file name, so it mustn't lead anywhere else
+    if block.name.contains(&['/', '\\', ':'][..]) {
+        return CommandResult::Failed(format!("{} can't be used as a file name", block.name));
+    }
+    let path = format!("{}.dxf", block.name);
+    let text = dxf::write_block(w, &block);
+    match std::fs::write(&path, text) {
+        Ok(()) => {
+            w.write_resource::<LogHistory>()
+                .push(format!("wrote {}", path));
+            CommandResult::Done
+        }
+        Err(e) => CommandResult::Failed(format!("couldn't write {}: {}", path, e)),
+    }
+}
+
+/// Defines a field for blocks to carry, drawn as its tag until it's made
+/// part of one. Constant fields are given their value here.
+fn attdef_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (mode, tag, position, rest) = match inputs {
+        [CapturedInput::Keyword(mode), CapturedInput::Text(tag), CapturedInput::Point(x, y), rest @ ..] => {
+            (mode.as_str(), tag, to_point(*x, *y), rest)
+        }
+        _ => return CommandResult::Failed("expected a mode, a tag and a point".to_string()),
+    };
+    let (constant, invisible) = match mode {
+        "c" => (true, false),
+        "i" => (false, true),
+        "ci" | "ic" => (true, true),
+        "n" => (false, false),
+        _ => return CommandResult::Failed("unknown mode".to_string()),
+    };
+    let (height, rest) = match split_height(ctx, rest) {
+        Ok(found) => found,
+        Err(result) => return result,
+    };
+    // enter on its own leaves the prompt as the tag and the default blank
+    let answer = |input: &CapturedInput| match input {
+        CapturedInput::Text(text) => Some(text.clone()),
+        CapturedInput::Confirm => Some(String::new()),
+        _ => None,
+    };
+    let (prompt, default) = match (constant, rest) {
+        (false, []) => return CommandResult::More(InputDesc::Text, "prompt".to_string()),
+        (false, [_]) => return CommandResult::More(InputDesc::Text, "default value".to_string()),
+        (false, [prompt, default]) => (answer(prompt), answer(default)),
+        (true, []) => return CommandResult::More(InputDesc::Text, "value".to_string()),
+        (true, [value]) => (Some(String::new()), answer(value)),
+        _ => (None, None),
+    };
+    let (prompt, default) = match (prompt, default) {
+        (Some(prompt), Some(default)) => (prompt, default),
+        _ => return CommandResult::Failed("expected a prompt and a value".to_string()),
+    };
+    let justify = Justify::new(Horizontal::Left, Vertical::Baseline);
+    let text = Text::new(
+        tag,
+        position,
+        height,
+        0.0,
+        justify,
+        ctx.lettering,
+        ctx.properties,
+    );
+    match text.and_then(|text| AttDef::new(&prompt, &default, constant, invisible, text)) {
+        Some(field) => {
+            w.create_entity().with(Drawable::AttDef(field)).build();
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("a tag can't be blank or have spaces in it".to_string()),
+    }
+}
+
+/// `insert` with a value for each of its block's fields that isn't
+/// constant, from `answers` in order, or else the next one to ask for.
+/// Enter on its own keeps the value the field has now.
+fn fill_fields(insert: Insert, answers: &[CapturedInput]) -> Result<Insert, CommandResult> {
+    let mut attributes = vec![];
+    for (i, field) in insert
+        .fields()
+        .into_iter()
+        .filter(|f| !f.constant)
+        .enumerate()
+    {
+        let value = match answers.get(i) {
+            Some(CapturedInput::Text(value)) => value.clone(),
+            Some(CapturedInput::Confirm) => insert.value(field),
+            Some(_) => return Err(CommandResult::Failed("expected a value".to_string())),
+            None => {
+                let prompt = format!("{} <{}>", field.prompt, insert.value(field));
+                return Err(CommandResult::More(InputDesc::Text, prompt));
+            }
+        };
+        attributes.push(Attribute::new(&field.tag, &value));
+    }
+    Ok(insert.with_attributes(attributes))
+}
+
+/// Changes the values a block reference gives its block's fields.
+fn attedit_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (entity, answers) = match inputs {
+        [CapturedInput::Select(entity, _, _), answers @ ..] => (*entity, answers),
+        _ => return CommandResult::Failed("expected a block reference".to_string()),
+    };
+    let insert = match w.read_storage::<Drawable>().get(entity) {
+        Some(Drawable::Insert(insert)) => insert.clone(),
+        _ => return CommandResult::Failed("that isn't a block reference".to_string()),
+    };
+    if insert.fields().iter().all(|field| field.constant) {
+        return CommandResult::Failed("that block has no values to edit".to_string());
+    }
+    match fill_fields(insert, answers) {
+        Ok(edited) => {
+            if let Some(drawable) = w.write_storage::<Drawable>().get_mut(entity) {
+                *drawable = Drawable::Insert(edited);
+            }
+            CommandResult::Done
+        }
+        Err(result) => result,
+    }
+}
+
+/// where attext writes the fields of every block reference
+const ATTEXT_PATH: &str = "attributes.csv";
+
+fn attext_command(w: &mut World, _: &CommandContext, _: &[CapturedInput]) -> CommandResult {
+    let text = csv::write_attributes(w);
+    match std::fs::write(ATTEXT_PATH, text) {
+        Ok(()) => {
+            w.write_resource::<LogHistory>()
+                .push(format!("wrote {}", ATTEXT_PATH));
+            CommandResult::Done
+        }
+        Err(e) => CommandResult::Failed(format!("couldn't write {}: {}", ATTEXT_PATH, e)),
+    }
+}
+
+/// Sets how lengths are written out, rewriting every dimension to match.
+fn units_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (format, precision) = match inputs {
+        [CapturedInput::Keyword(format), CapturedInput::Distance(precision)] => {
+            (format.as_str(), *precision)
+        }
+        _ => return CommandResult::Failed("expected a format and a precision".to_string()),
+    };
+    let format = match format {
+        "d" => UnitFormat::Decimal,
+        "f" => UnitFormat::Fractional,
+        "a" => UnitFormat::Architectural,
+        _ => return CommandResult::Failed(format!("unknown format {}", format)),
+    };
+    let units = Units {
+        format,
+        precision: precision.round().clamp(0.0, 8.0) as usize,
+    };
+    *w.write_resource::<Units>() = units;
+    change_drawables(
+        w,
+        |drawable| drawable.uses_other_units(units),
+        |drawable| drawable.set_units(units),
+    );
+    CommandResult::Done
+}
+
+/// Cuts a drawable in two where it passes nearest the picked point.
+fn break_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (entity, at) = match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(x, y)] => {
+            (*entity, to_point(*x, *y))
+        }
+        _ => return CommandResult::Failed("expected an object and a point".to_string()),
+    };
+    let pieces = {
+        let drawables = w.read_storage::<Drawable>();
+        drawables
+            .get(entity)
+            .and_then(|d| d.broken_at(at, ctx.view.tolerance()))
+    };
+    match pieces {
+        Some(pieces) => {
+            replace_with_pieces(w, entity, pieces);
+            CommandResult::Done
+        }
+        None => CommandResult::Failed("can't break that there".to_string()),
+    }
+}
+
+/// Joins two lines or arcs with an arc of the given radius, trimming or
+/// stretching each to where the arc touches it.
+fn fillet_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (a, a_pick, b, b_pick, radius) = match inputs {
+        [CapturedInput::Select(a, ax, ay), CapturedInput::Select(b, bx, by), CapturedInput::Distance(r)] => {
+            (*a, to_point(*ax, *ay), *b, to_point(*bx, *by), *r as f32)
+        }
+        _ => return CommandResult::Failed("expected two objects and a radius".to_string()),
+    };
+    if a == b {
+        return CommandResult::Failed("pick two different objects".to_string());
+    }
+    if radius < 0.0 {
+        return CommandResult::Failed("the radius can't be negative".to_string());
+    }
+    let mut drawables = w.write_storage::<Drawable>();
+    let filleted = match (drawables.get(a), drawables.get(b)) {
+        (Some(first), Some(second)) => {
+            fillet(first, a_pick, second, b_pick, radius, ctx.properties)
+        }
+        _ => None,
+    };
+    let (first, second, arc) = match filleted {
+        Some(filleted) => filleted,
+        None => return CommandResult::Failed("no fillet of that radius fits".to_string()),
+    };
+    // changed in place so whatever depends on them follows
+    if let Some(drawable) = drawables.get_mut(a) {
+        *drawable = first;
+    }
+    if let Some(drawable) = drawables.get_mut(b) {
+        *drawable = second;
+    }
+    drop(drawables);
+    if let Some(arc) = arc {
+        w.create_entity().with(Drawable::Arc(arc)).build();
+    }
+    CommandResult::Done
+}
+
+/// Rounds off every corner of a polyline that sits between two straight
+/// segments long enough to take the arc.
+fn fillet_polyline_command(
+    w: &mut World,
+    _: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (entity, radius) = match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Distance(r)] => (*entity, *r as f32),
+        _ => return CommandResult::Failed("expected a polyline and a radius".to_string()),
+    };
+    if radius < 0.0 {
+        return CommandResult::Failed("the radius can't be negative".to_string());
+    }
+    let left = {
+        let mut drawables = w.write_storage::<Drawable>();
+        match drawables.get_mut(entity) {
+            Some(Drawable::Polyline(polyline)) => {
+                let (filleted, left) = polyline.filleted(radius);
+                *polyline = filleted;
+                left
+            }
+            _ => return CommandResult::Failed("that isn't a polyline".to_string()),
+        }
+    };
+    if left > 0 && radius > 0.0 {
+        w.write_resource::<LogHistory>()
+            .push(format!("{} corners too short to fit that radius", left));
+    } else if left > 0 {
+        w.write_resource::<LogHistory>().push(format!(
+            "{} arcs between parallel segments left as they were",
+            left
+        ));
+    }
+    CommandResult::Done
+}
+
+/// Cuts off the corner between two lines, or two segments of a polyline,
+/// the way the last chamfer was cut.
+fn chamfer_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (a, a_pick, b, b_pick) = match inputs {
+        [CapturedInput::Select(a, ax, ay), CapturedInput::Select(b, bx, by)] => {
+            (*a, to_point(*ax, *ay), *b, to_point(*bx, *by))
+        }
+        _ => return CommandResult::Failed("expected two lines".to_string()),
+    };
+    let settings = *w.read_resource::<ChamferSettings>();
+    let mut drawables = w.write_storage::<Drawable>();
+    if a == b {
+        let tolerance = ctx.view.tolerance();
+        let chamfered = match drawables.get(a) {
+            Some(Drawable::Polyline(polyline)) => match (
+                polyline.nearest_span(a_pick, tolerance),
+                polyline.nearest_span(b_pick, tolerance),
+            ) {
+                (Some(first), Some(second)) => {
+                    polyline.chamfered_between(first, second, settings.cut, settings.trim)
+                }
+                _ => None,
+            },
+            _ => {
+                return CommandResult::Failed(
+                    "pick two lines, or two segments of one polyline".to_string(),
+                )
+            }
+        };
+        let (chamfered, edge) = match chamfered {
+            Some(chamfered) => chamfered,
+            None => return CommandResult::Failed(NO_CHAMFER.to_string()),
+        };
+        if settings.trim {
+            if let Some(drawable) = drawables.get_mut(a) {
+                *drawable = Drawable::Polyline(chamfered);
+            }
+        }
+        drop(drawables);
+        if let Some((from, to)) = edge {
+            let edge = Line::new(from, to, ctx.properties);
+            w.create_entity().with(Drawable::Line(edge)).build();
+        }
+        return CommandResult::Done;
+    }
+    let chamfered = match (drawables.get(a), drawables.get(b)) {
+        (Some(Drawable::Line(first)), Some(Drawable::Line(second))) => chamfer(
+            first,
+            a_pick,
+            second,
+            b_pick,
+            settings.cut,
+            settings.trim,
+            ctx.properties,
+        ),
+        _ => {
+            return CommandResult::Failed(
+                "pick two lines, or two segments of one polyline".to_string(),
+            )
+        }
+    };
+    let (first, second, edge) = match chamfered {
+        Some(chamfered) => chamfered,
+        None => return CommandResult::Failed(NO_CHAMFER.to_string()),
+    };
+    // changed in place so whatever depends on them follows
+    if let Some(drawable) = drawables.get_mut(a) {
+        *drawable = Drawable::Line(first);
+    }
+    if let Some(drawable) = drawables.get_mut(b) {
+        *drawable = Drawable::Line(second);
+    }
+    drop(drawables);
+    if let Some(edge) = edge {
+        w.create_entity().with(Drawable::Line(edge)).build();
+    }
+    CommandResult::Done
+}
+
+fn chamfer_distances_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Distance(first), CapturedInput::Distance(second), picks @ ..] => {
+            if *first < 0.0 || *second < 0.0 {
+                return CommandResult::Failed("the distances can't be negative".to_string());
+            }
+            w.write_resource::<ChamferSettings>().cut =
+                Chamfer::Distances(*first as f32, *second as f32);
+            chamfer_command(w, ctx, picks)
+        }
+        _ => CommandResult::Failed("expected two distances".to_string()),
+    }
+}
+
+fn chamfer_angle_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Distance(distance), CapturedInput::Distance(angle), picks @ ..] => {
+            if *distance < 0.0 || *angle <= 0.0 || *angle >= 180.0 {
+                return CommandResult::Failed(
+                    "expected a positive distance and an angle between 0 and 180".to_string(),
+                );
+            }
+            w.write_resource::<ChamferSettings>().cut =
+                Chamfer::DistanceAngle(*distance as f32, (*angle as f32).to_radians());
+            chamfer_command(w, ctx, picks)
+        }
+        _ => CommandResult::Failed("expected a distance and an angle".to_string()),
+    }
+}
+
+fn chamfer_trim_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Keyword(trim), picks @ ..] => {
+            w.write_resource::<ChamferSettings>().trim = match trim.as_str() {
+                "t" => true,
+                "n" => false,
+                _ => return CommandResult::Failed(format!("unknown option {}", trim)),
+            };
+            chamfer_command(w, ctx, picks)
+        }
+        _ => CommandResult::Failed("expected t or n".to_string()),
+    }
+}
+
+/// Cuts off every corner of a polyline between two straight segments long
+/// enough to take the cut.
+fn chamfer_polyline_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let entity = match inputs {
+        [CapturedInput::Select(entity, _, _)] => *entity,
+        _ => return CommandResult::Failed("expected a polyline".to_string()),
+    };
+    let settings = *w.read_resource::<ChamferSettings>();
+    let (edges, uncut) = {
+        let mut drawables = w.write_storage::<Drawable>();
+        let (chamfered, edges, uncut) = match drawables.get(entity) {
+            Some(Drawable::Polyline(polyline)) => polyline.chamfered(settings.cut, settings.trim),
+            _ => return CommandResult::Failed("that isn't a polyline".to_string()),
+        };
+        if settings.trim {
+            if let Some(drawable) = drawables.get_mut(entity) {
+                *drawable = Drawable::Polyline(chamfered);
+            }
+        }
+        (edges, uncut)
+    };
+    for (from, to) in edges {
+        let edge = Line::new(from, to, ctx.properties);
+        w.create_entity().with(Drawable::Line(edge)).build();
+    }
+    if uncut > 0 {
+        w.write_resource::<LogHistory>()
+            .push(format!("{} corners couldn't be cut", uncut));
+    }
+    CommandResult::Done
+}
+
+/// the visible drawables, other than previews, that `picked` holds, or all
+/// of them when nothing was picked
+fn edges_among(w: &World, picked: &[Entity]) -> Vec<(Entity, Drawable)> {
+    let layers = w.read_resource::<Layers>();
+    let entities = w.entities();
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    (&entities, &drawables, !&previews)
+        .join()
+        .filter(|(entity, _, _)| picked.is_empty() || picked.contains(entity))
+        .filter(|(_, drawable, _)| {
+            drawable
+                .properties()
+                .and_then(|props| layers.get(props.layer))
+                .map(|layer| !(layer.hidden || layer.frozen))
+                .unwrap_or(true)
+        })
+        .map(|(entity, drawable, _)| (entity, drawable.clone()))
+        .collect()
+}
+
+/// what's left of `entity` after trimming at `pick`
+fn trimmed_pieces(
+    w: &World,
+    ctx: &CommandContext,
+    entity: Entity,
+    pick: Point,
+    edges: &[(Entity, Drawable)],
+) -> Result<Vec<Drawable>, String> {
+    let others: Vec<Drawable> = edges
+        .iter()
+        .filter(|(edge, _)| *edge != entity)
+        .map(|(_, drawable)| drawable.clone())
+        .collect();
+    let tolerance = ctx.view.tolerance();
+    let drawables = w.read_storage::<Drawable>();
+    let drawable = drawables.get(entity).ok_or("that's been erased")?;
+    let cuts = crossings(drawable, &others, tolerance);
+    trim(drawable, pick, &cuts, tolerance)
+        .ok_or_else(|| "no cutting edge to trim that back to".to_string())
+}
+
+/// `entity` swapped for the pieces left of it after trimming
+fn replace_with_pieces(w: &mut World, entity: Entity, pieces: Vec<Drawable>) {
+    let mut left = pieces.into_iter();
+    // the first piece stays the same entity so whatever depends on it follows
+    if let Some(first) = left.next() {
+        if let Some(drawable) = w.write_storage::<Drawable>().get_mut(entity) {
+            *drawable = first;
+        }
+    }
+    for piece in left {
+        w.create_entity().with(piece).build();
+    }
+}
+
+/// `entity` swapped for what's left of it after trimming at `pick`
+fn trim_entity(
+    w: &mut World,
+    ctx: &CommandContext,
+    entity: Entity,
+    pick: Point,
+    edges: &[(Entity, Drawable)],
+) -> Result<(), String> {
+    let pieces = trimmed_pieces(w, ctx, entity, pick, edges)?;
+    replace_with_pieces(w, entity, pieces);
+    Ok(())
+}
+
+/// Cuts off the side of an object picked, back to where it crosses the
+/// cutting edges, and carries on with the next object picked.
+fn trim_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (picked, entity, pick) = match inputs {
+        [CapturedInput::Multiselect(picked), CapturedInput::Select(entity, x, y)] => {
+            (picked, *entity, to_point(*x, *y))
+        }
+        _ => return CommandResult::Failed("expected edges and an object".to_string()),
+    };
+    let edges = edges_among(w, picked);
+    match trim_entity(w, ctx, entity, pick, &edges) {
+        Ok(()) => CommandResult::Repeat(vec![CapturedInput::Multiselect(picked.clone())]),
+        Err(reason) => CommandResult::Failed(reason),
+    }
+}
+
+/// Trims every object the fence crosses, on the side it crosses them.
+fn trim_fence_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (picked, fence) = match inputs {
+        [CapturedInput::Multiselect(picked), CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] =>
+        {
+            let fence = Line::new(to_point(*ax, *ay), to_point(*bx, *by), ctx.properties);
+            (picked, [Drawable::Line(fence)])
+        }
+        _ => return CommandResult::Failed("expected edges and a fence".to_string()),
+    };
+    let edges = edges_among(w, picked);
+    let tolerance = ctx.view.tolerance();
+    let crossed: Vec<(Entity, Point)> = edges_among(w, &[])
+        .into_iter()
+        .filter_map(|(entity, drawable)| {
+            let at = *crossings(&drawable, &fence, tolerance).first()?;
+            Some((entity, at))
+        })
+        .collect();
+    // everything is trimmed against the edges as they were before any of
+    // them got cut back, then swapped in together
+    let trimmed: Vec<(Entity, Vec<Drawable>)> = crossed
+        .into_iter()
+        .filter_map(|(entity, at)| {
+            let pieces = trimmed_pieces(w, ctx, entity, at, &edges).ok()?;
+            Some((entity, pieces))
+        })
+        .collect();
+    let count = trimmed.len();
+    for (entity, pieces) in trimmed {
+        replace_with_pieces(w, entity, pieces);
+    }
+    w.write_resource::<LogHistory>()
+        .push(format!("trimmed {} objects", count));
+    CommandResult::Done
+}
+
+/// Stretches the end of an object picked out to the nearest boundary edge
+/// it meets, and carries on with the next object picked.
+fn extend_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (picked, entity, pick) = match inputs {
+        [CapturedInput::Multiselect(picked), CapturedInput::Select(entity, x, y)] => {
+            (picked, *entity, to_point(*x, *y))
+        }
+        _ => return CommandResult::Failed("expected edges and an object".to_string()),
+    };
+    let edges: Vec<Drawable> = edges_among(w, picked)
+        .into_iter()
+        .filter(|(edge, _)| *edge != entity)
+        .map(|(_, drawable)| drawable)
+        .collect();
+    let mut drawables = w.write_storage::<Drawable>();
+    let extended = drawables
+        .get(entity)
+        .and_then(|drawable| extend(drawable, pick, &edges, ctx.view.tolerance()));
+    match (extended, drawables.get_mut(entity)) {
+        (Some(extended), Some(drawable)) => {
+            *drawable = extended;
+            drop(drawables);
+            CommandResult::Repeat(vec![CapturedInput::Multiselect(picked.clone())])
+        }
+        _ => CommandResult::Failed("it doesn't meet a boundary edge that way".to_string()),
+    }
+}
+
+/// adds copies of `entity` running `distance` away on the side of `toward`
+fn create_offsets(
+    w: &mut World,
+    ctx: &CommandContext,
+    entity: Entity,
+    distance: f32,
+    toward: Point,
+) -> Result<(), String> {
+    let offsets = {
+        let drawables = w.read_storage::<Drawable>();
+        let drawable = drawables.get(entity).ok_or("that's been erased")?;
+        offset(drawable, distance, toward, ctx.view.tolerance())
+    };
+    for copy in offsets.ok_or("can't offset that by that much")? {
+        w.create_entity().with(copy).build();
+    }
+    Ok(())
+}
+
+/// Adds a copy of an object running alongside it at a distance, and carries
+/// on offsetting by the same distance.
+fn offset_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (distance, entity, toward) = match inputs {
+        [CapturedInput::Distance(d), CapturedInput::Select(entity, _, _), CapturedInput::Point(x, y)] => {
+            (*d, *entity, to_point(*x, *y))
+        }
+        _ => return CommandResult::Failed("expected a distance, an object and a side".to_string()),
+    };
+    match create_offsets(w, ctx, entity, distance as f32, toward) {
+        Ok(()) => CommandResult::Repeat(vec![CapturedInput::Distance(distance)]),
+        Err(reason) => CommandResult::Failed(reason),
+    }
+}
+
+/// Adds a copy of an object running alongside it through a picked point.
+fn offset_through_command(
+    w: &mut World,
+    ctx: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    let (entity, through) = match inputs {
+        [CapturedInput::Select(entity, _, _), CapturedInput::Point(x, y)] => {
+            (*entity, to_point(*x, *y))
+        }
+        _ => return CommandResult::Failed("expected an object and a point".to_string()),
+    };
+    let distance = match w.read_storage::<Drawable>().get(entity) {
+        Some(drawable) => drawable.distance_to(through, ctx.view.tolerance()),
+        None => return CommandResult::Failed("that's been erased".to_string()),
+    };
+    match create_offsets(w, ctx, entity, distance, through) {
+        Ok(()) => CommandResult::Repeat(vec![]),
+        Err(reason) => CommandResult::Failed(reason),
+    }
+}
+
+/// Adds a mirrored copy of each selected drawable.
+fn mirror_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    let (entities, transform) = match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] => {
+            match Affine::mirror(to_point(*ax, *ay), to_point(*bx, *by)) {
+                Some(transform) => (entities, transform),
+                None => {
+                    return CommandResult::Failed(
+                        "the mirror line needs two different points".to_string(),
+                    )
+                }
+            }
+        }
+        _ => return CommandResult::Failed("expected a selection and two points".to_string()),
+    };
+    let mirrored: Vec<Drawable> = {
+        let drawables = w.read_storage::<Drawable>();
+        entities
+            .iter()
+            .filter_map(|e| drawables.get(*e))
+            .map(|d| d.transformed(&transform))
+            .collect()
+    };
+    for drawable in mirrored {
+        w.create_entity().with(drawable).build();
+    }
+    CommandResult::Done
+}
+
+/// moves the drawables of `entities` by `transform` where they are, so
+/// whatever depends on them follows
+fn transform_entities(w: &mut World, entities: &[Entity], transform: &Affine) {
+    let mut drawables = w.write_storage::<Drawable>();
+    for entity in entities {
+        if let Some(drawable) = drawables.get_mut(*entity) {
+            *drawable = drawable.transformed(transform);
+        }
+    }
+}
+
+/// Moves the selected drawables by the distance between two points.
+fn move_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] =>
+        {
+            let transform = Affine::translation(to_point(*bx, *by) - to_point(*ax, *ay));
+            transform_entities(w, entities, &transform);
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection and two points".to_string()),
+    }
+}
+
+/// Adds copies of the selected drawables moved by the distance between two
+/// points, carrying on with more copies from the same base point.
+fn copy_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] =>
+        {
+            let transform = Affine::translation(to_point(*bx, *by) - to_point(*ax, *ay));
+            let copies: Vec<Drawable> = {
+                let drawables = w.read_storage::<Drawable>();
+                entities
+                    .iter()
+                    .filter_map(|e| drawables.get(*e))
+                    .map(|d| d.transformed(&transform))
+                    .collect()
+            };
+            for drawable in copies {
+                w.create_entity().with(drawable).build();
+            }
+            CommandResult::Repeat(vec![
+                CapturedInput::Multiselect(entities.clone()),
+                CapturedInput::Point(*ax, *ay),
+            ])
+        }
+        _ => CommandResult::Failed("expected a selection and two points".to_string()),
+    }
+}
+
+/// Turns the selected drawables around a base point.
+fn rotate_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(x, y), CapturedInput::Distance(angle)] =>
+        {
+            let transform = Affine::rotation(to_point(*x, *y), (*angle as f32).to_radians());
+            transform_entities(w, entities, &transform);
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection, a point and an angle".to_string()),
+    }
+}
+
+/// a number typed where a point was asked for
+fn typed_number(text: &str) -> Result<f32, CommandResult> {
+    text.parse()
+        .map_err(|_| CommandResult::Failed(format!("{} isn't a number", text)))
+}
+
+/// the reference value and the new one given after the base point, each
+/// typed in or measured by `measure`: the reference between two points
+/// picked, the new value from the base point out to one picked
+fn reference_values(
+    base: Point,
+    inputs: &[CapturedInput],
+    measure: impl Fn(Point, Point) -> f32,
+    value: &str,
+) -> Result<(f32, f32), CommandResult> {
+    let (reference, rest) = match inputs {
+        [CapturedInput::Point(_, _)] => {
+            return Err(CommandResult::More(
+                InputDesc::Point,
+                "second point".to_string(),
+            ))
+        }
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by), rest @ ..] => {
+            (measure(to_point(*ax, *ay), to_point(*bx, *by)), rest)
+        }
+        [CapturedInput::Keyword(text), rest @ ..] => (typed_number(text)?, rest),
+        _ => {
+            return Err(CommandResult::Failed(format!(
+                "expected a reference {}",
+                value
+            )))
+        }
+    };
+    match rest {
+        [] => Err(CommandResult::More(
+            InputDesc::Point,
+            format!("new {}, or a point", value),
+        )),
+        [CapturedInput::Point(x, y)] => Ok((reference, measure(base, to_point(*x, *y)))),
+        [CapturedInput::Keyword(text)] => Ok((reference, typed_number(text)?)),
+        _ => Err(CommandResult::Failed(format!("expected a new {}", value))),
+    }
+}
+
+/// Turns the selected drawables around a base point so that the reference
+/// angle ends up at the new one. Either angle can be typed in degrees or
+/// picked, the reference as a line between two points and the new angle as
+/// a point around the base point.
+fn rotate_reference_command(
+    w: &mut World,
+    _: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(x, y), rest @ ..] => {
+            let base = to_point(*x, *y);
+            let angle = |a: Point, b: Point| (b - a).angle().to_degrees();
+            let (from, to) = match reference_values(base, rest, angle, "angle in degrees") {
+                Ok(angles) => angles,
+                Err(result) => return result,
+            };
+            let transform = Affine::rotation(base, (to - from).to_radians());
+            transform_entities(w, entities, &transform);
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection, a point and two angles".to_string()),
+    }
+}
+
+/// Resizes the selected drawables toward or away from a base point.
+fn scale_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(x, y), CapturedInput::Distance(factor)] =>
+        {
+            if *factor <= 0.0 {
+                return CommandResult::Failed("the scale factor has to be more than 0".to_string());
+            }
+            let transform = Affine::scaling(to_point(*x, *y), *factor as f32);
+            transform_entities(w, entities, &transform);
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection, a point and a factor".to_string()),
+    }
+}
+
+/// Resizes the selected drawables around a base point so that the
+/// reference length becomes the new one. Either length can be typed or
+/// picked, the reference between two points and the new length out from
+/// the base point.
+fn scale_reference_command(
+    w: &mut World,
+    _: &CommandContext,
+    inputs: &[CapturedInput],
+) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities), CapturedInput::Point(x, y), rest @ ..] => {
+            let base = to_point(*x, *y);
+            let (from, to) = match reference_values(base, rest, Point::distance, "length") {
+                Ok(lengths) => lengths,
+                Err(result) => return result,
+            };
+            if from <= 0.0 || to <= 0.0 {
+                return CommandResult::Failed("the lengths have to be more than 0".to_string());
+            }
+            let transform = Affine::scaling(base, to / from);
+            transform_entities(w, entities, &transform);
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection, a point and two lengths".to_string()),
+    }
+}
+
+/// where dxfout writes the drawing
+const DXF_PATH: &str = "drawing.dxf";
+
+fn dxfout_command(w: &mut World, _: &CommandContext, _: &[CapturedInput]) -> CommandResult {
+    let text = dxf::write_drawing(w);
+    match std::fs::write(DXF_PATH, text) {
+        Ok(()) => {
+            w.write_resource::<LogHistory>()
+                .push(format!("wrote {}", DXF_PATH));
+            CommandResult::Done
+        }
+        Err(e) => CommandResult::Failed(format!("couldn't write {}: {}", DXF_PATH, e)),
+    }
+}
+
+fn length_command(w: &mut World, _: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Multiselect(entities)] => {
+            let total: f32 = {
+                let drawables = w.read_storage::<Drawable>();
+                entities
+                    .iter()
+                    .filter_map(|e| drawables.get(*e))
+                    .map(|d| d.length())
+                    .sum()
+            };
+            let mut history = w.write_resource::<LogHistory>();
+            history.push(format!("length: {:.4}", total));
+            CommandResult::Done
+        }
+        _ => CommandResult::Failed("expected a selection".to_string()),
+    }
+}
+
+fn quit_command(_: &mut World, _: &CommandContext, _: &[CapturedInput]) -> CommandResult {
+    CommandResult::Quit
+}
+
+fn line_command(w: &mut World, ctx: &CommandContext, inputs: &[CapturedInput]) -> CommandResult {
+    match inputs {
+        [CapturedInput::Point(ax, ay), CapturedInput::Point(bx, by)] => {
+            let start = Point::new(*ax as f32, *ay as f32);
+            let end = Point::new(*bx as f32, *by as f32);
+            let line = Drawable::Line(Line::new(start, end, ctx.properties));
+            w.create_entity().with(line).build();
+            w.write_resource::<LastSegment>().tangent = Some((end, end - start));
+
+            // keep drawing segments from the end of this one until cancelled
+            CommandResult::Repeat(vec![CapturedInput::Point(*bx, *by)])
+        }
+        _ => CommandResult::Failed("expected two points".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst::ecs::prelude::*;
+
+    use super::{reference_values, transform_entities};
+    use crate::components::{Affine, Color, Drawable, Line, Point, Properties};
+    use crate::resources::{CapturedInput, CommandResult, Layer, Layers, LineType, LineTypes};
+
+    fn props() -> Properties {
+        let mut line_types = LineTypes::new();
+        let line_type = line_types.push(LineType {
+            draw_line: LineType::line_type_continous,
+            period: 0.0,
+        });
+        let mut layers = Layers::new();
+        let layer = layers.push(Layer {
+            name: "0".to_string(),
+            color: Color::Fixed(7),
+            line_type,
+            hidden: false,
+            frozen: false,
+            locked: false,
+        });
+        Properties::from_layer(layer, layers.get(layer).unwrap())
+    }
+
+    #[test]
+    fn only_the_selected_drawables_are_transformed() {
+        let mut w = World::new();
+        w.register::<Drawable>();
+        let line = Line::new(Point::new(0.0, 0.0), Point::new(1.0, 0.0), props());
+        let picked = w.create_entity().with(Drawable::Line(line)).build();
+        let left = w.create_entity().with(Drawable::Line(line)).build();
+        let transform = Affine::rotation(Point::new(0.0, 0.0), std::f32::consts::FRAC_PI_2);
+        transform_entities(&mut w, &[picked], &transform);
+        let drawables = w.read_storage::<Drawable>();
+        match drawables.get(picked) {
+            Some(Drawable::Line(turned)) => {
+                assert!(turned.end.distance(Point::new(0.0, 1.0)) < 1e-5)
+            }
+            other => panic!("expected a line, got {:?}", other),
+        }
+        assert_eq!(drawables.get(left), Some(&Drawable::Line(line)));
+    }
+
+    #[test]
+    fn reference_values_can_be_picked_or_typed() {
+        let base = Point::new(0.0, 0.0);
+        let inputs = [
+            CapturedInput::Point(1.0, 1.0),
+            CapturedInput::Point(4.0, 5.0),
+            CapturedInput::Keyword("10".to_string()),
+        ];
+        match reference_values(base, &inputs, Point::distance, "length") {
+            Ok((from, to)) => assert!((from - 5.0).abs() < 1e-5 && (to - 10.0).abs() < 1e-5),
+            Err(_) => panic!("both lengths were given"),
+        }
+        let inputs = [
+            CapturedInput::Keyword("2".to_string()),
+            CapturedInput::Point(0.0, 3.0),
+        ];
+        match reference_values(base, &inputs, Point::distance, "length") {
+            Ok((from, to)) => assert!((from - 2.0).abs() < 1e-5 && (to - 3.0).abs() < 1e-5),
+            Err(_) => panic!("both lengths were given"),
+        }
+    }
+
+    #[test]
+    fn reference_values_ask_for_what_is_missing() {
+        let base = Point::new(0.0, 0.0);
+        let one_point = [CapturedInput::Point(1.0, 1.0)];
+        match reference_values(base, &one_point, Point::distance, "length") {
+            Err(CommandResult::More(_, prompt)) => assert_eq!(prompt, "second point"),
+            _ => panic!("expected to be asked for the second point"),
+        }
+        let typed = [CapturedInput::Keyword("2".to_string())];
+        match reference_values(base, &typed, Point::distance, "length") {
+            Err(CommandResult::More(_, prompt)) => assert_eq!(prompt, "new length, or a point"),
+            _ => panic!("expected to be asked for the new length"),
+        }
+        let word = [CapturedInput::Keyword("big".to_string())];
+        match reference_values(base, &word, Point::distance, "length") {
+            Err(CommandResult::Failed(message)) => assert_eq!(message, "big isn't a number"),
+            _ => panic!("expected a word to be refused"),
+        }
+    }
 }
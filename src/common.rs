@@ -3,7 +3,7 @@ use specs::prelude::*;
 
 use crate::{components::ActiveCamera, resources::ViewInfo};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct GenerationID<M> {
     id: usize,
     generation: usize,
@@ -12,6 +12,14 @@ pub struct GenerationID<M> {
 
 impl<M> Copy for GenerationID<M> {}
 
+impl<M> PartialEq for GenerationID<M> {
+    fn eq(&self, other: &GenerationID<M>) -> bool {
+        self.id == other.id && self.generation == other.generation
+    }
+}
+
+impl<M> Eq for GenerationID<M> {}
+
 impl<M> Clone for GenerationID<M> {
     fn clone(&self) -> GenerationID<M> {
         *self
@@ -29,16 +37,38 @@ impl<T> GenerationVec<T> {
     }
     pub fn get(&self, id: GenerationID<T>) -> Option<&T> {
         let GenerationID { id, generation, .. } = id;
-        if let Some((gen, Some(item))) = self.inner.get(id) {
-            if *gen == generation {
-                return Some(item);
-            } else {
-                return None;
-            }
-        } else {
-            return None;
+        match self.inner.get(id) {
+            Some((gen, Some(item))) if *gen == generation => Some(item),
+            _ => None,
+        }
+    }
+    pub fn get_mut(&mut self, id: GenerationID<T>) -> Option<&mut T> {
+        let GenerationID { id, generation, .. } = id;
+        match self.inner.get_mut(id) {
+            Some((gen, Some(item))) if *gen == generation => Some(item),
+            _ => None,
         }
     }
+    /// every item still present, with the id it can be looked up by
+    pub fn iter(&self) -> impl Iterator<Item = (GenerationID<T>, &T)> {
+        self.inner
+            .iter()
+            .enumerate()
+            .filter_map(|(id, (generation, item))| {
+                item.as_ref().map(|item| {
+                    let id = GenerationID {
+                        id,
+                        generation: *generation,
+                        _marker: Default::default(),
+                    };
+                    (id, item)
+                })
+            })
+    }
+    /// every item still present, to be changed in place
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.inner.iter_mut().filter_map(|(_, item)| item.as_mut())
+    }
     pub fn push(&mut self, item: T) -> GenerationID<T> {
         if let Some((index, (gen, _))) = self
             .inner
@@ -63,13 +93,6 @@ impl<T> GenerationVec<T> {
             }
         }
     }
-    pub fn remove(&mut self, index: usize) -> Option<T> {
-        if let Some((_gen, found)) = self.inner.get_mut(index) {
-            found.take()
-        } else {
-            None
-        }
-    }
 }
 
 pub fn as_alphanumeric(key: VirtualKeyCode) -> Option<char> {
@@ -289,6 +312,7 @@ pub struct ScreenTranslation {
     pub dy: f32,
 }
 
+#[derive(Clone, Copy)]
 pub struct ScreenSize {
     pub width: f64,
     pub height: f64,
@@ -304,8 +328,8 @@ impl Default for ScreenSize {
 }
 
 pub struct ScreenPos {
-    x: f64,
-    y: f64,
+    pub x: f64,
+    pub y: f64,
 }
 
 #[derive(Default, Copy, Clone)]
@@ -0,0 +1,145 @@
+use super::Point;
+
+/// how far curves a transform can't keep the shape of may stray once it has
+/// stretched them, in drawing units: well under a pixel at any zoom worked at
+const TRANSFORM_TOLERANCE: f32 = 1e-3;
+
+/// A 2d affine transform taking `p` to `x * p.x + y * p.y + offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    pub x: Point,
+    pub y: Point,
+    pub offset: Point,
+}
+
+impl Affine {
+    /// moving everything along by `by`
+    pub fn translation(by: Point) -> Self {
+        Affine {
+            x: Point::new(1.0, 0.0),
+            y: Point::new(0.0, 1.0),
+            offset: by,
+        }
+    }
+    /// turning counter-clockwise by `angle` around `center`
+    pub fn rotation(center: Point, angle: f32) -> Self {
+        let x = Point::from_angle(angle);
+        let y = x.perp();
+        Affine {
+            x,
+            y,
+            offset: center - (x * center.x + y * center.y),
+        }
+    }
+    /// growing by `factor` away from `center`
+    pub fn scaling(center: Point, factor: f32) -> Self {
+        Affine {
+            x: Point::new(factor, 0.0),
+            y: Point::new(0.0, factor),
+            offset: center - center * factor,
+        }
+    }
+    /// reflection across the line through `a` and `b`
+    pub fn mirror(a: Point, b: Point) -> Option<Self> {
+        let d = (b - a).normalize()?;
+        let x = Point::new(2.0 * d.x * d.x - 1.0, 2.0 * d.x * d.y);
+        let y = Point::new(2.0 * d.x * d.y, 2.0 * d.y * d.y - 1.0);
+        let reflected = x * a.x + y * a.y;
+        Some(Affine {
+            x,
+            y,
+            offset: a - reflected,
+        })
+    }
+    /// where a direction or difference between points ends up, ignoring the offset
+    pub fn apply_vector(&self, v: Point) -> Point {
+        self.x * v.x + self.y * v.y
+    }
+    pub fn apply(&self, p: Point) -> Point {
+        self.apply_vector(p) + self.offset
+    }
+    /// negative when the transform flips shapes over
+    pub fn determinant(&self) -> f32 {
+        self.x.cross(self.y)
+    }
+    /// how closely curves have to be followed before this transform so
+    /// they stay within `TRANSFORM_TOLERANCE` of their shape after it
+    pub fn tolerance(&self) -> f32 {
+        // no length grows by more than this
+        let stretch = (self.x.dot(self.x) + self.y.dot(self.y)).sqrt();
+        TRANSFORM_TOLERANCE / stretch.max(f32::EPSILON)
+    }
+    /// how much lengths grow if this transform keeps shapes the same, only
+    /// moving, turning, flipping or resizing them
+    pub fn uniform_scale(&self) -> Option<f32> {
+        let (sx, sy) = (self.x.length(), self.y.length());
+        let slack = 1e-5 * sx.max(sy);
+        if (sx - sy).abs() <= slack && self.x.dot(self.y).abs() <= slack * sx.max(sy) {
+            Some(sx)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::super::tests::near;
+    use super::{Affine, Point};
+
+    #[test]
+    fn translations_move_points_but_not_directions() {
+        let by = Affine::translation(Point::new(3.0, -2.0));
+        assert!(near(by.apply(Point::new(1.0, 1.0)), Point::new(4.0, -1.0)));
+        assert!(near(
+            by.apply_vector(Point::new(1.0, 1.0)),
+            Point::new(1.0, 1.0)
+        ));
+        assert_eq!(by.uniform_scale(), Some(1.0));
+    }
+
+    #[test]
+    fn rotations_turn_counter_clockwise_around_their_center() {
+        let center = Point::new(2.0, 1.0);
+        let quarter = Affine::rotation(center, FRAC_PI_2);
+        assert!(near(quarter.apply(center), center));
+        assert!(near(
+            quarter.apply(Point::new(4.0, 1.0)),
+            Point::new(2.0, 3.0)
+        ));
+        assert!((quarter.determinant() - 1.0).abs() < 1e-5);
+        assert!((quarter.uniform_scale().unwrap() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn scaling_keeps_its_center_in_place() {
+        let center = Point::new(1.0, 1.0);
+        let double = Affine::scaling(center, 2.0);
+        assert!(near(double.apply(center), center));
+        assert!(near(
+            double.apply(Point::new(2.0, 3.0)),
+            Point::new(3.0, 5.0)
+        ));
+        assert_eq!(double.uniform_scale(), Some(2.0));
+    }
+
+    #[test]
+    fn mirrors_flip_shapes_over() {
+        let across = Affine::mirror(Point::new(0.0, 1.0), Point::new(1.0, 2.0)).unwrap();
+        assert!(near(
+            across.apply(Point::new(1.0, 0.0)),
+            Point::new(-1.0, 2.0)
+        ));
+        assert!(across.determinant() < 0.0);
+        assert!(Affine::mirror(Point::new(1.0, 1.0), Point::new(1.0, 1.0)).is_none());
+        // stretching one way only changes the shape
+        let stretch = Affine {
+            x: Point::new(2.0, 0.0),
+            y: Point::new(0.0, 1.0),
+            offset: Point::new(0.0, 0.0),
+        };
+        assert_eq!(stretch.uniform_scale(), None);
+    }
+}
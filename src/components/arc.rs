@@ -0,0 +1,292 @@
+use std::f32::consts::PI;
+
+use super::{arc_divisions, circumcenter, connect, Affine, Drawable, Ellipse, Point, Properties};
+
+/// A circular arc swept counter-clockwise from `start_angle` to `end_angle`,
+/// angles in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arc {
+    pub center: Point,
+    pub radius: f32,
+    pub start_angle: f32,
+    pub end_angle: f32,
+    pub props: Properties,
+}
+
+impl Arc {
+    pub fn new(
+        center: Point,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        props: Properties,
+    ) -> Self {
+        Arc {
+            center,
+            radius,
+            start_angle,
+            end_angle,
+            props,
+        }
+    }
+    /// the arc from `start` through `second` to `end`
+    pub fn through_points(
+        start: Point,
+        second: Point,
+        end: Point,
+        props: Properties,
+    ) -> Option<Self> {
+        let center = circumcenter(start, second, end)?;
+        let radius = center.distance(start);
+        let a0 = (start - center).angle();
+        let a1 = (second - center).angle();
+        let a2 = (end - center).angle();
+        if angle_within(a1, a0, a2) {
+            Some(Arc::new(center, radius, a0, a2, props))
+        } else {
+            Some(Arc::new(center, radius, a2, a0, props))
+        }
+    }
+    /// counter-clockwise arc around `center` from `start` to the direction of `end`
+    pub fn start_center_end(
+        start: Point,
+        center: Point,
+        end: Point,
+        props: Properties,
+    ) -> Option<Self> {
+        let radius = center.distance(start);
+        if radius <= f32::EPSILON || center.distance(end) <= f32::EPSILON {
+            return None;
+        }
+        let start_angle = (start - center).angle();
+        let end_angle = (end - center).angle();
+        Some(Arc::new(center, radius, start_angle, end_angle, props))
+    }
+    /// counter-clockwise arc from `start` to `end`, a negative radius picks
+    /// the larger of the two possible arcs
+    pub fn start_end_radius(
+        start: Point,
+        end: Point,
+        radius: f32,
+        props: Properties,
+    ) -> Option<Self> {
+        let chord = end - start;
+        let half = chord.length() / 2.0;
+        if half <= f32::EPSILON || radius.abs() < half {
+            return None;
+        }
+        let offset = (radius * radius - half * half).sqrt();
+        let normal = chord.normalize()?.perp();
+        let middle = start + chord * 0.5;
+        let center = if radius > 0.0 {
+            middle + normal * offset
+        } else {
+            middle - normal * offset
+        };
+        let start_angle = (start - center).angle();
+        let end_angle = (end - center).angle();
+        Some(Arc::new(
+            center,
+            radius.abs(),
+            start_angle,
+            end_angle,
+            props,
+        ))
+    }
+    /// the arc leaving `start` heading along `direction` which ends at `end`
+    pub fn tangent_from(
+        start: Point,
+        direction: Point,
+        end: Point,
+        props: Properties,
+    ) -> Option<Self> {
+        let normal = direction.normalize()?.perp();
+        let chord = end - start;
+        let across = 2.0 * chord.dot(normal);
+        if across.abs() <= f32::EPSILON {
+            return None;
+        }
+        // distance along the normal to the point equally far from start and end
+        let t = chord.dot(chord) / across;
+        let center = start + normal * t;
+        let start_angle = (start - center).angle();
+        let end_angle = (end - center).angle();
+        if t > 0.0 {
+            Some(Arc::new(center, t, start_angle, end_angle, props))
+        } else {
+            // turning clockwise, which is the same arc swept the other way
+            Some(Arc::new(center, -t, end_angle, start_angle, props))
+        }
+    }
+    /// angle covered going counter-clockwise from start to end
+    pub fn sweep(&self) -> f32 {
+        let sweep = (self.end_angle - self.start_angle).rem_euclid(2.0 * PI);
+        if sweep <= f32::EPSILON {
+            2.0 * PI
+        } else {
+            sweep
+        }
+    }
+    pub fn point_at(&self, angle: f32) -> Point {
+        self.center + Point::from_angle(angle) * self.radius
+    }
+    pub fn start_point(&self) -> Point {
+        self.point_at(self.start_angle)
+    }
+    pub fn end_point(&self) -> Point {
+        self.point_at(self.end_angle)
+    }
+    /// which way something drawing this arc is heading when it reaches `end`,
+    /// which should be one of the arc's end points
+    pub fn heading_at(&self, end: Point) -> Point {
+        if end.distance(self.end_point()) <= end.distance(self.start_point()) {
+            Point::from_angle(self.end_angle).perp()
+        } else {
+            Point::from_angle(self.start_angle).perp() * -1.0
+        }
+    }
+    /// this arc moved by `transform`, stretching into an elliptical arc if
+    /// the transform doesn't keep its shape
+    pub fn transformed(&self, transform: &Affine) -> Drawable {
+        match transform.uniform_scale() {
+            Some(scale) => {
+                let start = transform
+                    .apply_vector(Point::from_angle(self.start_angle))
+                    .angle();
+                let end = transform
+                    .apply_vector(Point::from_angle(self.end_angle))
+                    .angle();
+                // flipped over, the arc now runs counter-clockwise from its old end
+                let (start_angle, end_angle) = if transform.determinant() < 0.0 {
+                    (end, start)
+                } else {
+                    (start, end)
+                };
+                Drawable::Arc(Arc::new(
+                    transform.apply(self.center),
+                    self.radius * scale,
+                    start_angle,
+                    end_angle,
+                    self.props,
+                ))
+            }
+            None => Drawable::Ellipse(
+                Ellipse::circular(
+                    self.center,
+                    self.radius,
+                    self.start_angle,
+                    self.end_angle,
+                    self.props,
+                )
+                .transformed(transform),
+            ),
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let sweep = self.sweep();
+        let divisions = arc_divisions(self.radius, sweep, tolerance);
+        let points: Vec<Point> = (0..=divisions)
+            .map(|i| self.point_at(self.start_angle + sweep * i as f32 / divisions as f32))
+            .collect();
+        connect(&points)
+    }
+}
+
+/// whether `angle` lies on the counter-clockwise sweep from `from` to `to`
+pub fn angle_within(angle: f32, from: f32, to: f32) -> bool {
+    (angle - from).rem_euclid(2.0 * PI) <= (to - from).rem_euclid(2.0 * PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::super::tests::{near, props};
+    use super::{Arc, Point};
+
+    #[test]
+    fn through_points_runs_counter_clockwise_either_way() {
+        let (a, b, c) = (
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(-1.0, 0.0),
+        );
+        for arc in [
+            Arc::through_points(a, b, c, props()).unwrap(),
+            Arc::through_points(c, b, a, props()).unwrap(),
+        ] {
+            assert!(near(arc.center, Point::new(0.0, 0.0)));
+            assert!((arc.radius - 1.0).abs() < 1e-5);
+            assert!((arc.sweep() - PI).abs() < 1e-5);
+            assert!(near(arc.point_at(arc.start_angle + FRAC_PI_2), b));
+        }
+    }
+
+    #[test]
+    fn through_points_in_a_row_make_no_arc() {
+        let arc = Arc::through_points(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            props(),
+        );
+        assert_eq!(arc, None);
+    }
+
+    #[test]
+    fn start_center_end_needs_a_radius() {
+        let (start, center) = (Point::new(1.0, 0.0), Point::new(0.0, 0.0));
+        assert_eq!(Arc::start_center_end(center, center, start, props()), None);
+        assert_eq!(Arc::start_center_end(start, center, center, props()), None);
+        let arc = Arc::start_center_end(start, center, Point::new(0.0, 3.0), props()).unwrap();
+        assert!(near(arc.end_point(), Point::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn start_end_radius_picks_the_arc_by_sign() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let small = Arc::start_end_radius(start, end, 2.0, props()).unwrap();
+        let large = Arc::start_end_radius(start, end, -2.0, props()).unwrap();
+        assert!(small.sweep() < PI && large.sweep() > PI);
+        for arc in [small, large] {
+            assert!(near(arc.start_point(), start) && near(arc.end_point(), end));
+        }
+    }
+
+    #[test]
+    fn start_end_radius_too_short_or_no_chord() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        assert_eq!(Arc::start_end_radius(start, end, 0.5, props()), None);
+        assert_eq!(Arc::start_end_radius(start, start, 1.0, props()), None);
+    }
+
+    #[test]
+    fn tangent_from_turns_either_way() {
+        let start = Point::new(0.0, 0.0);
+        let heading = Point::new(1.0, 0.0);
+        let left = Arc::tangent_from(start, heading, Point::new(1.0, 1.0), props()).unwrap();
+        assert!(near(left.center, Point::new(0.0, 1.0)));
+        assert!(near(left.start_point(), start));
+        let right = Arc::tangent_from(start, heading, Point::new(1.0, -1.0), props()).unwrap();
+        assert!(near(right.center, Point::new(0.0, -1.0)));
+        assert!(near(right.end_point(), start));
+    }
+
+    #[test]
+    fn tangent_from_straight_ahead_or_nowhere() {
+        let start = Point::new(0.0, 0.0);
+        let ahead = Point::new(3.0, 0.0);
+        let heading = Point::new(1.0, 0.0);
+        assert_eq!(Arc::tangent_from(start, heading, ahead, props()), None);
+        assert_eq!(
+            Arc::tangent_from(start, Point::new(0.0, 0.0), ahead, props()),
+            None
+        );
+    }
+
+    #[test]
+    fn same_start_and_end_is_a_whole_turn() {
+        let arc = Arc::new(Point::new(0.0, 0.0), 1.0, 1.0, 1.0, props());
+        assert!((arc.sweep() - 2.0 * PI).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,81 @@
+use super::{Affine, Text};
+
+/// A field in a block, filled in with a value each time the block is
+/// inserted. Outside of a block it's drawn as its tag.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttDef {
+    pub tag: String,
+    /// what's asked for when the block is inserted
+    pub prompt: String,
+    pub default: String,
+    /// the same in every insert, so never asked for
+    pub constant: bool,
+    /// kept with inserts but not drawn
+    pub invisible: bool,
+    /// the tag, placed and lettered the way values are drawn
+    pub text: Text,
+}
+
+impl AttDef {
+    /// a field tagged with `text`'s content, which is written in capitals
+    /// and can't have spaces in it
+    pub fn new(
+        prompt: &str,
+        default: &str,
+        constant: bool,
+        invisible: bool,
+        text: Text,
+    ) -> Option<Self> {
+        let tag = text.content.trim().to_uppercase();
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            return None;
+        }
+        let prompt = match prompt.trim() {
+            "" => tag.clone(),
+            prompt => prompt.to_string(),
+        };
+        Some(AttDef {
+            text: Text {
+                content: tag.clone(),
+                ..text
+            },
+            tag,
+            prompt,
+            default: default.to_string(),
+            constant,
+            invisible,
+        })
+    }
+    /// `value` written where this field is, unless it's hidden or blank
+    pub fn shown(&self, value: &str) -> Option<Text> {
+        if self.invisible || value.trim().is_empty() {
+            return None;
+        }
+        Some(Text {
+            content: value.to_string(),
+            ..self.text.clone()
+        })
+    }
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        AttDef {
+            text: self.text.transformed(transform),
+            ..self.clone()
+        }
+    }
+}
+
+/// the value an insert gives one of its block's fields
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub tag: String,
+    pub value: String,
+}
+
+impl Attribute {
+    pub fn new(tag: &str, value: &str) -> Self {
+        Attribute {
+            tag: tag.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::cmp::Ordering;
+
+use super::{
+    piece::{pieces_of, split_pieces, Piece},
+    Drawable, Point, Polyline, Properties, Vertex,
+};
+
+/// One side of an edge of the drawing, going from `from` to `to`.
+#[derive(Clone, Copy, Debug)]
+struct HalfEdge {
+    from: usize,
+    to: usize,
+    piece: Piece,
+}
+
+/// an area the edges go around, and the half edges going round it with the
+/// area on their left
+struct Face {
+    edges: Vec<usize>,
+    outline: Vec<Point>,
+    area: f32,
+}
+
+impl Face {
+    fn contains(&self, p: Point) -> bool {
+        let mut inside = false;
+        let count = self.outline.len();
+        for i in 0..count {
+            let (a, b) = (self.outline[i], self.outline[(i + 1) % count]);
+            if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// the node standing for every node joined up with `node`
+fn root(group: &mut [usize], mut node: usize) -> usize {
+    while group[node] != node {
+        group[node] = group[group[node]];
+        node = group[node];
+    }
+    node
+}
+
+/// The area around `pick` enclosed by the lines, arcs and curves of
+/// `drawables`, as closed polylines with `props`: the loop around it first
+/// and then the outlines of any islands inside it. Pieces are joined where
+/// they come within `tolerance` of one another.
+pub fn find_boundary<'a>(
+    drawables: impl Iterator<Item = &'a Drawable>,
+    pick: Point,
+    tolerance: f32,
+    props: Properties,
+) -> Option<Vec<Polyline>> {
+    let pieces: Vec<Piece> = drawables
+        .flat_map(|drawable| pieces_of(drawable, tolerance))
+        .filter(|piece| piece.length() > tolerance)
+        .collect();
+    let pieces = split_pieces(&pieces, tolerance);
+
+    // join up ends that meet into nodes
+    let mut nodes: Vec<Point> = vec![];
+    let mut node_at = |p: Point| match nodes.iter().position(|n| n.distance(p) <= tolerance) {
+        Some(found) => found,
+        None => {
+            nodes.push(p);
+            nodes.len() - 1
+        }
+    };
+    let mut edges: Vec<(usize, usize, Piece)> = vec![];
+    for piece in pieces {
+        let (from, to) = (node_at(piece.from), node_at(piece.to));
+        let middle = piece.point_at(0.5);
+        let repeated = edges.iter().any(|(a, b, other)| {
+            ((*a, *b) == (from, to) || (*a, *b) == (to, from))
+                && other.point_at(0.5).distance(middle) <= tolerance
+        });
+        if from != to && !repeated {
+            edges.push((from, to, piece));
+        }
+    }
+
+    // loose ends can't go around anything
+    loop {
+        let mut degree = vec![0; nodes.len()];
+        for (from, to, _) in edges.iter() {
+            degree[*from] += 1;
+            degree[*to] += 1;
+        }
+        let before = edges.len();
+        edges.retain(|(from, to, _)| degree[*from] > 1 && degree[*to] > 1);
+        if edges.len() == before {
+            break;
+        }
+    }
+    if edges.is_empty() {
+        return None;
+    }
+
+    let half_edges: Vec<HalfEdge> = edges
+        .iter()
+        .flat_map(|&(from, to, piece)| {
+            vec![
+                HalfEdge { from, to, piece },
+                HalfEdge {
+                    from: to,
+                    to: from,
+                    piece: piece.reversed(),
+                },
+            ]
+        })
+        .collect();
+    // the half edges leaving each node, counter-clockwise
+    let mut leaving: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    for (index, half) in half_edges.iter().enumerate() {
+        leaving[half.from].push(index);
+    }
+    for out in leaving.iter_mut() {
+        out.sort_by(|a, b| {
+            let (a, b) = (
+                half_edges[*a].piece.departure(),
+                half_edges[*b].piece.departure(),
+            );
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        });
+    }
+    // the half edge twinned with each, which is always its neighbour
+    let twin = |index: usize| index ^ 1;
+    // turning as far left as possible after arriving along `index`
+    let next = |index: usize| {
+        let out = &leaving[half_edges[index].to];
+        let back = out.iter().position(|o| *o == twin(index)).unwrap_or(0);
+        out[(back + out.len() - 1) % out.len()]
+    };
+
+    let mut faces: Vec<Face> = vec![];
+    let mut seen = vec![false; half_edges.len()];
+    for start in 0..half_edges.len() {
+        if seen[start] {
+            continue;
+        }
+        let mut face_edges = vec![];
+        let mut at = start;
+        while !seen[at] {
+            seen[at] = true;
+            face_edges.push(at);
+            at = next(at);
+        }
+        let outline: Vec<Point> = face_edges
+            .iter()
+            .flat_map(|e| half_edges[*e].piece.points(tolerance))
+            .collect();
+        let count = outline.len();
+        let area = (0..count)
+            .map(|i| outline[i].cross(outline[(i + 1) % count]))
+            .sum::<f32>()
+            / 2.0;
+        faces.push(Face {
+            edges: face_edges,
+            outline,
+            area,
+        });
+    }
+
+    // which nodes are joined to which, to tell islands apart
+    let mut group: Vec<usize> = (0..nodes.len()).collect();
+    for (from, to, _) in edges.iter() {
+        let (a, b) = (root(&mut group, *from), root(&mut group, *to));
+        group[a] = b;
+    }
+
+    let around = faces
+        .iter()
+        .filter(|face| face.area > 0.0 && face.contains(pick))
+        .min_by(|a, b| a.area.partial_cmp(&b.area).unwrap_or(Ordering::Equal))?;
+    let around_group = root(&mut group, half_edges[around.edges[0]].from);
+    let to_polyline = |face: &Face| {
+        let mut vertices: Vec<Vertex> = face
+            .edges
+            .iter()
+            .flat_map(|e| {
+                let piece = half_edges[*e].piece;
+                if face.edges.len() < 3 && piece.bulge != 0.0 {
+                    // a closed polyline needs three corners to join up
+                    let halves = [piece.between(0.0, 0.5), piece.between(0.5, 1.0)];
+                    halves.to_vec()
+                } else {
+                    vec![piece]
+                }
+            })
+            .map(|piece| Vertex {
+                bulge: piece.bulge,
+                ..Vertex::new(piece.from)
+            })
+            .collect();
+        vertices.dedup_by(|a, b| a.position.distance(b.position) <= tolerance);
+        Polyline::new(vertices, true, props)
+    };
+    let mut boundaries = vec![to_polyline(around)];
+    for face in faces.iter().filter(|face| face.area < 0.0) {
+        let face_group = root(&mut group, half_edges[face.edges[0]].from);
+        if face_group != around_group && around.contains(face.outline[0]) {
+            boundaries.push(to_polyline(face));
+        }
+    }
+    Some(boundaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::find_boundary;
+    use crate::components::{Circle, Drawable, Line, Point};
+
+    fn line(ax: f32, ay: f32, bx: f32, by: f32) -> Drawable {
+        Drawable::Line(Line::new(Point::new(ax, ay), Point::new(bx, by), props()))
+    }
+
+    /// a square from (0, 0) to (2, 2) out of lines running past its corners
+    fn crossed_square() -> Vec<Drawable> {
+        vec![
+            line(-1.0, 0.0, 3.0, 0.0),
+            line(-1.0, 2.0, 3.0, 2.0),
+            line(0.0, -1.0, 0.0, 3.0),
+            line(2.0, -1.0, 2.0, 3.0),
+        ]
+    }
+
+    #[test]
+    fn lines_crossing_close_off_a_square() {
+        let drawables = crossed_square();
+        let found = find_boundary(drawables.iter(), Point::new(1.0, 1.0), 1e-3, props()).unwrap();
+        assert_eq!(found.len(), 1);
+        let square = &found[0];
+        assert!(square.closed);
+        assert!((square.length() - 8.0).abs() < 1e-3);
+        for corner in [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)] {
+            let corner = Point::new(corner.0, corner.1);
+            assert!(square.vertices.iter().any(|v| near(v.position, corner)));
+        }
+    }
+
+    #[test]
+    fn nothing_around_a_point_outside() {
+        let drawables = crossed_square();
+        assert!(find_boundary(drawables.iter(), Point::new(5.0, 5.0), 1e-3, props()).is_none());
+    }
+
+    #[test]
+    fn loose_lines_go_around_nothing() {
+        let drawables = [line(0.0, 0.0, 2.0, 0.0), line(2.0, 0.0, 2.0, 2.0)];
+        assert!(find_boundary(drawables.iter(), Point::new(1.0, 1.0), 1e-3, props()).is_none());
+        let none: Vec<Drawable> = vec![];
+        assert!(find_boundary(none.iter(), Point::new(1.0, 1.0), 1e-3, props()).is_none());
+    }
+
+    #[test]
+    fn a_circle_left_standing_inside_is_an_island() {
+        let mut drawables = crossed_square();
+        let circle = Circle::new(Point::new(1.0, 1.0), 0.5, props()).unwrap();
+        drawables.push(Drawable::Circle(circle));
+        let found = find_boundary(drawables.iter(), Point::new(0.2, 0.2), 1e-3, props()).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!((found[1].length() - std::f32::consts::PI).abs() < 1e-3);
+        // and picked inside the circle, the circle is all there is
+        let found = find_boundary(drawables.iter(), Point::new(1.0, 1.0), 1e-3, props()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].vertices.len() >= 3);
+        assert!((found[0].length() - std::f32::consts::PI).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,270 @@
+use super::{arc_divisions, connect, Affine, Drawable, Point, Polyline, Properties, Vertex};
+
+/// how many halvings the numeric solves get to narrow in on an answer
+const SOLVE_STEPS: usize = 100;
+
+/// A cable hanging between two supports under its own weight, the curve
+/// `y = a cosh((x - h) / a) + k`. Solved values are kept in double precision
+/// as `k` gets large and cancels against the cosh for cables which barely sag.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Catenary {
+    pub start: Point,
+    pub end: Point,
+    pub length: f32,
+    pub a: f64,
+    pub h: f64,
+    pub k: f64,
+    pub props: Properties,
+}
+
+impl Catenary {
+    /// the cable of `length` hanging between `start` and `end`, which has to
+    /// be longer than the distance between them
+    pub fn with_length(start: Point, end: Point, length: f32, props: Properties) -> Option<Self> {
+        let (x1, y1, x2, y2) = ordered(start, end);
+        let (dx, dy, length64) = (x2 - x1, y2 - y1, length as f64);
+        if dx <= f64::from(f32::EPSILON) || length64 * length64 <= dx * dx + dy * dy {
+            return None;
+        }
+        let span = (length64 * length64 - dy * dy).sqrt();
+        // the cable's span across shrinks towards dx as a grows, so bracket
+        // the a giving the right span and halve in on it
+        let across = |a: f64| 2.0 * a * (dx / (2.0 * a)).sinh();
+        let mut lo = dx / 1400.0;
+        let mut hi = dx;
+        while across(hi) > span {
+            hi *= 2.0;
+        }
+        for _ in 0..SOLVE_STEPS {
+            let mid = (lo * hi).sqrt();
+            if across(mid) > span {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let a = (lo * hi).sqrt();
+        let h = (x1 + x2) / 2.0 - a * (dy / length64).atanh();
+        let k = y1 - a * ((x1 - h) / a).cosh();
+        Some(Catenary {
+            start,
+            end,
+            length,
+            a,
+            h,
+            k,
+            props,
+        })
+    }
+    /// the cable between `start` and `end` which hangs `sag` below the
+    /// middle of the straight line joining them
+    pub fn with_sag(start: Point, end: Point, sag: f32, props: Properties) -> Option<Self> {
+        if sag <= 0.0 || (end.x - start.x).abs() <= f32::EPSILON {
+            return None;
+        }
+        let chord = start.distance(end);
+        // sag only grows as the cable gets longer, anything too short to hang counting as none
+        let sag_of = |length: f32| {
+            Catenary::with_length(start, end, length, props)
+                .map(|c| c.sag())
+                .unwrap_or(0.0)
+        };
+        let mut lo = chord;
+        let mut hi = chord * 2.0;
+        while sag_of(hi) < sag {
+            hi *= 2.0;
+        }
+        for _ in 0..SOLVE_STEPS {
+            let mid = (lo + hi) / 2.0;
+            if sag_of(mid) < sag {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Catenary::with_length(start, end, (lo + hi) / 2.0, props)
+    }
+    /// how far the middle of the cable hangs below the middle of the chord
+    pub fn sag(&self) -> f32 {
+        let (x1, y1, x2, y2) = ordered(self.start, self.end);
+        let middle = (x1 + x2) / 2.0;
+        ((y1 + y2) / 2.0 - self.y_at(middle)) as f32
+    }
+    fn y_at(&self, x: f64) -> f64 {
+        self.a * ((x - self.h) / self.a).cosh() + self.k
+    }
+    /// the point on the cable where its slope makes angle `phi` with the horizontal
+    fn point_at_slope(&self, phi: f64) -> Point {
+        let x = self.h + self.a * phi.tan().asinh();
+        Point::new(x as f32, (self.a / phi.cos() + self.k) as f32)
+    }
+    fn slope_at(&self, x: f64) -> f64 {
+        ((x - self.h) / self.a).sinh().atan()
+    }
+    /// the bottom of the curve, if it lies between the supports
+    pub fn lowest_point(&self) -> Option<Point> {
+        let (x1, _, x2, _) = ordered(self.start, self.end);
+        if x1 < self.h && self.h < x2 {
+            Some(Point::new(self.h as f32, (self.a + self.k) as f32))
+        } else {
+            None
+        }
+    }
+    /// the point halfway along the cable
+    pub fn midpoint(&self) -> Point {
+        let (x1, _, _, _) = ordered(self.start, self.end);
+        let from = ((x1 - self.h) / self.a).sinh();
+        let x = self.h + self.a * (from + self.length as f64 / (2.0 * self.a)).asinh();
+        Point::new(x as f32, self.y_at(x) as f32)
+    }
+    /// this cable moved by `transform`, which stays a cable as long as it
+    /// still hangs straight down, otherwise becoming a polyline within `tolerance`
+    pub fn transformed(&self, transform: &Affine, tolerance: f32) -> Drawable {
+        let hanging = transform.x.y == 0.0
+            && transform.y.x == 0.0
+            && transform.y.y > 0.0
+            && (transform.x.x.abs() - transform.y.y).abs() <= 1e-5 * transform.y.y;
+        let moved = Catenary::with_length(
+            transform.apply(self.start),
+            transform.apply(self.end),
+            self.length * transform.y.y,
+            self.props,
+        );
+        match moved {
+            Some(catenary) if hanging => Drawable::Catenary(catenary),
+            _ => {
+                let segments = self.segments(tolerance);
+                let mut vertices: Vec<Vertex> = segments
+                    .iter()
+                    .map(|(a, _)| Vertex::new(transform.apply(*a)))
+                    .collect();
+                if let Some((_, b)) = segments.last() {
+                    vertices.push(Vertex::new(transform.apply(*b)));
+                }
+                Drawable::Polyline(Polyline::new(vertices, false, self.props))
+            }
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        // the curve is tightest at its bottom where its radius is a, so
+        // dividing evenly by slope keeps every piece within tolerance
+        let (x1, _, x2, _) = ordered(self.start, self.end);
+        let (from, to) = (self.slope_at(x1), self.slope_at(x2));
+        let divisions = arc_divisions(self.a as f32, (to - from) as f32, tolerance);
+        let (left, right) = supports(self.start, self.end);
+        let mut points = vec![left];
+        points.extend(
+            (1..divisions)
+                .map(|i| self.point_at_slope(from + (to - from) * i as f64 / divisions as f64)),
+        );
+        points.push(right);
+        connect(&points)
+    }
+}
+
+/// the supports left to right
+fn supports(start: Point, end: Point) -> (Point, Point) {
+    if start.x <= end.x {
+        (start, end)
+    } else {
+        (end, start)
+    }
+}
+
+/// the supports left to right, in double precision
+fn ordered(start: Point, end: Point) -> (f64, f64, f64, f64) {
+    let (left, right) = supports(start, end);
+    (left.x as f64, left.y as f64, right.x as f64, right.y as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::{ordered, Catenary};
+    use crate::components::Point;
+
+    /// how long the cable is between its supports, worked out from its curve
+    fn arc_length(catenary: &Catenary) -> f64 {
+        let (x1, _, x2, _) = ordered(catenary.start, catenary.end);
+        let sinh = |x: f64| ((x - catenary.h) / catenary.a).sinh();
+        catenary.a * (sinh(x2) - sinh(x1))
+    }
+
+    /// whether the curve passes through both supports
+    fn hangs_from_its_supports(catenary: &Catenary) -> bool {
+        [catenary.start, catenary.end].iter().all(|support| {
+            let y = catenary.y_at(support.x as f64) as f32;
+            near(Point::new(support.x, y), *support)
+        })
+    }
+
+    #[test]
+    fn level_supports_by_length() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        let cable = Catenary::with_length(start, end, 12.0, props()).unwrap();
+        assert!((arc_length(&cable) - 12.0).abs() < 1e-3);
+        assert!(hangs_from_its_supports(&cable));
+        // hanging evenly, its bottom is halfway across
+        let bottom = cable.lowest_point().unwrap();
+        assert!((bottom.x - 5.0).abs() < 1e-3);
+        assert!((cable.sag() + bottom.y).abs() < 1e-3);
+    }
+
+    #[test]
+    fn uneven_supports_by_length() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(10.0, 4.0));
+        let cable = Catenary::with_length(start, end, 13.0, props()).unwrap();
+        assert!((arc_length(&cable) - 13.0).abs() < 1e-3);
+        assert!(hangs_from_its_supports(&cable));
+        // the same cable whichever support is given first
+        let swapped = Catenary::with_length(end, start, 13.0, props()).unwrap();
+        assert!((swapped.a - cable.a).abs() < 1e-6 && (swapped.h - cable.h).abs() < 1e-6);
+    }
+
+    #[test]
+    fn barely_sagging_cable_still_hangs_from_its_supports() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(100.0, 0.0));
+        let cable = Catenary::with_length(start, end, 100.01, props()).unwrap();
+        assert!(hangs_from_its_supports(&cable));
+        assert!(cable.sag() > 0.0);
+    }
+
+    #[test]
+    fn too_short_to_reach_is_no_cable() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        assert_eq!(Catenary::with_length(start, end, 10.0, props()), None);
+        assert_eq!(Catenary::with_length(start, end, 5.0, props()), None);
+    }
+
+    #[test]
+    fn level_supports_by_sag() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        let cable = Catenary::with_sag(start, end, 2.0, props()).unwrap();
+        assert!((cable.sag() - 2.0).abs() < 1e-3);
+        assert!((arc_length(&cable) - cable.length as f64).abs() < 1e-3);
+        assert!(hangs_from_its_supports(&cable));
+    }
+
+    #[test]
+    fn uneven_supports_by_sag() {
+        let (start, end) = (Point::new(-3.0, 5.0), Point::new(7.0, 1.0));
+        let cable = Catenary::with_sag(start, end, 1.5, props()).unwrap();
+        assert!((cable.sag() - 1.5).abs() < 1e-3);
+        assert!((arc_length(&cable) - cable.length as f64).abs() < 1e-3);
+        assert!(hangs_from_its_supports(&cable));
+    }
+
+    #[test]
+    fn supports_one_above_the_other_cant_sag() {
+        let (start, end) = (Point::new(2.0, 0.0), Point::new(2.0, 5.0));
+        assert_eq!(Catenary::with_sag(start, end, 1.0, props()), None);
+        assert_eq!(Catenary::with_length(start, end, 8.0, props()), None);
+    }
+
+    #[test]
+    fn no_sag_is_no_cable() {
+        let (start, end) = (Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        assert_eq!(Catenary::with_sag(start, end, 0.0, props()), None);
+        assert_eq!(Catenary::with_sag(start, end, -1.0, props()), None);
+    }
+}
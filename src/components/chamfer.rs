@@ -0,0 +1,186 @@
+use super::{Drawable, Line, Point, Primitive, Properties};
+
+/// How far back from a corner a chamfer cuts, along the first side picked
+/// and then the second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Chamfer {
+    Distances(f32, f32),
+    /// a distance along the first side and the angle the cut makes with
+    /// it, in radians
+    DistanceAngle(f32, f32),
+}
+
+impl Chamfer {
+    /// how far back along either side the cut starts at a corner whose
+    /// sides open out at `opening`
+    pub fn setbacks(&self, opening: f32) -> Option<(f32, f32)> {
+        match *self {
+            Chamfer::Distances(first, second) => Some((first, second)),
+            Chamfer::DistanceAngle(distance, angle) => {
+                // the far angle of the triangle cut off, which has to be
+                // left over for the cut to meet the second side
+                let far = (opening + angle).sin();
+                if opening + angle >= std::f32::consts::PI || far <= f32::EPSILON {
+                    return None;
+                }
+                Some((distance, distance * angle.sin() / far))
+            }
+        }
+    }
+}
+
+/// The corner where two lines meet cut off by `cut`, each line keeping the
+/// side it was picked on. Gives the lines trimmed back to the cut, or left
+/// as they are without `trim`, and the line cutting the corner if it has
+/// any length.
+pub fn chamfer(
+    first: &Line,
+    first_pick: Point,
+    second: &Line,
+    second_pick: Point,
+    cut: Chamfer,
+    trim: bool,
+    props: Properties,
+) -> Option<(Line, Line, Option<Line>)> {
+    let (first, second) = (Drawable::Line(*first), Drawable::Line(*second));
+    let (a, b) = (Primitive::of(&first)?, Primitive::of(&second)?);
+    let corner = *a.intersect(&b).first()?;
+    let along_first = (a.closest_point(first_pick) - corner).normalize()?;
+    let along_second = (b.closest_point(second_pick) - corner).normalize()?;
+    let opening = along_first
+        .cross(along_second)
+        .atan2(along_first.dot(along_second))
+        .abs();
+    let (first_setback, second_setback) = cut.setbacks(opening)?;
+    if first_setback < 0.0 || second_setback < 0.0 {
+        return None;
+    }
+    let first_end = corner + along_first * first_setback;
+    let second_end = corner + along_second * second_setback;
+    let (first, second) = if trim {
+        (
+            first.trimmed_to(first_end, first_end + along_first)?,
+            second.trimmed_to(second_end, second_end + along_second)?,
+        )
+    } else {
+        (first, second)
+    };
+    let edge = if first_end.distance(second_end) > f32::EPSILON {
+        Some(Line::new(first_end, second_end, props))
+    } else {
+        None
+    };
+    match (first, second) {
+        (Drawable::Line(first), Drawable::Line(second)) => Some((first, second, edge)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::super::tests::{near, polyline, props};
+    use super::{chamfer, Chamfer};
+    use crate::components::{Line, Point};
+
+    #[test]
+    fn distances_are_taken_as_they_are() {
+        let cut = Chamfer::Distances(1.0, 2.0);
+        assert_eq!(cut.setbacks(FRAC_PI_2), Some((1.0, 2.0)));
+    }
+
+    #[test]
+    fn distance_and_angle_at_a_square_corner() {
+        let (first, second) = Chamfer::DistanceAngle(1.0, FRAC_PI_4)
+            .setbacks(FRAC_PI_2)
+            .unwrap();
+        assert!((first - 1.0).abs() < 1e-5 && (second - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn an_angle_that_never_meets_the_second_side() {
+        let cut = Chamfer::DistanceAngle(1.0, FRAC_PI_2);
+        assert_eq!(cut.setbacks(FRAC_PI_2), None);
+        assert_eq!(cut.setbacks(PI), None);
+    }
+
+    fn square_corner(trim: bool) -> Option<(Line, Line, Option<Line>)> {
+        let first = Line::new(Point::new(0.0, 0.0), Point::new(4.0, 0.0), props());
+        let second = Line::new(Point::new(0.0, 0.0), Point::new(0.0, 4.0), props());
+        chamfer(
+            &first,
+            Point::new(3.0, 0.0),
+            &second,
+            Point::new(0.0, 3.0),
+            Chamfer::Distances(1.0, 2.0),
+            trim,
+            props(),
+        )
+    }
+
+    #[test]
+    fn lines_are_cut_back_to_the_chamfer() {
+        let (first, second, edge) = square_corner(true).unwrap();
+        assert!(near(first.start, Point::new(1.0, 0.0)));
+        assert!(near(second.start, Point::new(0.0, 2.0)));
+        let edge = edge.unwrap();
+        assert!(near(edge.start, Point::new(1.0, 0.0)) && near(edge.end, Point::new(0.0, 2.0)));
+    }
+
+    #[test]
+    fn without_trimming_the_lines_stay_whole() {
+        let (first, second, edge) = square_corner(false).unwrap();
+        assert!(near(first.start, Point::new(0.0, 0.0)));
+        assert!(near(second.start, Point::new(0.0, 0.0)));
+        assert!(edge.is_some());
+    }
+
+    #[test]
+    fn parallel_lines_have_no_corner() {
+        let first = Line::new(Point::new(0.0, 0.0), Point::new(4.0, 0.0), props());
+        let second = Line::new(Point::new(0.0, 2.0), Point::new(4.0, 2.0), props());
+        let cut = Chamfer::Distances(1.0, 1.0);
+        let (pick_first, pick_second) = (Point::new(3.0, 0.0), Point::new(3.0, 2.0));
+        assert!(chamfer(&first, pick_first, &second, pick_second, cut, true, props()).is_none());
+    }
+
+    #[test]
+    fn zero_distances_leave_no_edge() {
+        let first = Line::new(Point::new(1.0, 0.0), Point::new(4.0, 0.0), props());
+        let second = Line::new(Point::new(0.0, 1.0), Point::new(0.0, 4.0), props());
+        let cut = Chamfer::Distances(0.0, 0.0);
+        let (pick_first, pick_second) = (Point::new(3.0, 0.0), Point::new(0.0, 3.0));
+        let (first, second, edge) =
+            chamfer(&first, pick_first, &second, pick_second, cut, true, props()).unwrap();
+        assert!(near(first.start, Point::new(0.0, 0.0)));
+        assert!(near(second.start, Point::new(0.0, 0.0)));
+        assert_eq!(edge, None);
+    }
+
+    #[test]
+    fn polyline_corners_are_cut_or_given_as_edges() {
+        let square = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], true);
+        let cut = Chamfer::Distances(1.0, 1.0);
+        let (trimmed, edges, uncut) = square.chamfered(cut, true);
+        assert_eq!((trimmed.vertices.len(), edges.len(), uncut), (8, 0, 0));
+        let (whole, edges, uncut) = square.chamfered(cut, false);
+        assert_eq!(whole, square);
+        assert_eq!((edges.len(), uncut), (4, 0));
+        assert!(edges
+            .iter()
+            .all(|(a, b)| (a.distance(*b) - 2f32.sqrt()).abs() < 1e-4));
+    }
+
+    #[test]
+    fn one_polyline_corner_between_two_spans() {
+        let open = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)], false);
+        let cut = Chamfer::Distances(1.0, 2.0);
+        let (trimmed, edge) = open.chamfered_between(0, 1, cut, true).unwrap();
+        assert_eq!((trimmed.vertices.len(), edge), (4, None));
+        let (whole, edge) = open.chamfered_between(0, 1, cut, false).unwrap();
+        assert_eq!(whole, open);
+        let (from, to) = edge.unwrap();
+        assert!(near(from, Point::new(3.0, 0.0)) && near(to, Point::new(4.0, 2.0)));
+    }
+}
@@ -0,0 +1,82 @@
+use std::f32::consts::PI;
+
+use super::{
+    arc_divisions, circumcenter, connect, Affine, Drawable, Ellipse, Point, Primitive, Properties,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f32,
+    pub props: Properties,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f32, props: Properties) -> Option<Self> {
+        if radius <= f32::EPSILON {
+            return None;
+        }
+        Some(Circle {
+            center,
+            radius,
+            props,
+        })
+    }
+    /// the circle with `a` and `b` at opposite ends of a diameter
+    pub fn two_point(a: Point, b: Point, props: Properties) -> Option<Self> {
+        Circle::new((a + b) * 0.5, a.distance(b) / 2.0, props)
+    }
+    pub fn three_point(a: Point, b: Point, c: Point, props: Properties) -> Option<Self> {
+        let center = circumcenter(a, b, c)?;
+        Circle::new(center, center.distance(a), props)
+    }
+    /// the circle of `radius` touching both shapes, choosing the solution whose
+    /// points of contact are nearest to where each shape was picked
+    pub fn tangent_tangent_radius(
+        first: Primitive,
+        first_pick: Point,
+        second: Primitive,
+        second_pick: Point,
+        radius: f32,
+        props: Properties,
+    ) -> Option<Self> {
+        let mut best: Option<(f32, Point)> = None;
+        for a in first.offsets(radius) {
+            for b in second.offsets(radius) {
+                for center in a.intersect(&b) {
+                    let miss = first.closest_point(center).distance(first_pick)
+                        + second.closest_point(center).distance(second_pick);
+                    if best.map(|(least, _)| miss < least).unwrap_or(true) {
+                        best = Some((miss, center));
+                    }
+                }
+            }
+        }
+        Circle::new(best?.1, radius, props)
+    }
+    /// this circle moved by `transform`, becoming an ellipse if the
+    /// transform doesn't keep its shape
+    pub fn transformed(&self, transform: &Affine) -> Drawable {
+        match transform.uniform_scale() {
+            Some(scale) => Drawable::Circle(Circle {
+                center: transform.apply(self.center),
+                radius: self.radius * scale,
+                props: self.props,
+            }),
+            None => Drawable::Ellipse(
+                Ellipse::circular(self.center, self.radius, 0.0, 2.0 * PI, self.props)
+                    .transformed(transform),
+            ),
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let divisions = arc_divisions(self.radius, 2.0 * PI, tolerance);
+        let points: Vec<Point> = (0..=divisions)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / divisions as f32;
+                self.center + Point::from_angle(angle) * self.radius
+            })
+            .collect();
+        connect(&points)
+    }
+}
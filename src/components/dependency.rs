@@ -0,0 +1,52 @@
+use specs::prelude::*;
+
+use super::{Drawable, Point};
+
+/// which part of a source drawable a dependent follows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+    Start,
+    End,
+    Center,
+    Whole,
+}
+
+/// one source a dependent follows, and which part of it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Link {
+    pub source: Entity,
+    pub feature: Feature,
+}
+
+impl Link {
+    pub fn new(source: Entity, feature: Feature) -> Self {
+        Link { source, feature }
+    }
+}
+
+/// The sources a drawable was derived from, in the order its `follow`
+/// expects them. When any source changes the drawable is worked out again
+/// from all of them, and once a source is erased the drawable lets go of
+/// them all and stays where it was. Links are only given to drawables as
+/// they're made, so nothing can end up following itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependent {
+    pub links: Vec<Link>,
+}
+
+impl Component for Dependent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Dependent {
+    pub fn new(links: Vec<Link>) -> Self {
+        Dependent { links }
+    }
+}
+
+/// what a dependent is given of each of its sources
+#[derive(Clone, Debug, PartialEq)]
+pub enum Anchor {
+    Point(Point),
+    Shape(Box<Drawable>),
+}
@@ -0,0 +1,921 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use super::{
+    arc::angle_within, Affine, Anchor, Arc, Drawable, Horizontal, Justify, Lettering, Point,
+    Primitive, Properties, Text, Vertical,
+};
+use crate::{
+    common::GenerationID,
+    resources::{DimStyle, Units},
+};
+
+/// arrows go outside the extension lines when they're closer together
+/// than this many arrow lengths
+const ARROWS_INSIDE: f32 = 2.5;
+
+/// How a dimension is drawn, copied from its dimension style the way text
+/// copies its lettering.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DimLook {
+    pub style: GenerationID<DimStyle>,
+    pub arrow_size: f32,
+    pub text_height: f32,
+    pub text_gap: f32,
+    pub extension_offset: f32,
+    pub extension_beyond: f32,
+    pub lettering: Lettering,
+}
+
+impl DimLook {
+    /// the look of dimensions in the style `id`, lettered with `lettering`
+    pub fn from_style(id: GenerationID<DimStyle>, style: &DimStyle, lettering: Lettering) -> Self {
+        DimLook {
+            style: id,
+            arrow_size: style.arrow_size,
+            text_height: style.text_height,
+            text_gap: style.text_gap,
+            extension_offset: style.extension_offset,
+            extension_beyond: style.extension_beyond,
+            lettering,
+        }
+    }
+}
+
+/// what a dimension measures
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Measure {
+    /// the distance between two points along the direction at `rotation`,
+    /// which is usually horizontal or vertical
+    Linear {
+        first: Point,
+        second: Point,
+        rotation: f32,
+    },
+    /// the straight distance between two points
+    Aligned {
+        first: Point,
+        second: Point,
+    },
+    /// the angle swept counter-clockwise around `vertex` from `first` to
+    /// `second`, or the rest of the turn if the dimension arc is placed outside it
+    Angular {
+        vertex: Point,
+        first: Point,
+        second: Point,
+    },
+    Radius {
+        center: Point,
+        radius: f32,
+    },
+    Diameter {
+        center: Point,
+        radius: f32,
+    },
+    /// the length along an arc
+    ArcLength {
+        center: Point,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+    },
+    /// how far `feature` is across from `origin`, or up from it when not `along_x`
+    Ordinate {
+        origin: Point,
+        feature: Point,
+        along_x: bool,
+    },
+    /// a radius too big to draw from its center, drawn from `origin`
+    /// instead with a jog at `jog`
+    JoggedRadius {
+        center: Point,
+        radius: f32,
+        origin: Point,
+        jog: Point,
+    },
+}
+
+impl Measure {
+    fn transformed(&self, transform: &Affine) -> Self {
+        let scale = transform
+            .uniform_scale()
+            .unwrap_or_else(|| transform.determinant().abs().sqrt());
+        match *self {
+            Measure::Linear {
+                first,
+                second,
+                rotation,
+            } => Measure::Linear {
+                first: transform.apply(first),
+                second: transform.apply(second),
+                rotation: transform.apply_vector(Point::from_angle(rotation)).angle(),
+            },
+            Measure::Aligned { first, second } => Measure::Aligned {
+                first: transform.apply(first),
+                second: transform.apply(second),
+            },
+            // flipped over, the sweep from first to second runs the other way
+            Measure::Angular {
+                vertex,
+                first,
+                second,
+            } if transform.determinant() < 0.0 => Measure::Angular {
+                vertex: transform.apply(vertex),
+                first: transform.apply(second),
+                second: transform.apply(first),
+            },
+            Measure::Angular {
+                vertex,
+                first,
+                second,
+            } => Measure::Angular {
+                vertex: transform.apply(vertex),
+                first: transform.apply(first),
+                second: transform.apply(second),
+            },
+            Measure::Radius { center, radius } => Measure::Radius {
+                center: transform.apply(center),
+                radius: radius * scale,
+            },
+            Measure::Diameter { center, radius } => Measure::Diameter {
+                center: transform.apply(center),
+                radius: radius * scale,
+            },
+            Measure::ArcLength {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                let moved_center = transform.apply(center);
+                let moved = |angle: f32| {
+                    (transform.apply(center + Point::from_angle(angle) * radius) - moved_center)
+                        .angle()
+                };
+                let (start_angle, end_angle) = if transform.determinant() < 0.0 {
+                    (moved(end_angle), moved(start_angle))
+                } else {
+                    (moved(start_angle), moved(end_angle))
+                };
+                Measure::ArcLength {
+                    center: moved_center,
+                    radius: radius * scale,
+                    start_angle,
+                    end_angle,
+                }
+            }
+            Measure::Ordinate {
+                origin,
+                feature,
+                along_x,
+            } => Measure::Ordinate {
+                origin: transform.apply(origin),
+                feature: transform.apply(feature),
+                along_x,
+            },
+            Measure::JoggedRadius {
+                center,
+                radius,
+                origin,
+                jog,
+            } => Measure::JoggedRadius {
+                center: transform.apply(center),
+                radius: radius * scale,
+                origin: transform.apply(origin),
+                jog: transform.apply(jog),
+            },
+        }
+    }
+}
+
+/// A measurement written on the drawing, with extension lines out from
+/// what's measured to a dimension line carrying arrows and the value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dimension {
+    pub measure: Measure,
+    /// a point the dimension line passes through, or for radial and
+    /// ordinate dimensions where the text goes
+    pub location: Point,
+    pub look: DimLook,
+    pub units: Units,
+    pub props: Properties,
+}
+
+impl Dimension {
+    /// the dimension of `measure` placed by `location`, if there's
+    /// anything to measure
+    pub fn new(
+        measure: Measure,
+        location: Point,
+        look: DimLook,
+        units: Units,
+        props: Properties,
+    ) -> Option<Self> {
+        let dimension = Dimension {
+            measure,
+            location,
+            look,
+            units,
+            props,
+        };
+        let empty = match measure {
+            Measure::Ordinate { .. } => false,
+            _ => dimension.measurement() <= f32::EPSILON,
+        };
+        if empty {
+            None
+        } else {
+            Some(dimension)
+        }
+    }
+    /// which way a linear or aligned measurement runs
+    fn direction(&self) -> Option<Point> {
+        match self.measure {
+            Measure::Linear { rotation, .. } => Some(Point::from_angle(rotation)),
+            Measure::Aligned { first, second } => (second - first).normalize(),
+            _ => None,
+        }
+    }
+    /// the angle the dimension arc of an angular dimension starts at and
+    /// how far it sweeps, taking in wherever the arc was placed
+    fn sweep(&self, vertex: Point, first: Point, second: Point) -> (f32, f32) {
+        let (from, to) = ((first - vertex).angle(), (second - vertex).angle());
+        if angle_within((self.location - vertex).angle(), from, to) {
+            (from, (to - from).rem_euclid(2.0 * PI))
+        } else {
+            (to, (from - to).rem_euclid(2.0 * PI))
+        }
+    }
+    pub fn measurement(&self) -> f32 {
+        match self.measure {
+            Measure::Linear { first, second, .. } | Measure::Aligned { first, second } => {
+                match self.direction() {
+                    Some(direction) => (second - first).dot(direction).abs(),
+                    None => 0.0,
+                }
+            }
+            Measure::Angular {
+                vertex,
+                first,
+                second,
+            } => self.sweep(vertex, first, second).1,
+            Measure::Radius { radius, .. } | Measure::JoggedRadius { radius, .. } => radius,
+            Measure::Diameter { radius, .. } => 2.0 * radius,
+            Measure::ArcLength {
+                radius,
+                start_angle,
+                end_angle,
+                ..
+            } => radius * (end_angle - start_angle).rem_euclid(2.0 * PI),
+            Measure::Ordinate {
+                origin,
+                feature,
+                along_x,
+            } => {
+                if along_x {
+                    (feature.x - origin.x).abs()
+                } else {
+                    (feature.y - origin.y).abs()
+                }
+            }
+        }
+    }
+    /// the measurement written in the drawing's units, marked with what
+    /// kind of measurement it is
+    pub fn label(&self) -> String {
+        let value = self.measurement();
+        match self.measure {
+            Measure::Angular { .. } => self.units.format_angle(value),
+            Measure::Radius { .. } | Measure::JoggedRadius { .. } => {
+                format!("R{}", self.units.format(value))
+            }
+            Measure::Diameter { .. } => format!("\u{2300}{}", self.units.format(value)),
+            Measure::ArcLength { .. } => format!("\u{2312}{}", self.units.format(value)),
+            _ => self.units.format(value),
+        }
+    }
+    /// where the dimension line of a linear or aligned dimension meets
+    /// each extension line
+    fn feet(&self) -> Option<(Point, Point)> {
+        let (first, second) = match self.measure {
+            Measure::Linear { first, second, .. } | Measure::Aligned { first, second } => {
+                (first, second)
+            }
+            _ => return None,
+        };
+        let across = self.direction()?.perp();
+        Some((
+            first + across * (self.location - first).dot(across),
+            second + across * (self.location - second).dot(across),
+        ))
+    }
+    /// which way from `center` the dimension line of a radial dimension runs
+    fn radial(&self, center: Point) -> Point {
+        (self.location - center)
+            .normalize()
+            .unwrap_or_else(|| Point::new(1.0, 0.0))
+    }
+    /// the extension lines, dimension line and arrowheads, arcs staying
+    /// within `tolerance` of their true shape
+    pub fn lines(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let arrow = self.look.arrow_size;
+        match self.measure {
+            Measure::Linear { first, second, .. } | Measure::Aligned { first, second } => {
+                self.linear_lines(first, second)
+            }
+            Measure::Angular {
+                vertex,
+                first,
+                second,
+            } => {
+                let (start, sweep) = self.sweep(vertex, first, second);
+                let radius = self.location.distance(vertex);
+                let mut lines = vec![];
+                for p in [first, second].iter() {
+                    let reach = p.distance(vertex);
+                    if let Some(out) = (*p - vertex).normalize() {
+                        if radius > reach + self.look.extension_offset {
+                            lines.push((
+                                vertex + out * (reach + self.look.extension_offset),
+                                vertex + out * (radius + self.look.extension_beyond),
+                            ));
+                        }
+                    }
+                }
+                lines.extend(self.dimension_arc(vertex, radius, start, sweep, tolerance));
+                lines
+            }
+            Measure::Radius { center, radius } => {
+                let out = self.radial(center);
+                let tip = center + out * radius;
+                if self.location.distance(center) > radius {
+                    let mut lines = vec![(tip, self.location)];
+                    lines.extend(arrowhead(tip, out * -1.0, arrow));
+                    lines
+                } else {
+                    let mut lines = vec![(center, tip)];
+                    lines.extend(arrowhead(tip, out, arrow));
+                    lines
+                }
+            }
+            Measure::Diameter { center, radius } => {
+                let out = self.radial(center);
+                let (near, far) = (center + out * radius, center - out * radius);
+                let end = if self.location.distance(center) > radius {
+                    self.location
+                } else {
+                    near
+                };
+                let mut lines = vec![(far, end)];
+                lines.extend(arrowhead(near, out, arrow));
+                lines.extend(arrowhead(far, out * -1.0, arrow));
+                lines
+            }
+            Measure::ArcLength {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                let placed = self.location.distance(center);
+                let mut lines = vec![];
+                for angle in [start_angle, end_angle].iter() {
+                    let out = Point::from_angle(*angle);
+                    if placed > radius {
+                        lines.push((
+                            center + out * (radius + self.look.extension_offset),
+                            center + out * (placed + self.look.extension_beyond),
+                        ));
+                    } else {
+                        lines.push((
+                            center + out * (radius - self.look.extension_offset),
+                            center + out * (placed - self.look.extension_beyond).max(0.0),
+                        ));
+                    }
+                }
+                let sweep = (end_angle - start_angle).rem_euclid(2.0 * PI);
+                lines.extend(self.dimension_arc(center, placed, start_angle, sweep, tolerance));
+                lines
+            }
+            Measure::Ordinate {
+                feature, along_x, ..
+            } => {
+                let axis = if along_x {
+                    Point::new(0.0, 1.0)
+                } else {
+                    Point::new(1.0, 0.0)
+                };
+                let reach = (self.location - feature).dot(axis);
+                let lead = if reach >= 0.0 { axis } else { axis * -1.0 };
+                let reach = reach.abs();
+                let start = feature + lead * self.look.extension_offset;
+                let aside = (self.location - feature) - lead * reach;
+                if aside.length() <= f32::EPSILON
+                    || reach < self.look.extension_offset + 2.0 * arrow
+                {
+                    return vec![(start, self.location)];
+                }
+                // jog across partway so the text can sit clear of its neighbours
+                let bend = feature + lead * (reach - 2.0 * arrow);
+                let rejoin = bend + aside + lead * arrow;
+                vec![(start, bend), (bend, rejoin), (rejoin, self.location)]
+            }
+            Measure::JoggedRadius {
+                center,
+                radius,
+                origin,
+                jog,
+            } => {
+                let out = self.radial(center);
+                let tip = center + out * radius;
+                let parallel = origin + out * (jog - origin).dot(out);
+                let radial = center + out * ((jog - center).dot(out) + arrow);
+                let mut lines = vec![(origin, parallel), (parallel, radial), (radial, tip)];
+                lines.extend(arrowhead(tip, out, arrow));
+                if self.location.distance(center) > radius {
+                    lines.push((tip, self.location));
+                }
+                lines
+            }
+        }
+    }
+    fn linear_lines(&self, first: Point, second: Point) -> Vec<(Point, Point)> {
+        let (a, b) = match self.feet() {
+            Some(feet) => feet,
+            None => return vec![],
+        };
+        let mut lines = vec![];
+        for (origin, foot) in [(first, a), (second, b)].iter() {
+            if let Some(out) = (*foot - *origin).normalize() {
+                lines.push((
+                    *origin + out * self.look.extension_offset,
+                    *foot + out * self.look.extension_beyond,
+                ));
+            }
+        }
+        let along = match (b - a).normalize() {
+            Some(along) => along,
+            None => return lines,
+        };
+        let arrow = self.look.arrow_size;
+        if a.distance(b) >= ARROWS_INSIDE * arrow {
+            lines.push((a, b));
+            lines.extend(arrowhead(a, along * -1.0, arrow));
+            lines.extend(arrowhead(b, along, arrow));
+        } else {
+            // too tight for the arrows, so they point in from outside
+            lines.push((a - along * (2.0 * arrow), b + along * (2.0 * arrow)));
+            lines.extend(arrowhead(a, along, arrow));
+            lines.extend(arrowhead(b, along * -1.0, arrow));
+        }
+        lines
+    }
+    /// an arc around `center` with arrows at either end pointing out along it
+    fn dimension_arc(
+        &self,
+        center: Point,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+        tolerance: f32,
+    ) -> Vec<(Point, Point)> {
+        let arc = Arc::new(center, radius, start, start + sweep, self.props);
+        let mut lines = arc.segments(tolerance);
+        let arrow = self.look.arrow_size;
+        lines.extend(arrowhead(
+            arc.start_point(),
+            Point::from_angle(start).perp() * -1.0,
+            arrow,
+        ));
+        lines.extend(arrowhead(
+            arc.point_at(start + sweep),
+            Point::from_angle(start + sweep).perp(),
+            arrow,
+        ));
+        lines
+    }
+    /// the measurement written where it belongs for the kind of dimension,
+    /// turned whichever way keeps it reading left to right or upwards
+    pub fn text(&self) -> Option<Text> {
+        let label = self.label();
+        let gap = self.look.text_gap;
+        let place = |position: Point, rotation: f32, horizontal: Horizontal, vertical: Vertical| {
+            Text::new(
+                &label,
+                position,
+                self.look.text_height,
+                rotation,
+                Justify::new(horizontal, vertical),
+                self.look.lettering,
+                self.props,
+            )
+        };
+        // text on the outside of the middle of a dimension arc
+        let around = |center: Point, radius: f32, middle: f32| {
+            let out = Point::from_angle(middle);
+            let rotation = readable(out.perp().angle());
+            let vertical = if Point::from_angle(rotation).perp().dot(out) >= 0.0 {
+                Vertical::Bottom
+            } else {
+                Vertical::Top
+            };
+            place(
+                center + out * (radius + gap),
+                rotation,
+                Horizontal::Center,
+                vertical,
+            )
+        };
+        // level text beside the end of a radial dimension line
+        let beside = |center: Point| {
+            if self.radial(center).x >= 0.0 {
+                let at = self.location + Point::new(gap, 0.0);
+                place(at, 0.0, Horizontal::Left, Vertical::Middle)
+            } else {
+                let at = self.location - Point::new(gap, 0.0);
+                place(at, 0.0, Horizontal::Right, Vertical::Middle)
+            }
+        };
+        match self.measure {
+            Measure::Linear { .. } | Measure::Aligned { .. } => {
+                let (a, b) = self.feet()?;
+                let rotation = readable((b - a).angle());
+                let up = Point::from_angle(rotation).perp();
+                place(
+                    (a + b) * 0.5 + up * gap,
+                    rotation,
+                    Horizontal::Center,
+                    Vertical::Bottom,
+                )
+            }
+            Measure::Angular {
+                vertex,
+                first,
+                second,
+            } => {
+                let (start, sweep) = self.sweep(vertex, first, second);
+                around(vertex, self.location.distance(vertex), start + sweep / 2.0)
+            }
+            Measure::ArcLength {
+                center,
+                start_angle,
+                end_angle,
+                ..
+            } => {
+                let sweep = (end_angle - start_angle).rem_euclid(2.0 * PI);
+                around(
+                    center,
+                    self.location.distance(center),
+                    start_angle + sweep / 2.0,
+                )
+            }
+            Measure::Radius { center, .. }
+            | Measure::Diameter { center, .. }
+            | Measure::JoggedRadius { center, .. } => beside(center),
+            Measure::Ordinate {
+                feature, along_x, ..
+            } => {
+                let (rotation, axis) = if along_x {
+                    (FRAC_PI_2, Point::new(0.0, 1.0))
+                } else {
+                    (0.0, Point::new(1.0, 0.0))
+                };
+                if (self.location - feature).dot(axis) >= 0.0 {
+                    let at = self.location + axis * gap;
+                    place(at, rotation, Horizontal::Left, Vertical::Middle)
+                } else {
+                    let at = self.location - axis * gap;
+                    place(at, rotation, Horizontal::Right, Vertical::Middle)
+                }
+            }
+        }
+    }
+    pub fn snap_points(&self) -> Vec<Point> {
+        match self.measure {
+            Measure::Linear { first, second, .. } | Measure::Aligned { first, second } => {
+                let mut points = vec![first, second];
+                if let Some((a, b)) = self.feet() {
+                    points.extend(&[a, b, (a + b) * 0.5]);
+                }
+                points
+            }
+            Measure::Angular { vertex, .. } => vec![vertex, self.location],
+            Measure::Radius { center, radius }
+            | Measure::Diameter { center, radius }
+            | Measure::ArcLength { center, radius, .. } => {
+                vec![center, center + self.radial(center) * radius]
+            }
+            Measure::Ordinate { feature, .. } => vec![feature, self.location],
+            Measure::JoggedRadius {
+                center,
+                radius,
+                origin,
+                ..
+            } => vec![origin, center + self.radial(center) * radius],
+        }
+    }
+    /// this dimension measuring its sources where they are now, false if
+    /// they no longer fit what it measures
+    pub fn follow(&mut self, anchors: &[Anchor]) -> bool {
+        match self.followed(anchors) {
+            Some((measure, location)) => {
+                self.measure = measure;
+                self.location = location;
+                true
+            }
+            None => false,
+        }
+    }
+    /// what's measured now and where the dimension line goes, keeping its
+    /// place beside what it measures
+    fn followed(&self, anchors: &[Anchor]) -> Option<(Measure, Point)> {
+        let round = |anchor: &Anchor| match anchor {
+            Anchor::Shape(shape) => match Primitive::of(shape)? {
+                Primitive::Circle { center, radius } => Some((center, radius)),
+                Primitive::Line { .. } => None,
+            },
+            Anchor::Point(_) => None,
+        };
+        let location = self.location;
+        match (self.measure, anchors) {
+            (
+                Measure::Linear {
+                    first,
+                    second,
+                    rotation,
+                },
+                [Anchor::Point(a), Anchor::Point(b)],
+            ) => {
+                let moved = (*a + *b - first - second) * 0.5;
+                let measure = Measure::Linear {
+                    first: *a,
+                    second: *b,
+                    rotation,
+                };
+                Some((measure, location + moved))
+            }
+            (Measure::Aligned { first, second }, [Anchor::Point(a), Anchor::Point(b)]) => {
+                let aside = (location - first).dot((second - first).normalize()?.perp());
+                let across = (*b - *a).normalize()?.perp();
+                let measure = Measure::Aligned {
+                    first: *a,
+                    second: *b,
+                };
+                Some((measure, *a + across * aside))
+            }
+            (
+                Measure::Angular {
+                    vertex,
+                    first,
+                    second,
+                },
+                [Anchor::Shape(a), Anchor::Shape(b)],
+            ) => {
+                let (a, b) = (Primitive::of(a)?, Primitive::of(b)?);
+                let moved = *a.intersect(&b).first()?;
+                let along = |line: Primitive, end: Point| match line {
+                    Primitive::Line { direction, .. } => {
+                        Some(moved + direction * (end - vertex).dot(direction))
+                    }
+                    Primitive::Circle { .. } => None,
+                };
+                let measure = Measure::Angular {
+                    vertex: moved,
+                    first: along(a, first)?,
+                    second: along(b, second)?,
+                };
+                Some((measure, location + (moved - vertex)))
+            }
+            (Measure::Radius { center, .. }, [shape]) => {
+                let (moved, radius) = round(shape)?;
+                let measure = Measure::Radius {
+                    center: moved,
+                    radius,
+                };
+                Some((measure, location + (moved - center)))
+            }
+            (Measure::Diameter { center, .. }, [shape]) => {
+                let (moved, radius) = round(shape)?;
+                let measure = Measure::Diameter {
+                    center: moved,
+                    radius,
+                };
+                Some((measure, location + (moved - center)))
+            }
+            (
+                Measure::JoggedRadius {
+                    center,
+                    origin,
+                    jog,
+                    ..
+                },
+                [shape],
+            ) => {
+                let (moved, radius) = round(shape)?;
+                let shift = moved - center;
+                let measure = Measure::JoggedRadius {
+                    center: moved,
+                    radius,
+                    origin: origin + shift,
+                    jog: jog + shift,
+                };
+                Some((measure, location + shift))
+            }
+            (Measure::ArcLength { center, .. }, [Anchor::Shape(shape)]) => {
+                let arc = match **shape {
+                    Drawable::Arc(arc) => arc,
+                    _ => return None,
+                };
+                let measure = Measure::ArcLength {
+                    center: arc.center,
+                    radius: arc.radius,
+                    start_angle: arc.start_angle,
+                    end_angle: arc.end_angle,
+                };
+                Some((measure, location + (arc.center - center)))
+            }
+            (
+                Measure::Ordinate {
+                    origin,
+                    feature,
+                    along_x,
+                },
+                [Anchor::Point(p)],
+            ) => {
+                let measure = Measure::Ordinate {
+                    origin,
+                    feature: *p,
+                    along_x,
+                };
+                Some((measure, location + (*p - feature)))
+            }
+            _ => None,
+        }
+    }
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        Dimension {
+            measure: self.measure.transformed(transform),
+            location: transform.apply(self.location),
+            ..*self
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let mut segments = self.lines(tolerance);
+        if let Some(text) = self.text() {
+            segments.extend(text.segments());
+        }
+        segments
+    }
+}
+
+/// a closed arrowhead with its tip at `tip`, pointing along `pointing`
+pub fn arrowhead(tip: Point, pointing: Point, size: f32) -> Vec<(Point, Point)> {
+    let base = tip - pointing * size;
+    let half = pointing.perp() * (size / 6.0);
+    vec![
+        (tip, base + half),
+        (base + half, base - half),
+        (base - half, tip),
+    ]
+}
+
+/// `angle` turned half around if that keeps text at it from reading upside down
+fn readable(angle: f32) -> f32 {
+    let angle = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if angle > FRAC_PI_2 + 1e-4 || angle <= -FRAC_PI_2 + 1e-4 {
+        angle - PI * angle.signum()
+    } else {
+        angle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::super::tests::{near, props};
+    use super::{DimLook, Dimension, Measure};
+    use crate::components::{Affine, Lettering, Point};
+    use crate::resources::{DimStyle, DimStyles, TextStyle, TextStyles, Units};
+
+    fn look() -> DimLook {
+        let lettering = TextStyle::new("standard");
+        let text_style = TextStyles::new().push(lettering.clone());
+        let style = DimStyle::new("standard", text_style);
+        let id = DimStyles::new().push(style.clone());
+        DimLook::from_style(id, &style, Lettering::from_style(text_style, &lettering))
+    }
+
+    fn dimension(measure: Measure, location: Point) -> Option<Dimension> {
+        Dimension::new(measure, location, look(), Units::default(), props())
+    }
+
+    #[test]
+    fn linear_dimensions_measure_along_their_rotation() {
+        let (first, second) = (Point::new(0.0, 0.0), Point::new(30.0, 40.0));
+        let across = |rotation| Measure::Linear {
+            first,
+            second,
+            rotation,
+        };
+        let horizontal = dimension(across(0.0), Point::new(0.0, 60.0)).unwrap();
+        assert!((horizontal.measurement() - 30.0).abs() < 1e-4);
+        assert_eq!(horizontal.label(), "30.00");
+        let vertical = dimension(across(FRAC_PI_2), Point::new(50.0, 0.0)).unwrap();
+        assert!((vertical.measurement() - 40.0).abs() < 1e-4);
+        let aligned = dimension(Measure::Aligned { first, second }, first).unwrap();
+        assert!((aligned.measurement() - 50.0).abs() < 1e-4);
+        // nothing between the points along the way it measures
+        let level = Measure::Linear {
+            first,
+            second: Point::new(0.0, 10.0),
+            rotation: 0.0,
+        };
+        assert!(dimension(level, first).is_none());
+    }
+
+    #[test]
+    fn the_dimension_line_runs_between_the_feet() {
+        let measure = Measure::Linear {
+            first: Point::new(0.0, 0.0),
+            second: Point::new(100.0, 20.0),
+            rotation: 0.0,
+        };
+        let placed = dimension(measure, Point::new(50.0, 60.0)).unwrap();
+        let lines = placed.lines(0.01);
+        let (a, b) = (Point::new(0.0, 60.0), Point::new(100.0, 60.0));
+        assert!(lines.iter().any(|(p, q)| near(*p, a) && near(*q, b)));
+        // extension lines start clear of the points and reach past the line
+        let look = look();
+        let first_extension = (
+            Point::new(0.0, look.extension_offset),
+            Point::new(0.0, 60.0 + look.extension_beyond),
+        );
+        assert!(lines
+            .iter()
+            .any(|(p, q)| near(*p, first_extension.0) && near(*q, first_extension.1)));
+        // the dimension line and both sides of an arrowhead meet at each foot
+        for foot in [a, b] {
+            let meeting = lines
+                .iter()
+                .filter(|(p, q)| near(*p, foot) || near(*q, foot));
+            assert_eq!(meeting.count(), 3);
+        }
+    }
+
+    #[test]
+    fn angular_dimensions_take_the_side_they_are_placed_on() {
+        let measure = Measure::Angular {
+            vertex: Point::new(0.0, 0.0),
+            first: Point::new(10.0, 0.0),
+            second: Point::new(0.0, 10.0),
+        };
+        let inside = dimension(measure, Point::new(20.0, 20.0)).unwrap();
+        assert!((inside.measurement() - FRAC_PI_2).abs() < 1e-4);
+        assert_eq!(inside.label(), "90.00\u{b0}");
+        let outside = dimension(measure, Point::new(-20.0, -20.0)).unwrap();
+        assert!((outside.measurement() - 1.5 * PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn mirrored_angles_measure_the_same() {
+        let measure = Measure::Angular {
+            vertex: Point::new(0.0, 0.0),
+            first: Point::new(10.0, 0.0),
+            second: Point::new(10.0, 10.0),
+        };
+        let placed = dimension(measure, Point::new(20.0, 5.0)).unwrap();
+        let mirror = Affine::mirror(Point::new(0.0, 0.0), Point::new(0.0, 1.0)).unwrap();
+        let mirrored = placed.transformed(&mirror);
+        assert!((mirrored.measurement() - placed.measurement()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn radial_labels() {
+        let center = Point::new(0.0, 0.0);
+        let radius = dimension(
+            Measure::Radius {
+                center,
+                radius: 5.0,
+            },
+            center,
+        )
+        .unwrap();
+        assert_eq!(radius.label(), "R5.00");
+        let diameter = Measure::Diameter {
+            center,
+            radius: 5.0,
+        };
+        assert_eq!(
+            dimension(diameter, center).unwrap().label(),
+            "\u{2300}10.00"
+        );
+        assert!(dimension(
+            Measure::Radius {
+                center,
+                radius: 0.0
+            },
+            center
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,218 @@
+use std::f32::consts::PI;
+
+use super::{arc_divisions, connect, Affine, Point, Properties};
+
+/// An ellipse, or the part of one swept counter-clockwise from `start` to
+/// `end`. The parameters are angles on the circle the ellipse would be if it
+/// was stretched back out along its minor axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ellipse {
+    pub center: Point,
+    /// from the center to one end of the major axis
+    pub major: Point,
+    /// length of the minor axis over the major, between zero and one
+    pub ratio: f32,
+    pub start: f32,
+    pub end: f32,
+    pub props: Properties,
+}
+
+impl Ellipse {
+    /// a whole ellipse with one axis running from `center` along `axis` and
+    /// the other reaching `other` from the center, whichever is longer
+    /// becoming the major axis
+    pub fn from_axes(center: Point, axis: Point, other: f32, props: Properties) -> Option<Self> {
+        let length = axis.length();
+        let other = other.abs();
+        if length <= f32::EPSILON || other <= f32::EPSILON {
+            return None;
+        }
+        let (major, ratio) = if other > length {
+            (axis.perp() * (other / length), length / other)
+        } else {
+            (axis, other / length)
+        };
+        Some(Ellipse {
+            center,
+            major,
+            ratio,
+            start: 0.0,
+            end: 2.0 * PI,
+            props,
+        })
+    }
+    /// a circle, or an arc of one, as an ellipse which can then be stretched
+    pub fn circular(center: Point, radius: f32, start: f32, end: f32, props: Properties) -> Self {
+        Ellipse {
+            center,
+            major: Point::new(radius, 0.0),
+            ratio: 1.0,
+            start,
+            end,
+            props,
+        }
+    }
+    /// how far around the ellipse from start to end, in parameter
+    pub fn sweep(&self) -> f32 {
+        let sweep = (self.end - self.start).rem_euclid(2.0 * PI);
+        if sweep <= f32::EPSILON {
+            2.0 * PI
+        } else {
+            sweep
+        }
+    }
+    /// cut this ellipse down to the part between the rays from its center
+    /// through `from` and `to`
+    pub fn trimmed(self, from: Point, to: Point) -> Self {
+        Ellipse {
+            start: self.parameter_toward(from),
+            end: self.parameter_toward(to),
+            ..self
+        }
+    }
+    /// parameter of the point where a ray from the center towards `p` meets the ellipse
+    pub fn parameter_toward(&self, p: Point) -> f32 {
+        let a = self.major.length();
+        let unit = self.major * (1.0 / a);
+        let local = p - self.center;
+        let u = local.dot(unit) / a;
+        let v = local.dot(unit.perp()) / (a * self.ratio);
+        v.atan2(u)
+    }
+    pub fn point_at(&self, t: f32) -> Point {
+        self.center + self.major * t.cos() + self.major.perp() * (self.ratio * t.sin())
+    }
+    /// this ellipse moved by `transform`, which always leaves an ellipse
+    /// though its axes may end up pointing somewhere new
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        // the transformed ellipse is c + a1 cos t + a2 sin t, its major axis
+        // lying at the parameter where that is furthest from the center
+        let a1 = transform.apply_vector(self.major);
+        let a2 = transform.apply_vector(self.major.perp() * self.ratio);
+        let t0 = 0.5 * (2.0 * a1.dot(a2)).atan2(a1.dot(a1) - a2.dot(a2));
+        let (sin, cos) = t0.sin_cos();
+        let major = a1 * cos + a2 * sin;
+        let minor = a2 * cos - a1 * sin;
+        let (start, end) = if major.cross(minor) >= 0.0 {
+            (self.start - t0, self.end - t0)
+        } else {
+            // flipped over, so the parameter runs the other way
+            (t0 - self.end, t0 - self.start)
+        };
+        Ellipse {
+            center: transform.apply(self.center),
+            major,
+            ratio: minor.length() / major.length(),
+            start,
+            end,
+            props: self.props,
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        // squashing a circle never makes its chords stray further from the
+        // curve, so dividing as for a circle the size of the major axis is enough
+        let sweep = self.sweep();
+        let divisions = arc_divisions(self.major.length(), sweep, tolerance);
+        let points: Vec<Point> = (0..=divisions)
+            .map(|i| self.point_at(self.start + sweep * i as f32 / divisions as f32))
+            .collect();
+        connect(&points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::super::tests::{near, props};
+    use super::Ellipse;
+    use crate::components::{Affine, Point};
+
+    /// whether `p` lies on the whole ellipse
+    fn on(ellipse: &Ellipse, p: Point) -> bool {
+        near(ellipse.point_at(ellipse.parameter_toward(p)), p)
+    }
+
+    #[test]
+    fn the_longer_axis_becomes_the_major() {
+        let center = Point::new(1.0, 1.0);
+        let wide = Ellipse::from_axes(center, Point::new(4.0, 0.0), 2.0, props()).unwrap();
+        assert!(near(wide.major, Point::new(4.0, 0.0)));
+        assert!((wide.ratio - 0.5).abs() < 1e-6);
+        let tall = Ellipse::from_axes(center, Point::new(2.0, 0.0), 4.0, props()).unwrap();
+        assert!((tall.major.length() - 4.0).abs() < 1e-5);
+        assert!((tall.ratio - 0.5).abs() < 1e-6);
+        assert!(near(tall.point_at(0.0), Point::new(1.0, 5.0)));
+    }
+
+    #[test]
+    fn no_axis_is_no_ellipse() {
+        let center = Point::new(0.0, 0.0);
+        assert_eq!(Ellipse::from_axes(center, center, 2.0, props()), None);
+        assert_eq!(
+            Ellipse::from_axes(center, Point::new(2.0, 0.0), 0.0, props()),
+            None
+        );
+    }
+
+    #[test]
+    fn points_around_it_are_at_their_parameters() {
+        let ellipse =
+            Ellipse::from_axes(Point::new(0.0, 0.0), Point::new(4.0, 0.0), 2.0, props()).unwrap();
+        assert!(near(ellipse.point_at(0.0), Point::new(4.0, 0.0)));
+        assert!(near(ellipse.point_at(FRAC_PI_2), Point::new(0.0, 2.0)));
+        assert!(near(ellipse.point_at(PI), Point::new(-4.0, 0.0)));
+        assert!((ellipse.sweep() - 2.0 * PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn trimming_runs_between_the_rays() {
+        let ellipse =
+            Ellipse::from_axes(Point::new(0.0, 0.0), Point::new(4.0, 0.0), 2.0, props()).unwrap();
+        let quarter = ellipse.trimmed(Point::new(5.0, 0.0), Point::new(0.0, 1.0));
+        assert!(near(quarter.point_at(quarter.start), Point::new(4.0, 0.0)));
+        assert!(near(quarter.point_at(quarter.end), Point::new(0.0, 2.0)));
+        assert!((quarter.sweep() - FRAC_PI_2).abs() < 1e-5);
+        // a ray off the axes meets it on the curve, not at the same parameter as its angle
+        let toward = Point::new(1.0, 1.0);
+        let t = ellipse.parameter_toward(toward);
+        assert!(ellipse.point_at(t).cross(toward).abs() < 1e-4);
+    }
+
+    #[test]
+    fn stretching_a_circle_makes_an_ellipse_through_the_stretched_points() {
+        let circle = Ellipse::circular(Point::new(0.0, 0.0), 1.0, 0.0, 2.0 * PI, props());
+        let skew = Affine {
+            x: Point::new(1.0, 0.5),
+            y: Point::new(0.0, 3.0),
+            offset: Point::new(2.0, -1.0),
+        };
+        let skewed = circle.transformed(&skew);
+        assert!(skewed.ratio > 0.0 && skewed.ratio <= 1.0);
+        for i in 0..8 {
+            let p = skew.apply(circle.point_at(i as f32 * PI / 4.0));
+            assert!(on(&skewed, p), "{:?} isn't on the ellipse", p);
+        }
+    }
+
+    #[test]
+    fn mirroring_an_arc_keeps_its_ends() {
+        let ellipse =
+            Ellipse::from_axes(Point::new(0.0, 0.0), Point::new(4.0, 0.0), 2.0, props()).unwrap();
+        let arc = Ellipse {
+            start: 0.0,
+            end: FRAC_PI_2,
+            ..ellipse
+        };
+        let mirror = Affine::mirror(Point::new(0.0, 0.0), Point::new(0.0, 1.0)).unwrap();
+        let mirrored = arc.transformed(&mirror);
+        let ends = [
+            mirrored.point_at(mirrored.start),
+            mirrored.point_at(mirrored.end),
+        ];
+        for expected in [Point::new(-4.0, 0.0), Point::new(0.0, 2.0)] {
+            assert!(ends.iter().any(|end| near(*end, expected)));
+        }
+        assert!((mirrored.sweep() - FRAC_PI_2).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,102 @@
+use std::f32::consts::PI;
+
+use super::{Arc, Circle, Drawable, Point, Primitive, Properties};
+
+/// Two lines or arcs trimmed or stretched to meet, joined by an arc of
+/// `radius` touching both, or at a sharp corner when the radius is zero.
+/// Each keeps the side it was picked on; circles are left whole. Gives the
+/// two shapes as they end up and the joining arc.
+pub fn fillet(
+    first: &Drawable,
+    first_pick: Point,
+    second: &Drawable,
+    second_pick: Point,
+    radius: f32,
+    props: Properties,
+) -> Option<(Drawable, Drawable, Option<Arc>)> {
+    let (a, b) = (Primitive::of(first)?, Primitive::of(second)?);
+    let (first_end, second_end, arc) = if radius > 0.0 {
+        let center =
+            Circle::tangent_tangent_radius(a, first_pick, b, second_pick, radius, props)?.center;
+        let (first_end, second_end) = (a.closest_point(center), b.closest_point(center));
+        let (start, end) = ((first_end - center).angle(), (second_end - center).angle());
+        // the short way round between where it touches
+        let arc = if (end - start).rem_euclid(2.0 * PI) <= PI {
+            Arc::new(center, radius, start, end, props)
+        } else {
+            Arc::new(center, radius, end, start, props)
+        };
+        (first_end, second_end, Some(arc))
+    } else {
+        let miss = |p: Point| p.distance(first_pick) + p.distance(second_pick);
+        let corner = a
+            .intersect(&b)
+            .into_iter()
+            .filter(|p| p.x.is_finite() && p.y.is_finite())
+            .min_by(|p, q| miss(*p).total_cmp(&miss(*q)))?;
+        (corner, corner, None)
+    };
+    let first = first
+        .trimmed_to(first_end, first_pick)
+        .unwrap_or_else(|| first.clone());
+    let second = second
+        .trimmed_to(second_end, second_pick)
+        .unwrap_or_else(|| second.clone());
+    Some((first, second, arc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::fillet;
+    use crate::components::{Drawable, Line, Point};
+
+    fn line(ax: f32, ay: f32, bx: f32, by: f32) -> Drawable {
+        Drawable::Line(Line::new(Point::new(ax, ay), Point::new(bx, by), props()))
+    }
+
+    fn ends(drawable: &Drawable) -> (Point, Point) {
+        match drawable {
+            Drawable::Line(line) => (line.start, line.end),
+            other => panic!("expected a line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_at_a_corner_are_rounded_off() {
+        let (first, second) = (line(0.0, 0.0, 4.0, 0.0), line(0.0, 0.0, 0.0, 4.0));
+        let (pick_first, pick_second) = (Point::new(3.0, 0.0), Point::new(0.0, 3.0));
+        let (first, second, arc) =
+            fillet(&first, pick_first, &second, pick_second, 1.0, props()).unwrap();
+        let arc = arc.unwrap();
+        assert!(near(arc.center, Point::new(1.0, 1.0)));
+        assert!((arc.sweep() - std::f32::consts::FRAC_PI_2).abs() < 1e-4);
+        assert_eq!(ends(&first), (Point::new(1.0, 0.0), Point::new(4.0, 0.0)));
+        assert_eq!(ends(&second), (Point::new(0.0, 1.0), Point::new(0.0, 4.0)));
+    }
+
+    #[test]
+    fn zero_radius_meets_the_lines_at_a_corner() {
+        let (first, second) = (line(1.0, 0.0, 4.0, 0.0), line(0.0, 1.0, 0.0, 4.0));
+        let (pick_first, pick_second) = (Point::new(3.0, 0.0), Point::new(0.0, 3.0));
+        let (first, second, arc) =
+            fillet(&first, pick_first, &second, pick_second, 0.0, props()).unwrap();
+        assert_eq!(arc, None);
+        assert!(near(ends(&first).0, Point::new(0.0, 0.0)));
+        assert!(near(ends(&second).0, Point::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn parallel_lines_never_meet() {
+        let (first, second) = (line(0.0, 0.0, 4.0, 0.0), line(0.0, 2.0, 4.0, 2.0));
+        let (pick_first, pick_second) = (Point::new(3.0, 0.0), Point::new(3.0, 2.0));
+        assert!(fillet(&first, pick_first, &second, pick_second, 0.0, props()).is_none());
+    }
+
+    #[test]
+    fn zero_length_line_has_nothing_to_fillet() {
+        let (first, second) = (line(1.0, 1.0, 1.0, 1.0), line(0.0, 0.0, 0.0, 4.0));
+        let (pick_first, pick_second) = (Point::new(1.0, 1.0), Point::new(0.0, 3.0));
+        assert!(fillet(&first, pick_first, &second, pick_second, 1.0, props()).is_none());
+    }
+}
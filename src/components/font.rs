@@ -0,0 +1,224 @@
+//! The built in stroke font text is drawn with.
+//!
+//! Glyphs sit on a grid six units to the cap height, with the baseline at
+//! row 2, lowercase letters reaching row 5, capitals row 8 and descenders
+//! dropping to row 0. Each glyph is written as strokes separated by spaces,
+//! every stroke a run of digit pairs giving the column and row it passes through.
+
+use super::Point;
+
+/// grid rows from the baseline to the top of a capital
+const CAP_ROWS: f32 = 6.0;
+
+/// grid row the baseline sits on
+const BASELINE_ROW: f32 = 2.0;
+
+/// how far along the baseline each character moves the next, in cap heights
+pub const ADVANCE: f32 = 1.0;
+
+/// the space left between one character and the next, in cap heights
+const GAP: f32 = 2.0 / CAP_ROWS;
+
+/// how far below the baseline descenders reach, in cap heights
+pub const DESCENT: f32 = BASELINE_ROW / CAP_ROWS;
+
+/// the strokes of `c` with its baseline starting at the origin, one cap
+/// height tall, anything the font doesn't have being drawn as a box
+pub fn glyph(c: char) -> Vec<Vec<Point>> {
+    strokes(c)
+        .unwrap_or("0208484202")
+        .split_whitespace()
+        .map(|stroke| {
+            let digits: Vec<f32> = stroke
+                .chars()
+                .filter_map(|d| d.to_digit(10))
+                .map(|d| d as f32)
+                .collect();
+            digits
+                .chunks(2)
+                .map(|pair| Point::new(pair[0] / CAP_ROWS, (pair[1] - BASELINE_ROW) / CAP_ROWS))
+                .collect()
+        })
+        .collect()
+}
+
+/// how long a line of text runs along its baseline, in cap heights
+pub fn line_width(line: &str) -> f32 {
+    let count = line.chars().count();
+    if count == 0 {
+        0.0
+    } else {
+        count as f32 * ADVANCE - GAP
+    }
+}
+
+fn strokes(c: char) -> Option<&'static str> {
+    let strokes = match c {
+        ' ' => "",
+        '!' => "2824 2223",
+        '"' => "1817 3837",
+        '#' => "1217 3237 0636 0333",
+        '$' => "473818070615354443321203 2921",
+        '%' => "0248 0717 3343",
+        '&' => "42061728373603122244",
+        '\'' => "2827",
+        '(' => "38272332",
+        ')' => "18272312",
+        '*' => "2327 0644 0446",
+        '+' => "2327 0545",
+        ',' => "232110",
+        '-' => "0545",
+        '.' => "2223",
+        '/' => "0248",
+        '0' => "120307183847433212 0347",
+        '1' => "172822 1232",
+        '2' => "07183847460242",
+        '3' => "0718384746354443321203 1535",
+        '4' => "32380444",
+        '5' => "480805354443321203",
+        '6' => "473818070312324344351504",
+        '7' => "084822",
+        '8' => "180706153546473818 1504031232434435",
+        '9' => "463515060718384743321203",
+        ':' => "2425 2223",
+        ';' => "2425 232110",
+        '<' => "470543",
+        '=' => "0444 0646",
+        '>' => "074503",
+        '?' => "0718384746352524 2223",
+        '@' => "35151333354647381807031242",
+        'A' => "022842 1535",
+        'B' => "02083847463505 3544433202",
+        'C' => "4738180703123243",
+        'D' => "02082846442202",
+        'E' => "42020848 0535",
+        'F' => "020848 0535",
+        'G' => "47381807031232434525",
+        'H' => "0208 4248 0545",
+        'I' => "1838 2822 1232",
+        'J' => "4843321203",
+        'K' => "0208 4804 1542",
+        'L' => "080242",
+        'M' => "0208254842",
+        'N' => "02084842",
+        'O' => "120307183847433212",
+        'P' => "02083847463505",
+        'Q' => "120307183847433212 2442",
+        'R' => "02083847463505 3542",
+        'S' => "473818070615354443321203",
+        'T' => "0848 2822",
+        'U' => "080312324348",
+        'V' => "082248",
+        'W' => "0812253248",
+        'X' => "0842 4802",
+        'Y' => "082548 2522",
+        'Z' => "08480242",
+        '[' => "38181232",
+        '\\' => "0842",
+        ']' => "18383212",
+        '^' => "062846",
+        '_' => "0040",
+        '`' => "1827",
+        'a' => "15354442 441403123243",
+        'b' => "0802 0415354443321203",
+        'c' => "4435150403123243",
+        'd' => "4842 4435150403123243",
+        'e' => "044435150403123242",
+        'f' => "4738281712 0535",
+        'g' => "4435150403123243 4541301001",
+        'h' => "0802 0415354442",
+        'i' => "2522 2728",
+        'j' => "35312010 3738",
+        'k' => "0802 4503 2442",
+        'l' => "18282332",
+        'm' => "0502 04152422 24354442",
+        'n' => "0502 0415354442",
+        'o' => "041535444332120304",
+        'p' => "0500 0415354443321203",
+        'q' => "4540 4435150403123243",
+        'r' => "0502 04153544",
+        's' => "451504433202",
+        't' => "18132232 0535",
+        'u' => "0503123243 4542",
+        'v' => "052245",
+        'w' => "0512243245",
+        'x' => "0542 4502",
+        'y' => "0523 4520",
+        'z' => "05450242",
+        '{' => "382827152332",
+        '|' => "2921",
+        '}' => "182827352312",
+        '~' => "05163546",
+        '\u{b0}' => "1728372617",
+        '\u{2300}' => "120307183847433212 0149",
+        '\u{2312}' => "05163645",
+        _ => return None,
+    };
+    Some(strokes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glyph, line_width, strokes, ADVANCE, CAP_ROWS, DESCENT, GAP};
+
+    /// every character that can be typed on the command line
+    fn printable() -> impl Iterator<Item = char> {
+        ' '..='~'
+    }
+
+    #[test]
+    fn every_stroke_is_whole_grid_points() {
+        for c in printable() {
+            for stroke in strokes(c).unwrap_or("").split_whitespace() {
+                assert!(
+                    stroke.len() % 2 == 0 && stroke.chars().all(|d| d.is_ascii_digit()),
+                    "{:?} has a broken stroke {:?}",
+                    c,
+                    stroke
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs_stay_within_their_cell() {
+        for c in printable() {
+            for p in glyph(c).into_iter().flatten() {
+                assert!(
+                    p.x >= 0.0 && p.x <= ADVANCE - GAP + 1e-6,
+                    "{:?} runs wide",
+                    c
+                );
+                // a few symbols reach a row past the descenders or capitals
+                let reach = DESCENT + 1.0 / CAP_ROWS + 1e-6;
+                assert!(p.y >= -reach && p.y <= 1.0 + reach, "{:?} runs tall", c);
+            }
+        }
+    }
+
+    #[test]
+    fn letters_and_digits_are_all_drawn() {
+        for c in ('a'..='z').chain('A'..='Z').chain('0'..='9') {
+            assert!(strokes(c).is_some(), "no glyph for {:?}", c);
+            assert!(!glyph(c).is_empty());
+        }
+        assert!(glyph(' ').is_empty());
+    }
+
+    #[test]
+    fn anything_else_is_a_box() {
+        let unknown = glyph('\u{263a}');
+        assert_eq!(unknown.len(), 1);
+        let outline = &unknown[0];
+        assert_eq!(outline.first(), outline.last());
+    }
+
+    #[test]
+    fn lines_are_as_wide_as_their_characters() {
+        assert_eq!(line_width(""), 0.0);
+        assert!((line_width("a") - (ADVANCE - GAP)).abs() < 1e-6);
+        assert!((line_width("abc") - (3.0 * ADVANCE - GAP)).abs() < 1e-6);
+        // counted in characters, not bytes
+        assert_eq!(line_width("\u{e9}\u{e9}"), line_width("ee"));
+    }
+}
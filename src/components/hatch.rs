@@ -0,0 +1,241 @@
+use super::{Affine, Anchor, Point, Polyline, Properties};
+
+/// most lines drawn for one family of a hatch, more widely spaced lines
+/// being drawn instead when a pattern is finer than that across the area
+const MAX_HATCH_LINES: usize = 4096;
+
+/// how far apart the lines a solid fill is drawn with are, in tolerances
+const SOLID_SPACING: f32 = 2.0;
+
+/// One family of parallel lines in a hatch pattern, in pattern units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternLine {
+    /// in radians
+    pub angle: f32,
+    /// a point one of the lines passes through
+    pub origin: Point,
+    /// how far each line is moved from the one before, along it and then across it
+    pub offset: Point,
+    /// lengths of the dashes along each line, negative for gaps and zero for
+    /// dots, repeating; a continuous line has none
+    pub dashes: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    pub lines: Vec<PatternLine>,
+}
+
+impl Pattern {
+    /// evenly spaced lines at no angle, crossed by the same lines turned a
+    /// quarter if `double`
+    pub fn user_defined(spacing: f32, double: bool) -> Self {
+        let family = |angle: f32| PatternLine {
+            angle,
+            origin: Point::new(0.0, 0.0),
+            offset: Point::new(0.0, spacing),
+            dashes: vec![],
+        };
+        let mut lines = vec![family(0.0)];
+        if double {
+            lines.push(family(std::f32::consts::FRAC_PI_2));
+        }
+        Pattern {
+            name: "_USER".to_string(),
+            lines,
+        }
+    }
+}
+
+/// what an area is filled with
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fill {
+    Solid,
+    Pattern(Pattern),
+}
+
+/// An area filled in with a pattern. Points inside an odd number of the
+/// boundary loops are filled, so loops inside others leave islands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hatch {
+    /// closed polylines around the area
+    pub loops: Vec<Polyline>,
+    pub fill: Fill,
+    /// how much bigger the pattern is drawn than it's defined
+    pub scale: f32,
+    /// how far the pattern is turned, in radians
+    pub angle: f32,
+    pub props: Properties,
+}
+
+impl Hatch {
+    /// the area inside `loops`, if there is any and the pattern can be drawn
+    pub fn new(
+        loops: Vec<Polyline>,
+        fill: Fill,
+        scale: f32,
+        angle: f32,
+        props: Properties,
+    ) -> Option<Self> {
+        if loops.is_empty() || scale <= 0.0 {
+            return None;
+        }
+        Some(Hatch {
+            loops,
+            fill,
+            scale,
+            angle,
+            props,
+        })
+    }
+    /// the boundary loops as straight edges
+    fn edges(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        self.loops
+            .iter()
+            .flat_map(|boundary| boundary.segments(tolerance))
+            .collect()
+    }
+    /// this hatch filling the loops its sources make now, false if any of
+    /// them no longer closes
+    pub fn follow(&mut self, anchors: &[Anchor]) -> bool {
+        let loops: Option<Vec<Polyline>> = anchors
+            .iter()
+            .map(|anchor| match anchor {
+                Anchor::Shape(shape) => shape.boundary_loop(),
+                Anchor::Point(_) => None,
+            })
+            .collect();
+        match loops {
+            Some(loops) if !loops.is_empty() => {
+                self.loops = loops;
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn snap_points(&self) -> Vec<Point> {
+        self.loops
+            .iter()
+            .flat_map(|boundary| boundary.vertices.iter().map(|v| v.position))
+            .collect()
+    }
+    /// this hatch moved by `transform`, its pattern turned and scaled along
+    /// with it as far as a pattern can be
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        let scale = transform
+            .uniform_scale()
+            .unwrap_or_else(|| transform.determinant().abs().sqrt());
+        Hatch {
+            loops: self
+                .loops
+                .iter()
+                .map(|boundary| boundary.transformed(transform, transform.tolerance()))
+                .collect(),
+            scale: self.scale * scale,
+            angle: transform
+                .apply_vector(Point::from_angle(self.angle))
+                .angle(),
+            ..self.clone()
+        }
+    }
+    /// the lines filling the area, the boundary followed within `tolerance`
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let edges = self.edges(tolerance);
+        match &self.fill {
+            Fill::Solid => {
+                let solid = PatternLine {
+                    angle: 0.0,
+                    origin: Point::new(0.0, 0.0),
+                    offset: Point::new(0.0, tolerance * SOLID_SPACING),
+                    dashes: vec![],
+                };
+                family_lines(&edges, &solid, 1.0, 0.0, tolerance)
+            }
+            Fill::Pattern(pattern) => pattern
+                .lines
+                .iter()
+                .flat_map(|family| family_lines(&edges, family, self.scale, self.angle, tolerance))
+                .collect(),
+        }
+    }
+}
+
+/// the lines of one pattern family which fall inside `edges`, dashed as
+/// the family says
+fn family_lines(
+    edges: &[(Point, Point)],
+    family: &PatternLine,
+    scale: f32,
+    angle: f32,
+    tolerance: f32,
+) -> Vec<(Point, Point)> {
+    let direction = Point::from_angle(family.angle + angle);
+    let across = direction.perp();
+    let origin = family.origin.rotate(angle) * scale;
+    let shift = (direction * family.offset.x + across * family.offset.y) * scale;
+    let spacing = shift.dot(across);
+    if spacing.abs() <= f32::EPSILON || edges.is_empty() {
+        return vec![];
+    }
+    let (low, high) = edges
+        .iter()
+        .flat_map(|(a, b)| vec![*a, *b])
+        .map(|p| (p - origin).dot(across) / spacing)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), s| {
+            (low.min(s), high.max(s))
+        });
+    let (first, last) = (low.floor() as i64, high.ceil() as i64);
+    let every = ((last - first) as usize / MAX_HATCH_LINES).max(1);
+    let mut lines = vec![];
+    for k in (first..=last).step_by(every) {
+        let base = origin + shift * k as f32;
+        let mut crossings: Vec<f32> = edges
+            .iter()
+            .filter_map(|(a, b)| {
+                let (sa, sb) = ((*a - base).dot(across), (*b - base).dot(across));
+                if (sa > 0.0) == (sb > 0.0) {
+                    return None;
+                }
+                let p = *a + (*b - *a) * (sa / (sa - sb));
+                Some((p - base).dot(direction))
+            })
+            .collect();
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        for inside in crossings.chunks_exact(2) {
+            let dashes: Vec<f32> = family.dashes.iter().map(|d| d * scale).collect();
+            for (from, to) in dashed(inside[0], inside[1], &dashes, tolerance) {
+                lines.push((base + direction * from, base + direction * to));
+            }
+        }
+    }
+    lines
+}
+
+/// the stretches of a line between `from` and `to` that `dashes` draw,
+/// the pattern starting over at every multiple of its length
+fn dashed(from: f32, to: f32, dashes: &[f32], tolerance: f32) -> Vec<(f32, f32)> {
+    let period: f32 = dashes.iter().map(|d| d.abs()).sum();
+    if dashes.is_empty() || period <= f32::EPSILON || (to - from) / period > MAX_HATCH_LINES as f32
+    {
+        return vec![(from, to)];
+    }
+    let mut stretches = vec![];
+    let mut at = (from / period).floor() * period;
+    for dash in dashes.iter().cycle() {
+        if at >= to {
+            break;
+        }
+        let end = at + dash.abs();
+        if *dash == 0.0 {
+            // dots are drawn just long enough to show up
+            if at >= from {
+                stretches.push((at, (at + tolerance).min(to)));
+            }
+        } else if *dash > 0.0 && end > from {
+            stretches.push((at.max(from), end.min(to)));
+        }
+        at = end;
+    }
+    stretches
+}
@@ -0,0 +1,198 @@
+use std::f32::consts::FRAC_PI_2;
+
+use super::{Affine, AttDef, Attribute, Drawable, Group, Point, Properties};
+use crate::common::GenerationID;
+
+/// Copies of a block laid out in rows and columns, spaced along and across
+/// the way the insert is turned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Array {
+    pub columns: usize,
+    pub rows: usize,
+    /// how far apart the columns are, then the rows
+    pub spacing: Point,
+}
+
+/// A reference to a block definition, placed, scaled and turned. It keeps a
+/// copy of the definition to draw, brought up to date when the block is
+/// redefined, and the values it gives the block's fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Insert {
+    pub block: GenerationID<Group>,
+    pub definition: Group,
+    /// where the block's base point goes
+    pub position: Point,
+    /// how much the block is stretched along its own x and y, negative
+    /// when it's flipped
+    pub scale: Point,
+    /// in radians
+    pub rotation: f32,
+    pub array: Option<Array>,
+    /// values for the block's fields, by tag; fields without one show their
+    /// default
+    pub attributes: Vec<Attribute>,
+    pub props: Properties,
+}
+
+impl Insert {
+    /// `definition` placed at `position`, if it can be seen at that scale
+    pub fn new(
+        block: GenerationID<Group>,
+        definition: Group,
+        position: Point,
+        scale: Point,
+        rotation: f32,
+        props: Properties,
+    ) -> Option<Self> {
+        if scale.x.abs() <= f32::EPSILON || scale.y.abs() <= f32::EPSILON {
+            return None;
+        }
+        Some(Insert {
+            block,
+            definition,
+            position,
+            scale,
+            rotation,
+            array: None,
+            attributes: vec![],
+            props,
+        })
+    }
+    /// this insert repeated in `array`, if it has any copies
+    pub fn with_array(self, array: Array) -> Option<Self> {
+        if array.columns == 0 || array.rows == 0 {
+            return None;
+        }
+        Some(Insert {
+            array: Some(array),
+            ..self
+        })
+    }
+    /// this insert giving its block's fields `attributes`
+    pub fn with_attributes(self, attributes: Vec<Attribute>) -> Self {
+        Insert { attributes, ..self }
+    }
+    /// the fields of the block, in the order they're asked for
+    pub fn fields(&self) -> Vec<&AttDef> {
+        self.definition
+            .members()
+            .iter()
+            .filter_map(|member| match member {
+                Drawable::AttDef(field) => Some(field),
+                _ => None,
+            })
+            .collect()
+    }
+    /// what this insert fills `field` in with
+    pub fn value(&self, field: &AttDef) -> String {
+        if field.constant {
+            return field.default.clone();
+        }
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.tag == field.tag)
+            .map_or_else(
+                || field.default.clone(),
+                |attribute| attribute.value.clone(),
+            )
+    }
+    /// the transform taking the definition to the copy in `column` and `row`
+    fn placement(&self, column: usize, row: usize) -> Affine {
+        let x = Point::from_angle(self.rotation) * self.scale.x;
+        let y = Point::from_angle(self.rotation + FRAC_PI_2) * self.scale.y;
+        let cell = match self.array {
+            Some(array) => Point::new(
+                array.spacing.x * column as f32,
+                array.spacing.y * row as f32,
+            )
+            .rotate(self.rotation),
+            None => Point::new(0.0, 0.0),
+        };
+        let base = self.definition.base;
+        Affine {
+            x,
+            y,
+            offset: self.position + cell - (x * base.x + y * base.y),
+        }
+    }
+    /// the block's drawables as they're placed, every copy of them
+    pub fn copies(&self) -> Vec<Drawable> {
+        let (columns, rows) = self.array.map_or((1, 1), |a| (a.columns, a.rows));
+        // the fields written out with their values
+        let filled = Group {
+            inner: self
+                .definition
+                .members()
+                .iter()
+                .filter_map(|member| match member {
+                    Drawable::AttDef(field) => field.shown(&self.value(field)).map(Drawable::Text),
+                    _ => Some(member.clone()),
+                })
+                .collect(),
+            ..self.definition.clone()
+        };
+        let mut copies = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                let placement = self.placement(column, row);
+                copies.push(Drawable::NamedGroup(filled.transformed(&placement)));
+            }
+        }
+        copies
+    }
+    /// whether this insert draws the block `id`, directly or from inside
+    /// another block
+    pub fn uses(&self, id: GenerationID<Group>) -> bool {
+        self.block == id
+            || self.definition.members().iter().any(|member| match member {
+                Drawable::Insert(insert) => insert.uses(id),
+                _ => false,
+            })
+    }
+    /// this insert drawing `definition` where it drew the block `id` before
+    pub fn redefine(&mut self, id: GenerationID<Group>, definition: &Group) {
+        if self.block == id {
+            self.definition = definition.clone();
+        }
+        for member in self.definition.inner.iter_mut() {
+            member.redefine(id, definition);
+        }
+    }
+    pub fn snap_points(&self) -> Vec<Point> {
+        let (columns, rows) = self.array.map_or((1, 1), |a| (a.columns, a.rows));
+        let mut points = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                points.push(self.placement(column, row).apply(self.definition.base));
+            }
+        }
+        points.extend(self.copies().iter().flat_map(|copy| copy.snap_points()));
+        points
+    }
+    /// this insert moved by `transform`, as far as a block can be placed
+    /// without being skewed
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        let along = transform.apply_vector(Point::from_angle(self.rotation));
+        let stretch = along.length();
+        let flip = transform.determinant().signum();
+        let spacing = transform
+            .uniform_scale()
+            .unwrap_or_else(|| transform.determinant().abs().sqrt());
+        Insert {
+            position: transform.apply(self.position),
+            scale: Point::new(self.scale.x * stretch, self.scale.y * stretch * flip),
+            rotation: along.angle(),
+            array: self.array.map(|array| Array {
+                spacing: Point::new(array.spacing.x * spacing, array.spacing.y * spacing * flip),
+                ..array
+            }),
+            ..self.clone()
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        self.copies()
+            .iter()
+            .flat_map(|copy| copy.segments(tolerance))
+            .collect()
+    }
+}
@@ -0,0 +1,249 @@
+use super::{Drawable, Point};
+
+/// An unbounded line or a whole circle, the shapes construction geometry
+/// like tangents and fillets is worked out on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Primitive {
+    Line { point: Point, direction: Point },
+    Circle { center: Point, radius: f32 },
+}
+
+impl Primitive {
+    /// the line or circle a drawable lies on, if it's that simple
+    pub fn of(drawable: &Drawable) -> Option<Primitive> {
+        match drawable {
+            Drawable::Line(line) => Some(Primitive::Line {
+                point: line.start,
+                direction: (line.end - line.start).normalize()?,
+            }),
+            Drawable::Arc(arc) => Some(Primitive::Circle {
+                center: arc.center,
+                radius: arc.radius,
+            }),
+            Drawable::Circle(circle) => Some(Primitive::Circle {
+                center: circle.center,
+                radius: circle.radius,
+            }),
+            _ => None,
+        }
+    }
+    /// the shapes running `distance` away from this one, on either side
+    pub fn offsets(&self, distance: f32) -> Vec<Primitive> {
+        match *self {
+            Primitive::Line { point, direction } => {
+                let normal = direction.perp() * distance;
+                vec![
+                    Primitive::Line {
+                        point: point + normal,
+                        direction,
+                    },
+                    Primitive::Line {
+                        point: point - normal,
+                        direction,
+                    },
+                ]
+            }
+            Primitive::Circle { center, radius } => {
+                let mut offsets = vec![Primitive::Circle {
+                    center,
+                    radius: radius + distance,
+                }];
+                if (radius - distance).abs() > f32::EPSILON {
+                    offsets.push(Primitive::Circle {
+                        center,
+                        radius: (radius - distance).abs(),
+                    });
+                }
+                offsets
+            }
+        }
+    }
+    pub fn closest_point(&self, p: Point) -> Point {
+        match *self {
+            Primitive::Line { point, direction } => point + direction * (p - point).dot(direction),
+            Primitive::Circle { center, radius } => match (p - center).normalize() {
+                Some(towards) => center + towards * radius,
+                None => center + Point::new(radius, 0.0),
+            },
+        }
+    }
+    pub fn intersect(&self, other: &Primitive) -> Vec<Point> {
+        use Primitive::*;
+        match (*self, *other) {
+            (
+                Line {
+                    point: p1,
+                    direction: d1,
+                },
+                Line {
+                    point: p2,
+                    direction: d2,
+                },
+            ) => line_line(p1, d1, p2, d2).into_iter().collect(),
+            (Line { point, direction }, Circle { center, radius })
+            | (Circle { center, radius }, Line { point, direction }) => {
+                line_circle(point, direction, center, radius)
+            }
+            (
+                Circle {
+                    center: c1,
+                    radius: r1,
+                },
+                Circle {
+                    center: c2,
+                    radius: r2,
+                },
+            ) => circle_circle(c1, r1, c2, r2),
+        }
+    }
+}
+
+/// where two unbounded lines cross, if they aren't parallel
+pub fn line_line(p1: Point, d1: Point, p2: Point, d2: Point) -> Option<Point> {
+    let denominator = d1.cross(d2);
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+    let t = (p2 - p1).cross(d2) / denominator;
+    Some(p1 + d1 * t)
+}
+
+/// where an unbounded line through `point` along the unit `direction` meets a circle
+pub fn line_circle(point: Point, direction: Point, center: Point, radius: f32) -> Vec<Point> {
+    let closest = point + direction * (center - point).dot(direction);
+    let distance = closest.distance(center);
+    if distance > radius * (1.0 + 1e-5) {
+        return vec![];
+    }
+    let half_chord = (radius * radius - distance * distance).max(0.0).sqrt();
+    if half_chord <= radius * 1e-5 {
+        return vec![closest];
+    }
+    vec![
+        closest - direction * half_chord,
+        closest + direction * half_chord,
+    ]
+}
+
+pub fn circle_circle(c1: Point, r1: f32, c2: Point, r2: f32) -> Vec<Point> {
+    let d = c1.distance(c2);
+    let slack = (r1 + r2) * 1e-5;
+    if d <= f32::EPSILON || d > r1 + r2 + slack || d < (r1 - r2).abs() - slack {
+        return vec![];
+    }
+    let along = (d * d + r1 * r1 - r2 * r2) / (2.0 * d);
+    let axis = (c2 - c1) * (1.0 / d);
+    let base = c1 + axis * along;
+    let height = (r1 * r1 - along * along).max(0.0).sqrt();
+    if height <= slack {
+        return vec![base];
+    }
+    vec![base + axis.perp() * height, base - axis.perp() * height]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::{circle_circle, line_circle, line_line, Primitive};
+    use crate::components::{Drawable, Line, Point};
+
+    fn line(point: Point, direction: Point) -> Primitive {
+        Primitive::Line {
+            point,
+            direction: direction.normalize().unwrap(),
+        }
+    }
+
+    fn circle(x: f32, y: f32, radius: f32) -> Primitive {
+        Primitive::Circle {
+            center: Point::new(x, y),
+            radius,
+        }
+    }
+
+    #[test]
+    fn crossing_lines_meet_once() {
+        let a = line(Point::new(0.0, 0.0), Point::new(1.0, 1.0));
+        let b = line(Point::new(2.0, 0.0), Point::new(-1.0, 1.0));
+        let met = a.intersect(&b);
+        assert_eq!(met.len(), 1);
+        assert!(near(met[0], Point::new(1.0, 1.0)));
+    }
+
+    #[test]
+    fn parallel_lines_never_meet() {
+        let (along, across) = (Point::new(1.0, 0.0), Point::new(0.0, 1.0));
+        assert_eq!(line_line(Point::new(0.0, 0.0), along, across, along), None);
+        // not even lying on top of one another
+        assert_eq!(line_line(Point::new(0.0, 0.0), along, along, along), None);
+    }
+
+    #[test]
+    fn line_through_a_circle_meets_it_twice() {
+        let met =
+            line(Point::new(-5.0, 0.0), Point::new(1.0, 0.0)).intersect(&circle(0.0, 0.0, 2.0));
+        assert_eq!(met.len(), 2);
+        assert!(near(met[0], Point::new(-2.0, 0.0)) && near(met[1], Point::new(2.0, 0.0)));
+    }
+
+    #[test]
+    fn tangent_line_touches_once() {
+        let met = line_circle(
+            Point::new(-5.0, 2.0),
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 0.0),
+            2.0,
+        );
+        assert_eq!(met.len(), 1);
+        assert!(near(met[0], Point::new(0.0, 2.0)));
+    }
+
+    #[test]
+    fn line_missing_a_circle() {
+        let met =
+            line(Point::new(0.0, 3.0), Point::new(1.0, 0.0)).intersect(&circle(0.0, 0.0, 2.0));
+        assert!(met.is_empty());
+    }
+
+    #[test]
+    fn overlapping_circles_meet_twice() {
+        let met = circle(0.0, 0.0, 2.0).intersect(&circle(2.0, 0.0, 2.0));
+        assert_eq!(met.len(), 2);
+        for p in met {
+            assert!((p.x - 1.0).abs() < 1e-4 && (p.y.abs() - 3f32.sqrt()).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn tangent_circles_touch_once() {
+        let outside = circle_circle(Point::new(0.0, 0.0), 1.0, Point::new(3.0, 0.0), 2.0);
+        assert_eq!(outside.len(), 1);
+        assert!(near(outside[0], Point::new(1.0, 0.0)));
+        let inside = circle_circle(Point::new(0.0, 0.0), 3.0, Point::new(1.0, 0.0), 2.0);
+        assert_eq!(inside.len(), 1);
+        assert!(near(inside[0], Point::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn circles_apart_nested_or_concentric_never_meet() {
+        let origin = Point::new(0.0, 0.0);
+        assert!(circle_circle(origin, 1.0, Point::new(5.0, 0.0), 1.0).is_empty());
+        assert!(circle_circle(origin, 3.0, Point::new(0.5, 0.0), 1.0).is_empty());
+        assert!(circle_circle(origin, 1.0, origin, 1.0).is_empty());
+    }
+
+    #[test]
+    fn zero_length_line_has_no_primitive() {
+        let p = Point::new(1.0, 1.0);
+        assert_eq!(
+            Primitive::of(&Drawable::Line(Line::new(p, p, props()))),
+            None
+        );
+    }
+
+    #[test]
+    fn offsets_of_a_circle_skip_a_zero_radius() {
+        let offsets = circle(0.0, 0.0, 2.0).offsets(2.0);
+        assert_eq!(offsets, vec![circle(0.0, 0.0, 4.0)]);
+    }
+}
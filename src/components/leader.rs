@@ -0,0 +1,288 @@
+use std::f32::consts::PI;
+
+use super::{
+    arc_divisions, connect, dimension::arrowhead, Affine, Anchor, Horizontal, Justify, Lettering,
+    MText, Point, Properties, Vertical,
+};
+use crate::{common::GenerationID, resources::LeaderStyle};
+
+/// strokes drawn from the tip of a filled arrowhead across to its base
+const FILL_STROKES: usize = 4;
+
+/// the shape drawn where a leader points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arrowhead {
+    ClosedFilled,
+    Open,
+    Dot,
+    /// an oblique stroke across the tip, as architects draw them
+    Tick,
+}
+
+impl Arrowhead {
+    /// the arrowhead typed as a keyword: `c`losed, `o`pen, `d`ot or `t`ick
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "c" => Some(Arrowhead::ClosedFilled),
+            "o" => Some(Arrowhead::Open),
+            "d" => Some(Arrowhead::Dot),
+            "t" => Some(Arrowhead::Tick),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Arrowhead::ClosedFilled => "closed filled",
+            Arrowhead::Open => "open",
+            Arrowhead::Dot => "dot",
+            Arrowhead::Tick => "tick",
+        }
+    }
+    /// the arrowhead `size` long at `tip`, `pointing` the way it points,
+    /// round ones staying within `tolerance` of a circle
+    pub fn lines(
+        self,
+        tip: Point,
+        pointing: Point,
+        size: f32,
+        tolerance: f32,
+    ) -> Vec<(Point, Point)> {
+        match self {
+            Arrowhead::ClosedFilled => {
+                let mut lines = arrowhead(tip, pointing, size);
+                let (one, other) = (lines[0].1, lines[1].1);
+                for i in 1..FILL_STROKES {
+                    let t = i as f32 / FILL_STROKES as f32;
+                    lines.push((tip, one + (other - one) * t));
+                }
+                lines
+            }
+            Arrowhead::Open => {
+                let base = tip - pointing * size;
+                let half = pointing.perp() * (size / 4.0);
+                vec![(tip, base + half), (tip, base - half)]
+            }
+            Arrowhead::Dot => {
+                // rings inside one another so the dot reads as filled
+                let radius = size / 4.0;
+                let divisions = arc_divisions(radius, 2.0 * PI, tolerance);
+                let mut lines = vec![];
+                for ring in 1..=3 {
+                    let r = radius * ring as f32 / 3.0;
+                    let points: Vec<Point> = (0..=divisions)
+                        .map(|i| {
+                            tip + Point::from_angle(2.0 * PI * i as f32 / divisions as f32) * r
+                        })
+                        .collect();
+                    lines.extend(connect(&points));
+                }
+                lines
+            }
+            Arrowhead::Tick => {
+                let slant = (pointing + pointing.perp()) * (size / 4.0);
+                vec![(tip - slant, tip + slant)]
+            }
+        }
+    }
+}
+
+/// How a leader is drawn, copied from its leader style the way dimensions
+/// copy theirs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LeaderLook {
+    pub style: GenerationID<LeaderStyle>,
+    pub arrowhead: Arrowhead,
+    pub arrow_size: f32,
+    /// length of the level line the note sits at the end of
+    pub landing: f32,
+    pub text_height: f32,
+    pub text_gap: f32,
+    pub lettering: Lettering,
+}
+
+impl LeaderLook {
+    /// the look of leaders in the style `id`, lettered with `lettering`
+    pub fn from_style(
+        id: GenerationID<LeaderStyle>,
+        style: &LeaderStyle,
+        lettering: Lettering,
+    ) -> Self {
+        LeaderLook {
+            style: id,
+            arrowhead: style.arrowhead,
+            arrow_size: style.arrow_size,
+            landing: style.landing,
+            text_height: style.text_height,
+            text_gap: style.text_gap,
+            lettering,
+        }
+    }
+}
+
+/// A callout: arms running from their arrowheads to a shared point, a level
+/// landing from there and a note at the end of the landing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Leader {
+    /// the arrowhead and any bends of each arm, which all go on to `anchor`
+    pub arms: Vec<Vec<Point>>,
+    /// where the arms meet and the landing starts
+    pub anchor: Point,
+    pub note: Option<MText>,
+    pub look: LeaderLook,
+    pub props: Properties,
+    /// the arms whose arrowheads follow a point on another drawable, in the
+    /// order of the leader's links
+    pub attached: Vec<usize>,
+}
+
+impl Leader {
+    /// a leader with `arms` meeting at `anchor`, noted with `content` unless
+    /// it's blank, if every arm points somewhere
+    pub fn new(
+        arms: Vec<Vec<Point>>,
+        anchor: Point,
+        content: &str,
+        look: LeaderLook,
+        props: Properties,
+    ) -> Option<Self> {
+        let pointing = |arm: &Vec<Point>| match arm.first() {
+            Some(tip) => tip.distance(anchor) > f32::EPSILON,
+            None => false,
+        };
+        if arms.is_empty() || !arms.iter().all(pointing) {
+            return None;
+        }
+        let origin = Point::new(0.0, 0.0);
+        let justify = Justify::new(Horizontal::Left, Vertical::Middle);
+        let note = MText::new(
+            content,
+            origin,
+            0.0,
+            look.text_height,
+            justify,
+            look.lettering,
+            props,
+        );
+        let mut leader = Leader {
+            arms,
+            anchor,
+            note,
+            look,
+            props,
+            attached: vec![],
+        };
+        leader.place_note();
+        Some(leader)
+    }
+    /// this leader with the arrowheads of `arms` following points on other
+    /// drawables
+    pub fn with_attached(self, arms: Vec<usize>) -> Self {
+        Leader {
+            attached: arms,
+            ..self
+        }
+    }
+    /// which way along x the landing runs, away from the first arm
+    fn landing_direction(&self) -> f32 {
+        let before = self
+            .arms
+            .first()
+            .and_then(|arm| arm.last())
+            .map_or(self.anchor, |p| *p);
+        if self.anchor.x < before.x {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+    pub fn landing_end(&self) -> Point {
+        self.anchor + Point::new(self.landing_direction() * self.look.landing, 0.0)
+    }
+    /// the note moved to the end of the landing, level and reading away from it
+    fn place_note(&mut self) {
+        let direction = self.landing_direction();
+        let at = self.landing_end() + Point::new(direction * self.look.text_gap, 0.0);
+        if let Some(note) = self.note.as_mut() {
+            let horizontal = if direction > 0.0 {
+                Horizontal::Left
+            } else {
+                Horizontal::Right
+            };
+            note.position = at;
+            note.rotation = 0.0;
+            note.justify = Justify::new(horizontal, Vertical::Middle);
+        }
+    }
+    /// the arrowheads of the attached arms moved to where the points they
+    /// follow are now, the bends and the note staying put
+    pub fn follow(&mut self, anchors: &[Anchor]) -> bool {
+        if anchors.len() != self.attached.len() {
+            return false;
+        }
+        let mut arms = self.arms.clone();
+        for (arm, anchor) in self.attached.iter().zip(anchors) {
+            match (arms.get_mut(*arm).and_then(|arm| arm.first_mut()), anchor) {
+                (Some(tip), Anchor::Point(p)) => *tip = *p,
+                _ => return false,
+            }
+        }
+        self.arms = arms;
+        self.place_note();
+        true
+    }
+    /// this leader looking the way `look` says
+    pub fn restyle(&mut self, look: &LeaderLook) {
+        self.look = *look;
+        if let Some(note) = self.note.as_mut() {
+            note.height = look.text_height;
+            note.lettering = look.lettering;
+        }
+        self.place_note();
+    }
+    /// the arms, arrowheads and landing, round arrowheads staying within
+    /// `tolerance` of their true shape
+    pub fn lines(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let mut lines = vec![];
+        for arm in self.arms.iter() {
+            let mut points = arm.clone();
+            points.push(self.anchor);
+            lines.extend(connect(&points));
+            if let Some(pointing) = (points[0] - points[1]).normalize() {
+                let head = self.look.arrowhead;
+                lines.extend(head.lines(points[0], pointing, self.look.arrow_size, tolerance));
+            }
+        }
+        if self.look.landing > 0.0 {
+            lines.push((self.anchor, self.landing_end()));
+        }
+        lines
+    }
+    pub fn snap_points(&self) -> Vec<Point> {
+        let mut points: Vec<Point> = self.arms.iter().flatten().copied().collect();
+        points.push(self.anchor);
+        points.push(self.landing_end());
+        points
+    }
+    /// this leader moved by `transform`, its landing and note staying level
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        let mut leader = Leader {
+            arms: self
+                .arms
+                .iter()
+                .map(|arm| arm.iter().map(|p| transform.apply(*p)).collect())
+                .collect(),
+            anchor: transform.apply(self.anchor),
+            note: self.note.as_ref().map(|note| note.transformed(transform)),
+            ..self.clone()
+        };
+        leader.place_note();
+        leader
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let mut segments = self.lines(tolerance);
+        if let Some(note) = &self.note {
+            segments.extend(note.segments());
+        }
+        segments
+    }
+}
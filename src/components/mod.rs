@@ -1,13 +1,65 @@
-use amethyst::renderer::{debug_drawing::DebugLinesComponent, palette::Srgba};
-use rand::prelude::*;
+use amethyst::renderer::palette::Srgba;
 use specs::prelude::*;
-
-use nalgebra::geometry::Point as nPoint;
+use std::ops::{Add, Mul, Sub};
 
 use crate::common::GenerationID;
 
 use crate::resources::Layer;
 use crate::resources::LineType;
+use crate::resources::{DimStyle, LeaderStyle, TextStyle, Units};
+
+mod affine;
+mod arc;
+mod attribute;
+mod boundary;
+mod catenary;
+mod chamfer;
+mod circle;
+mod dependency;
+mod dimension;
+mod ellipse;
+mod fillet;
+mod font;
+mod hatch;
+mod insert;
+mod intersect;
+mod leader;
+mod offset;
+mod parabola;
+mod piece;
+mod polyline;
+mod spline;
+mod text;
+mod trim;
+
+pub use affine::Affine;
+pub use arc::Arc;
+pub use attribute::{AttDef, Attribute};
+pub use boundary::find_boundary;
+pub use catenary::Catenary;
+pub use chamfer::{chamfer, Chamfer};
+pub use circle::Circle;
+pub use dependency::{Anchor, Dependent, Feature, Link};
+pub use dimension::{DimLook, Dimension, Measure};
+pub use ellipse::Ellipse;
+pub use fillet::fillet;
+pub use hatch::{Fill, Hatch, Pattern, PatternLine};
+pub use insert::{Array, Insert};
+pub use intersect::Primitive;
+pub use leader::{Arrowhead, Leader, LeaderLook};
+pub use offset::offset;
+pub use parabola::Parabola;
+pub use polyline::{bulge_from_heading, end_heading, Polyline, Vertex};
+pub use spline::Spline;
+pub use text::{Horizontal, Justify, Lettering, MText, Text, Vertical};
+pub use trim::{crossings, extend, trim};
+
+/// most pieces a single curve is ever split into for display
+const MAX_DIVISIONS: usize = 1024;
+
+/// how closely curves are followed when they have to be measured or turned
+/// into straight pieces, relative to their size
+const FINE_TOLERANCE: f32 = 1e-5;
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct ActiveCamera;
@@ -16,6 +68,23 @@ impl Component for ActiveCamera {
     type Storage = NullStorage<Self>;
 }
 
+/// marks drawables picked while a command collects its inputs
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Selected;
+
+impl Component for Selected {
+    type Storage = NullStorage<Self>;
+}
+
+/// marks drawables a command shows while it is still collecting inputs,
+/// which are thrown away before it runs again
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Preview;
+
+impl Component for Preview {
+    type Storage = NullStorage<Self>;
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct FullColor {
     pub r: u8,
@@ -25,14 +94,538 @@ pub struct FullColor {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Drawable {
-    Point(Point),
     Line(Line),
-    LineSet(Set),
+    Arc(Arc),
+    Circle(Circle),
+    Ellipse(Ellipse),
+    Polyline(Polyline),
+    Catenary(Catenary),
+    Parabola(Parabola),
+    Spline(Spline),
+    Text(Text),
+    MText(MText),
+    Dimension(Dimension),
+    Leader(Leader),
+    Hatch(Hatch),
+    Insert(Insert),
+    AttDef(AttDef),
     NamedGroup(Group),
 }
 
+/// flagged so drawables derived from others hear when they change
 impl Component for Drawable {
-    type Storage = VecStorage<Self>;
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
+impl Drawable {
+    /// the straight segments that make up this drawable on screen, curves
+    /// staying within `tolerance` of their true shape
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        match self {
+            Drawable::Line(line) => vec![(line.start, line.end)],
+            Drawable::Arc(arc) => arc.segments(tolerance),
+            Drawable::Circle(circle) => circle.segments(tolerance),
+            Drawable::Ellipse(ellipse) => ellipse.segments(tolerance),
+            Drawable::Polyline(polyline) => polyline.segments(tolerance),
+            Drawable::Catenary(catenary) => catenary.segments(tolerance),
+            Drawable::Parabola(parabola) => parabola.segments(tolerance),
+            Drawable::Spline(spline) => spline.segments(tolerance),
+            Drawable::Text(text) => text.segments(),
+            Drawable::MText(mtext) => mtext.segments(),
+            Drawable::Dimension(dimension) => dimension.segments(tolerance),
+            Drawable::Leader(leader) => leader.segments(tolerance),
+            Drawable::Hatch(hatch) => hatch.segments(tolerance),
+            Drawable::Insert(insert) => insert.segments(tolerance),
+            Drawable::AttDef(field) => field.text.segments(),
+            Drawable::NamedGroup(group) => group
+                .inner
+                .iter()
+                .flat_map(|d| d.segments(tolerance))
+                .collect(),
+        }
+    }
+    /// distance from `p` to the closest part of this drawable as displayed
+    pub fn distance_to(&self, p: Point, tolerance: f32) -> f32 {
+        self.segments(tolerance)
+            .into_iter()
+            .map(|(a, b)| p.distance_to_segment(a, b))
+            .fold(f32::INFINITY, f32::min)
+    }
+    /// points which clicks near them snap to: ends, middles, centers and the like
+    pub fn snap_points(&self) -> Vec<Point> {
+        match self {
+            Drawable::Line(line) => vec![line.start, line.end, (line.start + line.end) * 0.5],
+            Drawable::Arc(arc) => vec![
+                arc.start_point(),
+                arc.end_point(),
+                arc.point_at(arc.start_angle + arc.sweep() / 2.0),
+                arc.center,
+            ],
+            Drawable::Circle(circle) => {
+                let mut points: Vec<Point> = (0..4)
+                    .map(|i| {
+                        let angle = std::f32::consts::FRAC_PI_2 * i as f32;
+                        circle.center + Point::from_angle(angle) * circle.radius
+                    })
+                    .collect();
+                points.push(circle.center);
+                points
+            }
+            Drawable::Ellipse(ellipse) => {
+                let sweep = ellipse.sweep();
+                let mut points: Vec<Point> = (0..=4)
+                    .map(|i| ellipse.point_at(ellipse.start + sweep * i as f32 / 4.0))
+                    .collect();
+                points.push(ellipse.center);
+                points
+            }
+            Drawable::Polyline(polyline) => polyline.vertices.iter().map(|v| v.position).collect(),
+            Drawable::Catenary(catenary) => {
+                let mut points = vec![catenary.start, catenary.end, catenary.midpoint()];
+                points.extend(catenary.lowest_point());
+                points
+            }
+            Drawable::Parabola(parabola) => {
+                vec![parabola.start, parabola.end, parabola.point_at(0.5)]
+            }
+            Drawable::Spline(spline) => vec![spline.start_point(), spline.end_point()],
+            Drawable::Text(text) => vec![text.position],
+            Drawable::MText(mtext) => vec![mtext.position],
+            Drawable::Dimension(dimension) => dimension.snap_points(),
+            Drawable::Leader(leader) => leader.snap_points(),
+            Drawable::Hatch(hatch) => hatch.snap_points(),
+            Drawable::Insert(insert) => insert.snap_points(),
+            Drawable::AttDef(field) => vec![field.text.position],
+            Drawable::NamedGroup(group) => {
+                group.inner.iter().flat_map(|d| d.snap_points()).collect()
+            }
+        }
+    }
+    /// how long this drawable is measured along its path
+    pub fn length(&self) -> f32 {
+        match self {
+            Drawable::Text(_)
+            | Drawable::MText(_)
+            | Drawable::Dimension(_)
+            | Drawable::Leader(_)
+            | Drawable::Hatch(_)
+            | Drawable::AttDef(_) => 0.0,
+            Drawable::Line(line) => line.start.distance(line.end),
+            Drawable::Arc(arc) => arc.radius * arc.sweep(),
+            Drawable::Circle(circle) => 2.0 * std::f32::consts::PI * circle.radius,
+            Drawable::Ellipse(ellipse) => ellipse
+                .segments(ellipse.major.length() * FINE_TOLERANCE)
+                .into_iter()
+                .map(|(a, b)| a.distance(b))
+                .sum(),
+            Drawable::Polyline(polyline) => polyline.length(),
+            Drawable::Catenary(catenary) => catenary.length,
+            Drawable::Parabola(parabola) => {
+                let size = parabola.start.distance(parabola.end);
+                parabola
+                    .segments(size * FINE_TOLERANCE)
+                    .into_iter()
+                    .map(|(a, b)| a.distance(b))
+                    .sum()
+            }
+            Drawable::Spline(spline) => {
+                let size = spline.start_point().distance(spline.end_point()).max(1.0);
+                spline
+                    .segments(size * FINE_TOLERANCE)
+                    .into_iter()
+                    .map(|(a, b)| a.distance(b))
+                    .sum()
+            }
+            Drawable::Insert(insert) => insert.copies().iter().map(|d| d.length()).sum(),
+            Drawable::NamedGroup(group) => group.inner.iter().map(|d| d.length()).sum(),
+        }
+    }
+    /// this drawable moved by `transform`, curves which can't keep their
+    /// shape under it turning into whatever they become
+    pub fn transformed(&self, transform: &Affine) -> Drawable {
+        let tolerance = transform.tolerance();
+        match self {
+            Drawable::Line(line) => Drawable::Line(Line::new(
+                transform.apply(line.start),
+                transform.apply(line.end),
+                line.props,
+            )),
+            Drawable::Arc(arc) => arc.transformed(transform),
+            Drawable::Circle(circle) => circle.transformed(transform),
+            Drawable::Ellipse(ellipse) => Drawable::Ellipse(ellipse.transformed(transform)),
+            Drawable::Polyline(polyline) => {
+                Drawable::Polyline(polyline.transformed(transform, tolerance))
+            }
+            Drawable::Catenary(catenary) => catenary.transformed(transform, tolerance),
+            Drawable::Parabola(parabola) => Drawable::Parabola(parabola.transformed(transform)),
+            Drawable::Spline(spline) => Drawable::Spline(spline.transformed(transform)),
+            Drawable::Text(text) => Drawable::Text(text.transformed(transform)),
+            Drawable::MText(mtext) => Drawable::MText(mtext.transformed(transform)),
+            Drawable::Dimension(dimension) => Drawable::Dimension(dimension.transformed(transform)),
+            Drawable::Leader(leader) => Drawable::Leader(leader.transformed(transform)),
+            Drawable::Hatch(hatch) => Drawable::Hatch(hatch.transformed(transform)),
+            Drawable::Insert(insert) => Drawable::Insert(insert.transformed(transform)),
+            Drawable::AttDef(field) => Drawable::AttDef(field.transformed(transform)),
+            Drawable::NamedGroup(group) => Drawable::NamedGroup(group.transformed(transform)),
+        }
+    }
+    /// the pieces left after cutting this drawable where it passes nearest
+    /// `p`, if it's a kind that can be cut and `p` isn't at one of its ends
+    pub fn broken_at(&self, p: Point, tolerance: f32) -> Option<Vec<Drawable>> {
+        match self {
+            Drawable::Line(line) => {
+                let along = line.end - line.start;
+                let t = (p - line.start).dot(along) / along.dot(along);
+                if !(t > 0.0 && t < 1.0) {
+                    return None;
+                }
+                let cut = line.start + along * t;
+                Some(vec![
+                    Drawable::Line(Line::new(line.start, cut, line.props)),
+                    Drawable::Line(Line::new(cut, line.end, line.props)),
+                ])
+            }
+            Drawable::Arc(arc) => {
+                let angle = (p - arc.center).angle();
+                let into = (angle - arc.start_angle).rem_euclid(2.0 * std::f32::consts::PI);
+                if into <= f32::EPSILON || into >= arc.sweep() {
+                    return None;
+                }
+                Some(vec![
+                    Drawable::Arc(Arc::new(
+                        arc.center,
+                        arc.radius,
+                        arc.start_angle,
+                        angle,
+                        arc.props,
+                    )),
+                    Drawable::Arc(Arc::new(
+                        arc.center,
+                        arc.radius,
+                        angle,
+                        arc.end_angle,
+                        arc.props,
+                    )),
+                ])
+            }
+            Drawable::Polyline(polyline) => {
+                let pieces = polyline.broken_at(p, tolerance);
+                // cut at an open end it isn't broken at all, while a closed
+                // one opens up into a single piece
+                let wanted = if polyline.closed { 1 } else { 2 };
+                if pieces.len() != wanted
+                    || pieces.iter().any(|piece| piece.length() <= f32::EPSILON)
+                {
+                    return None;
+                }
+                Some(pieces.into_iter().map(Drawable::Polyline).collect())
+            }
+            Drawable::Spline(spline) => {
+                let (left, right) = spline.split(spline.closest_parameter(p, tolerance))?;
+                Some(vec![Drawable::Spline(left), Drawable::Spline(right)])
+            }
+            _ => None,
+        }
+    }
+    /// this line or arc trimmed or stretched to end at `at`, which is taken
+    /// to lie along it, keeping the part on the side of `keep`
+    pub fn trimmed_to(&self, at: Point, keep: Point) -> Option<Drawable> {
+        match self {
+            Drawable::Line(line) => {
+                let along = line.end - line.start;
+                let length = along.dot(along);
+                if length <= f32::EPSILON {
+                    return None;
+                }
+                let t = |p: Point| (p - line.start).dot(along) / length;
+                let at = line.start + along * t(at);
+                if t(keep) > t(at) {
+                    Some(Drawable::Line(Line::new(at, line.end, line.props)))
+                } else {
+                    Some(Drawable::Line(Line::new(line.start, at, line.props)))
+                }
+            }
+            Drawable::Arc(arc) => {
+                let full = 2.0 * std::f32::consts::PI;
+                let angle = (at - arc.center).angle();
+                let into = (angle - arc.start_angle).rem_euclid(full);
+                let sweep = arc.sweep();
+                let keep_start = if into <= sweep {
+                    // cut where it lies, keeping the side `keep` is on
+                    ((keep - arc.center).angle() - arc.start_angle).rem_euclid(full) < into
+                } else {
+                    // stretched out from whichever end is nearer
+                    into - sweep < full - into
+                };
+                let (start, end) = if keep_start {
+                    (arc.start_angle, angle)
+                } else {
+                    (angle, arc.end_angle)
+                };
+                Some(Drawable::Arc(Arc::new(
+                    arc.center, arc.radius, start, end, arc.props,
+                )))
+            }
+            _ => None,
+        }
+    }
+    /// whether this drawable, or anything in it, is lettered in the text
+    /// style `id`
+    pub fn uses_text_style(&self, id: GenerationID<TextStyle>) -> bool {
+        match self {
+            Drawable::Text(text) => text.lettering.style == id,
+            Drawable::MText(mtext) => mtext.lettering.style == id,
+            Drawable::AttDef(field) => field.text.lettering.style == id,
+            Drawable::Dimension(dimension) => dimension.look.lettering.style == id,
+            Drawable::Leader(leader) => leader.look.lettering.style == id,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                group.inner.iter().any(|member| member.uses_text_style(id))
+            }
+            _ => false,
+        }
+    }
+    /// bring text in the style `id` up to date with how the style looks now
+    pub fn restyle(&mut self, id: GenerationID<TextStyle>, style: &TextStyle) {
+        match self {
+            Drawable::Text(text) if text.lettering.style == id => text.restyle(style),
+            Drawable::MText(mtext) if mtext.lettering.style == id => mtext.restyle(style),
+            Drawable::AttDef(field) if field.text.lettering.style == id => {
+                field.text.restyle(style)
+            }
+            Drawable::Dimension(dimension) if dimension.look.lettering.style == id => {
+                dimension.look.lettering = Lettering::from_style(id, style)
+            }
+            Drawable::Leader(leader) if leader.look.lettering.style == id => {
+                let look = LeaderLook {
+                    lettering: Lettering::from_style(id, style),
+                    ..leader.look
+                };
+                leader.restyle(&look)
+            }
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                for member in group.inner.iter_mut() {
+                    member.restyle(id, style);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// whether this drawable, or anything in it, is a dimension in the style
+    /// `id`
+    pub fn uses_dim_style(&self, id: GenerationID<DimStyle>) -> bool {
+        match self {
+            Drawable::Dimension(dimension) => dimension.look.style == id,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                group.inner.iter().any(|member| member.uses_dim_style(id))
+            }
+            _ => false,
+        }
+    }
+    /// bring dimensions in the style `id` up to date with `look`
+    pub fn restyle_dimension(&mut self, id: GenerationID<DimStyle>, look: &DimLook) {
+        match self {
+            Drawable::Dimension(dimension) if dimension.look.style == id => dimension.look = *look,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                for member in group.inner.iter_mut() {
+                    member.restyle_dimension(id, look);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// whether this drawable, or anything in it, is a leader in the style `id`
+    pub fn uses_leader_style(&self, id: GenerationID<LeaderStyle>) -> bool {
+        match self {
+            Drawable::Leader(leader) => leader.look.style == id,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => group
+                .inner
+                .iter()
+                .any(|member| member.uses_leader_style(id)),
+            _ => false,
+        }
+    }
+    /// bring leaders in the style `id` up to date with `look`
+    pub fn restyle_leader(&mut self, id: GenerationID<LeaderStyle>, look: &LeaderLook) {
+        match self {
+            Drawable::Leader(leader) if leader.look.style == id => leader.restyle(look),
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                for member in group.inner.iter_mut() {
+                    member.restyle_leader(id, look);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// whether this drawable, or anything in it, writes a measurement in
+    /// anything but `units`
+    pub fn uses_other_units(&self, units: Units) -> bool {
+        match self {
+            Drawable::Dimension(dimension) => dimension.units != units,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => group
+                .inner
+                .iter()
+                .any(|member| member.uses_other_units(units)),
+            _ => false,
+        }
+    }
+    /// write any measurements in this drawable in `units`
+    pub fn set_units(&mut self, units: Units) {
+        match self {
+            Drawable::Dimension(dimension) => dimension.units = units,
+            Drawable::Insert(Insert {
+                definition: group, ..
+            })
+            | Drawable::NamedGroup(group) => {
+                for member in group.inner.iter_mut() {
+                    member.set_units(units);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// whether this drawable, or anything in it, draws the block `id`
+    pub fn uses_block(&self, id: GenerationID<Group>) -> bool {
+        match self {
+            Drawable::Insert(insert) => insert.uses(id),
+            Drawable::NamedGroup(group) => group.inner.iter().any(|member| member.uses_block(id)),
+            _ => false,
+        }
+    }
+    /// draw `definition` wherever this drawable draws the block `id`
+    pub fn redefine(&mut self, id: GenerationID<Group>, definition: &Group) {
+        match self {
+            Drawable::Insert(insert) => insert.redefine(id, definition),
+            Drawable::NamedGroup(group) => {
+                for member in group.inner.iter_mut() {
+                    member.redefine(id, definition);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// this drawable as a closed polyline a hatch can fill, if it closes,
+    /// curves being followed closely
+    pub fn boundary_loop(&self) -> Option<Polyline> {
+        let tolerance = self.length() * FINE_TOLERANCE;
+        let corners = |segments: Vec<(Point, Point)>| -> Vec<Vertex> {
+            segments.iter().map(|(a, _)| Vertex::new(*a)).collect()
+        };
+        let boundary = match self {
+            Drawable::Polyline(polyline) => {
+                let mut boundary = polyline.clone();
+                let first = boundary.vertices.first()?.position;
+                let last = boundary.vertices.last()?.position;
+                if !boundary.closed && first.distance(last) <= tolerance {
+                    boundary.vertices.pop();
+                    boundary.closed = true;
+                }
+                if !boundary.closed || boundary.vertices.len() < 2 {
+                    return None;
+                }
+                boundary
+            }
+            Drawable::Circle(circle) => {
+                // four quarter circle arcs
+                let bulge = (std::f32::consts::PI / 8.0).tan();
+                let vertices = (0..4)
+                    .map(|i| Vertex {
+                        bulge,
+                        ..Vertex::new(
+                            circle.center
+                                + Point::from_angle(i as f32 * std::f32::consts::FRAC_PI_2)
+                                    * circle.radius,
+                        )
+                    })
+                    .collect();
+                Polyline::new(vertices, true, circle.props)
+            }
+            Drawable::Ellipse(ellipse)
+                if ellipse.sweep() >= 2.0 * std::f32::consts::PI - f32::EPSILON =>
+            {
+                Polyline::new(corners(ellipse.segments(tolerance)), true, ellipse.props)
+            }
+            Drawable::Spline(spline)
+                if spline.start_point().distance(spline.end_point()) <= tolerance =>
+            {
+                Polyline::new(corners(spline.segments(tolerance)), true, spline.props)
+            }
+            _ => return None,
+        };
+        Some(boundary)
+    }
+    /// the part of this drawable a dependent following `feature` is given,
+    /// if it has that part
+    pub fn feature(&self, feature: Feature) -> Option<Anchor> {
+        let point = match (self, feature) {
+            (_, Feature::Whole) => return Some(Anchor::Shape(Box::new(self.clone()))),
+            (Drawable::Line(line), Feature::Start) => line.start,
+            (Drawable::Line(line), Feature::End) => line.end,
+            (Drawable::Line(line), Feature::Center) => (line.start + line.end) * 0.5,
+            (Drawable::Arc(arc), Feature::Start) => arc.start_point(),
+            (Drawable::Arc(arc), Feature::End) => arc.end_point(),
+            (Drawable::Arc(arc), Feature::Center) => arc.center,
+            (Drawable::Circle(circle), Feature::Center) => circle.center,
+            (Drawable::Ellipse(ellipse), Feature::Center) => ellipse.center,
+            (Drawable::Polyline(polyline), Feature::Start) => polyline.vertices.first()?.position,
+            (Drawable::Polyline(polyline), Feature::End) => polyline.vertices.last()?.position,
+            (Drawable::Spline(spline), Feature::Start) => spline.start_point(),
+            (Drawable::Spline(spline), Feature::End) => spline.end_point(),
+            (Drawable::Insert(insert), Feature::Center) => insert.position,
+            _ => return None,
+        };
+        Some(Anchor::Point(point))
+    }
+    /// work this drawable out again from where its sources are now, false if
+    /// it can't follow them
+    pub fn follow(&mut self, anchors: &[Anchor]) -> bool {
+        match self {
+            Drawable::Dimension(dimension) => dimension.follow(anchors),
+            Drawable::Hatch(hatch) => hatch.follow(anchors),
+            Drawable::Leader(leader) => leader.follow(anchors),
+            _ => false,
+        }
+    }
+    pub fn properties(&self) -> Option<&Properties> {
+        match self {
+            Drawable::Line(line) => Some(&line.props),
+            Drawable::Arc(arc) => Some(&arc.props),
+            Drawable::Circle(circle) => Some(&circle.props),
+            Drawable::Ellipse(ellipse) => Some(&ellipse.props),
+            Drawable::Polyline(polyline) => Some(&polyline.props),
+            Drawable::Catenary(catenary) => Some(&catenary.props),
+            Drawable::Parabola(parabola) => Some(&parabola.props),
+            Drawable::Spline(spline) => Some(&spline.props),
+            Drawable::Text(text) => Some(&text.props),
+            Drawable::MText(mtext) => Some(&mtext.props),
+            Drawable::Dimension(dimension) => Some(&dimension.props),
+            Drawable::Leader(leader) => Some(&leader.props),
+            Drawable::Hatch(hatch) => Some(&hatch.props),
+            Drawable::Insert(insert) => Some(&insert.props),
+            Drawable::AttDef(field) => Some(&field.text.props),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -41,52 +634,140 @@ pub struct Point {
     pub y: f32,
 }
 
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Point { x, y }
+    }
+    pub fn distance(self, other: Point) -> f32 {
+        (other.x - self.x).hypot(other.y - self.y)
+    }
+    pub fn from_angle(angle: f32) -> Self {
+        Point::new(angle.cos(), angle.sin())
+    }
+    pub fn dot(self, other: Point) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+    pub fn cross(self, other: Point) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+    /// angle of this point as a direction from the origin
+    pub fn angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+    /// this direction turned a quarter turn counter-clockwise
+    pub fn perp(self) -> Point {
+        Point::new(-self.y, self.x)
+    }
+    /// this direction turned counter-clockwise by `angle`
+    pub fn rotate(self, angle: f32) -> Point {
+        let (sin, cos) = angle.sin_cos();
+        Point::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+    pub fn normalize(self) -> Option<Point> {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            None
+        } else {
+            Some(self * (1.0 / length))
+        }
+    }
+    pub fn distance_to_segment(self, a: Point, b: Point) -> f32 {
+        let ab = b - a;
+        let len2 = ab.dot(ab);
+        if len2 == 0.0 {
+            return self.distance(a);
+        }
+        let t = ((self - a).dot(ab) / len2).clamp(0.0, 1.0);
+        self.distance(a + ab * t)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f32> for Point {
+    type Output = Point;
+    fn mul(self, rhs: f32) -> Point {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+/// center of the circle through three points, if they aren't in a line
+pub fn circumcenter(a: Point, b: Point, c: Point) -> Option<Point> {
+    let ab = b - a;
+    let ac = c - a;
+    let d = 2.0 * ab.cross(ac);
+    if d.abs() <= f32::EPSILON {
+        return None;
+    }
+    let ab2 = ab.dot(ab);
+    let ac2 = ac.dot(ac);
+    let offset = Point::new(ac.y * ab2 - ab.y * ac2, ab.x * ac2 - ac.x * ab2) * (1.0 / d);
+    Some(a + offset)
+}
+
+/// how many straight pieces an arc needs to stay within `tolerance` of the true curve
+pub fn arc_divisions(radius: f32, sweep: f32, tolerance: f32) -> usize {
+    let step = if tolerance < radius {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        std::f32::consts::FRAC_PI_2
+    };
+    ((sweep.abs() / step).ceil() as usize).clamp(1, MAX_DIVISIONS)
+}
+
+/// segments joining each point to the next
+pub fn connect(points: &[Point]) -> Vec<(Point, Point)> {
+    points.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// layer, color and line style shared by every kind of drawable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Properties {
+    pub layer: GenerationID<Layer>,
+    pub color: Color,
+    pub scale: f32,
+    pub linetype: GenerationID<LineType>,
+    pub weight: f32,
+}
+
+impl Properties {
+    /// properties for a new drawable, inheriting color and line type from its layer
+    pub fn from_layer(id: GenerationID<Layer>, layer: &Layer) -> Self {
+        Properties {
+            layer: id,
+            color: layer.color,
+            scale: 1.0,
+            linetype: layer.line_type,
+            weight: 1.0,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Line {
-    start: Point,
-    end: Point,
-    layer: GenerationID<Layer>,
-    color: Color,
-    scale: f32,
-    linetype: GenerationID<LineType>,
-    weight: f32,
+    pub start: Point,
+    pub end: Point,
+    pub props: Properties,
 }
 
 impl Line {
-    pub fn create(
-        style: GenerationID<LineType>,
-        layer: GenerationID<Layer>,
-    ) -> (Drawable, DebugLinesComponent) {
-        let mut rng = rand::thread_rng();
-        let a = Point {
-            x: rng.gen_range(0, 600) as f32,
-            y: rng.gen_range(0, 600) as f32,
-        };
-        let b = Point {
-            x: rng.gen_range(0, 600) as f32,
-            y: rng.gen_range(0, 600) as f32,
-        };
-        let c = FullColor {
-            r: 234,
-            g: 65,
-            b: 212,
-        };
-        let line = Drawable::Line(Line {
-            start: a,
-            end: b,
-            layer,
-            color: Color::Full(c),
-            scale: 1.0,
-            linetype: style,
-            weight: 1.0,
-        });
-        let mut debug_lines = DebugLinesComponent::new();
-        let start: nPoint<f32, nalgebra::base::dimension::U3> =
-            nPoint::from_slice(&[a.x, a.y, 0.0]);
-        let end: nPoint<f32, nalgebra::base::dimension::U3> = nPoint::from_slice(&[b.x, b.y, 0.0]);
-        let color = Srgba::new(c.r as f32, c.g as f32, c.b as f32, 1.0);
-        debug_lines.add_line(start, end, color);
-        (line, debug_lines)
+    pub fn new(start: Point, end: Point, props: Properties) -> Self {
+        Line { start, end, props }
     }
 }
 
@@ -96,12 +777,206 @@ pub enum Color {
     Full(FullColor),
 }
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Set {
-    inner: Vec<Line>,
+impl Color {
+    pub fn srgba(self) -> Srgba {
+        let FullColor { r, g, b } = match self {
+            Color::Fixed(index) => Color::palette(index),
+            Color::Full(c) => c,
+        };
+        Srgba::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+    }
+    /// the standard indexed colors, anything unknown is drawn white
+    fn palette(index: u8) -> FullColor {
+        let (r, g, b) = match index {
+            1 => (255, 0, 0),
+            2 => (255, 255, 0),
+            3 => (0, 255, 0),
+            4 => (0, 255, 255),
+            5 => (0, 0, 255),
+            6 => (255, 0, 255),
+            8 => (128, 128, 128),
+            9 => (192, 192, 192),
+            _ => (255, 255, 255),
+        };
+        FullColor { r, g, b }
+    }
 }
 
+/// Drawables kept together under a name, as a block definition is, placed
+/// relative to a base point.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Group {
+    pub name: String,
+    pub base: Point,
     inner: Vec<Drawable>,
 }
+
+impl Group {
+    pub fn new(name: &str, base: Point, members: Vec<Drawable>) -> Self {
+        Group {
+            name: name.to_string(),
+            base,
+            inner: members,
+        }
+    }
+    pub fn members(&self) -> &[Drawable] {
+        &self.inner
+    }
+    pub fn members_mut(&mut self) -> &mut [Drawable] {
+        &mut self.inner
+    }
+    /// this group and its base point moved by `transform`
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        Group {
+            name: self.name.clone(),
+            base: transform.apply(self.base),
+            inner: self
+                .inner
+                .iter()
+                .map(|d| d.transformed(transform))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::{Arc, Color, Drawable, Line, Point, Polyline, Properties, Vertex};
+    use crate::resources::{Layer, Layers, LineType, LineTypes};
+
+    /// properties on a plain layer, for shapes built in tests
+    pub(super) fn props() -> Properties {
+        let mut line_types = LineTypes::new();
+        let line_type = line_types.push(LineType {
+            draw_line: LineType::line_type_continous,
+            period: 0.0,
+        });
+        let mut layers = Layers::new();
+        let layer = layers.push(Layer {
+            name: "0".to_string(),
+            color: Color::Fixed(7),
+            line_type,
+            hidden: false,
+            frozen: false,
+            locked: false,
+        });
+        Properties::from_layer(layer, layers.get(layer).unwrap())
+    }
+
+    /// a polyline through `points`, all straight
+    pub(super) fn polyline(points: &[(f32, f32)], closed: bool) -> Polyline {
+        let vertices = points
+            .iter()
+            .map(|(x, y)| Vertex::new(Point::new(*x, *y)))
+            .collect();
+        Polyline::new(vertices, closed, props())
+    }
+
+    /// whether two points are the same but for rounding
+    pub(super) fn near(a: Point, b: Point) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    fn line(ax: f32, ay: f32, bx: f32, by: f32) -> Drawable {
+        Drawable::Line(Line::new(Point::new(ax, ay), Point::new(bx, by), props()))
+    }
+
+    #[test]
+    fn trimming_a_line_keeps_the_side_picked() {
+        let whole = line(0.0, 0.0, 4.0, 0.0);
+        let at = Point::new(1.0, 0.0);
+        assert_eq!(
+            whole.trimmed_to(at, Point::new(3.0, 0.0)),
+            Some(line(1.0, 0.0, 4.0, 0.0))
+        );
+        assert_eq!(
+            whole.trimmed_to(at, Point::new(0.5, 0.0)),
+            Some(line(0.0, 0.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn trimming_a_line_past_its_end_stretches_it() {
+        let whole = line(0.0, 0.0, 4.0, 0.0);
+        // and a point off to one side is taken back onto the line
+        let stretched = whole.trimmed_to(Point::new(6.0, 1.0), Point::new(1.0, 0.0));
+        assert_eq!(stretched, Some(line(0.0, 0.0, 6.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_length_line_cant_be_trimmed() {
+        let dot = line(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(
+            dot.trimmed_to(Point::new(2.0, 1.0), Point::new(3.0, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn trimming_an_arc_keeps_the_side_picked() {
+        let quarter = Arc::new(Point::new(0.0, 0.0), 1.0, 0.0, FRAC_PI_2, props());
+        let middle = Point::from_angle(FRAC_PI_4);
+        let start = match Drawable::Arc(quarter).trimmed_to(middle, Point::new(1.0, 0.1)) {
+            Some(Drawable::Arc(arc)) => arc,
+            other => panic!("expected an arc, got {:?}", other),
+        };
+        assert!(near(start.start_point(), Point::new(1.0, 0.0)));
+        assert!(near(start.end_point(), middle));
+        let end = match Drawable::Arc(quarter).trimmed_to(middle, Point::new(0.1, 1.0)) {
+            Some(Drawable::Arc(arc)) => arc,
+            other => panic!("expected an arc, got {:?}", other),
+        };
+        assert!(near(end.start_point(), middle));
+        assert!(near(end.end_point(), Point::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn trimming_an_arc_past_its_end_stretches_the_nearer_end() {
+        let quarter = Arc::new(Point::new(0.0, 0.0), 1.0, 0.0, FRAC_PI_2, props());
+        let past_end = Point::from_angle(FRAC_PI_2 + 0.5);
+        let stretched = match Drawable::Arc(quarter).trimmed_to(past_end, past_end) {
+            Some(Drawable::Arc(arc)) => arc,
+            other => panic!("expected an arc, got {:?}", other),
+        };
+        assert!(near(stretched.start_point(), Point::new(1.0, 0.0)));
+        assert!(near(stretched.end_point(), past_end));
+    }
+
+    #[test]
+    fn breaking_an_open_polyline_at_its_end_does_nothing() {
+        let open = Drawable::Polyline(polyline(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)], false));
+        assert_eq!(open.broken_at(Point::new(0.0, 0.0), 1e-3), None);
+        assert_eq!(open.broken_at(Point::new(2.0, 2.0), 1e-3), None);
+        let pieces = open.broken_at(Point::new(1.0, 0.0), 1e-3).unwrap();
+        assert_eq!(pieces.len(), 2);
+    }
+
+    #[test]
+    fn breaking_a_polyline_with_no_length_does_nothing() {
+        for closed in [false, true] {
+            let dot = Drawable::Polyline(polyline(&[(1.0, 1.0), (1.0, 1.0), (1.0, 1.0)], closed));
+            assert_eq!(dot.broken_at(Point::new(1.0, 1.0), 1e-3), None);
+        }
+    }
+
+    #[test]
+    fn breaking_a_line_at_its_end_does_nothing() {
+        let whole = line(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(whole.broken_at(Point::new(4.0, 0.0), 1e-3), None);
+        assert_eq!(
+            line(1.0, 1.0, 1.0, 1.0).broken_at(Point::new(1.0, 1.0), 1e-3),
+            None
+        );
+    }
+
+    #[test]
+    fn only_lines_and_arcs_are_trimmed_to_a_point() {
+        let polyline = Drawable::Polyline(polyline(&[(0.0, 0.0), (2.0, 0.0)], false));
+        assert_eq!(
+            polyline.trimmed_to(Point::new(1.0, 0.0), Point::new(2.0, 0.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::f32::consts::PI;
+
+use super::{
+    piece::{split_pieces, Piece},
+    Arc, Circle, Drawable, Line, Point, Polyline, Spline, Vertex,
+};
+
+/// most points a curve offset along its samples is fitted through
+const MAX_FIT_POINTS: usize = 64;
+
+/// which side of `drawable` `p` is on, going the way it's drawn: 1 on the
+/// left, -1 on the right
+fn side_of(drawable: &Drawable, p: Point, tolerance: f32) -> Option<f32> {
+    let (a, b) = drawable
+        .segments(tolerance)
+        .into_iter()
+        .min_by(|(a, b), (c, d)| {
+            p.distance_to_segment(*a, *b)
+                .partial_cmp(&p.distance_to_segment(*c, *d))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+    let cross = (b - a).cross(p - a);
+    if cross.abs() <= f32::EPSILON {
+        None
+    } else {
+        Some(cross.signum())
+    }
+}
+
+/// `piece` moved `shift` to its left, an arc growing or shrinking around
+/// the same center, unless it shrinks away to nothing
+fn offset_piece(piece: Piece, shift: f32) -> Option<Piece> {
+    match piece.circle() {
+        Some((center, radius)) => {
+            // the left of an arc turning left is its inside
+            let grown = radius - shift * piece.sweep().signum();
+            if grown <= f32::EPSILON {
+                return None;
+            }
+            let scale = grown / radius;
+            Some(Piece::new(
+                center + (piece.from - center) * scale,
+                center + (piece.to - center) * scale,
+                piece.bulge,
+            ))
+        }
+        None => {
+            let left = (piece.to - piece.from).normalize()?.perp() * shift;
+            Some(Piece::new(piece.from + left, piece.to + left, 0.0))
+        }
+    }
+}
+
+/// `piece` running between `from` and `to` instead, along the same line or
+/// round the same circle the same way
+fn reshaped(piece: Piece, from: Point, to: Point) -> Piece {
+    match piece.circle() {
+        Some((center, _)) => {
+            let turn = piece.sweep().signum();
+            let sweep = (((to - center).angle() - (from - center).angle()) * turn)
+                .rem_euclid(2.0 * PI)
+                * turn;
+            Piece::new(from, to, (sweep / 4.0).tan())
+        }
+        None => Piece::new(from, to, 0.0),
+    }
+}
+
+/// The outline running `shift` to the left of `polyline`, each segment moved
+/// over and stretched or cut back to meet its neighbours. Wherever it
+/// crosses itself, the loops coming closer to the polyline than `shift` are
+/// cut away, which can leave it in more than one piece.
+fn offset_polyline(polyline: &Polyline, shift: f32, tolerance: f32) -> Vec<Polyline> {
+    let pieces: Vec<Piece> = polyline
+        .spans()
+        .into_iter()
+        .filter_map(|(from, to)| offset_piece(Piece::new(from.position, to, from.bulge), shift))
+        .collect();
+    let count = pieces.len();
+    if count == 0 {
+        return vec![];
+    }
+    let joins = if polyline.closed { count } else { count - 1 };
+    let mut starts: Vec<Point> = pieces.iter().map(|piece| piece.from).collect();
+    let mut ends: Vec<Point> = pieces.iter().map(|piece| piece.to).collect();
+    let mut bridges: Vec<Option<Piece>> = vec![None; count];
+    for i in 0..joins {
+        let next = (i + 1) % count;
+        let (a, b) = (pieces[i], pieces[next]);
+        if a.to.distance(b.from) <= tolerance {
+            continue;
+        }
+        let gap = (a.to + b.from) * 0.5;
+        let meet = match (a.primitive(), b.primitive()) {
+            (Some(first), Some(second)) => first.intersect(&second).into_iter().min_by(|p, q| {
+                p.distance(gap)
+                    .partial_cmp(&q.distance(gap))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }),
+            _ => None,
+        };
+        match meet {
+            Some(meet) => {
+                ends[i] = meet;
+                starts[next] = meet;
+            }
+            // nowhere to meet, so they're joined straight across
+            None => bridges[i] = Some(Piece::new(a.to, b.from, 0.0)),
+        }
+    }
+    let mut joined = vec![];
+    for (i, piece) in pieces.into_iter().enumerate() {
+        joined.push(reshaped(piece, starts[i], ends[i]));
+        joined.extend(bridges[i]);
+    }
+
+    let original = Drawable::Polyline(polyline.clone());
+    let winding = area(&corners_of(&original, tolerance));
+    let kept = split_pieces(&joined, tolerance)
+        .into_iter()
+        .filter(|piece| {
+            original.distance_to(piece.point_at(0.5), tolerance) >= shift.abs() - 2.0 * tolerance
+        });
+    let mut chains: Vec<Vec<Piece>> = vec![];
+    let mut loops: Vec<Vec<Piece>> = vec![];
+    for piece in kept {
+        let joins = chains
+            .last()
+            .map(|chain| chain[chain.len() - 1].to.distance(piece.from) <= tolerance)
+            .unwrap_or(false);
+        // where two segments' offsets overlap, the same piece comes up twice
+        let repeated = chains.iter().flatten().any(|other| {
+            let ends = [other.from, other.to];
+            ends.iter().any(|p| p.distance(piece.from) <= tolerance)
+                && ends.iter().any(|p| p.distance(piece.to) <= tolerance)
+                && other.point_at(0.5).distance(piece.point_at(0.5)) <= tolerance
+        });
+        let chain = match chains.last_mut() {
+            Some(chain) if joins => chain,
+            _ if repeated => continue,
+            _ => {
+                chains.push(vec![]);
+                chains.last_mut().unwrap()
+            }
+        };
+        chain.push(piece);
+        // coming back to somewhere partway along cuts off a loop, which is
+        // only part of the outline if it goes round the same way as a closed
+        // polyline does
+        let end = piece.to;
+        if let Some(start) = chain
+            .iter()
+            .skip(1)
+            .position(|p| p.from.distance(end) <= tolerance)
+        {
+            let cut = chain.split_off(start + 1);
+            let around: Vec<Point> = cut
+                .iter()
+                .flat_map(|piece| piece.points(tolerance))
+                .collect();
+            if polyline.closed && area(&around) * winding > 0.0 {
+                loops.push(cut);
+            }
+        }
+    }
+    // going round a closed outline, the last piece carries on into the first
+    if polyline.closed && chains.len() > 1 {
+        let (first, last) = (chains[0][0], *chains[chains.len() - 1].last().unwrap());
+        if last.to.distance(first.from) <= tolerance {
+            let mut last = chains.pop().unwrap();
+            last.append(&mut chains[0]);
+            chains[0] = last;
+        }
+    }
+    chains.extend(loops);
+    chains
+        .into_iter()
+        .map(|chain| {
+            let (start, end) = (chain[0].from, chain[chain.len() - 1].to);
+            let closed = polyline.closed && start.distance(end) <= tolerance;
+            let mut vertices: Vec<Vertex> = chain
+                .iter()
+                .map(|piece| Vertex {
+                    bulge: piece.bulge,
+                    ..Vertex::new(piece.from)
+                })
+                .collect();
+            if !closed {
+                vertices.push(Vertex::new(end));
+            }
+            Polyline::new(vertices, closed, polyline.props)
+        })
+        .collect()
+}
+
+/// the corners of `drawable` followed within `tolerance`
+fn corners_of(drawable: &Drawable, tolerance: f32) -> Vec<Point> {
+    drawable
+        .segments(tolerance)
+        .into_iter()
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/// the area inside a loop through `points`, negative going clockwise
+fn area(points: &[Point]) -> f32 {
+    let count = points.len();
+    (0..count)
+        .map(|i| points[i].cross(points[(i + 1) % count]))
+        .sum::<f32>()
+        / 2.0
+}
+
+/// a curve followed through points `shift` to the left of where `drawable`
+/// is drawn, for shapes whose offsets aren't the same kind of shape
+fn offset_curve(drawable: &Drawable, shift: f32, tolerance: f32) -> Option<Spline> {
+    let segments = drawable.segments(tolerance);
+    let mut points: Vec<Point> = segments.iter().map(|(a, _)| *a).collect();
+    points.push(segments.last()?.1);
+    let count = points.len();
+    let closed = count > 2 && points[0].distance(points[count - 1]) <= tolerance;
+    let headings: Vec<Point> = (0..count)
+        .map(|i| {
+            let (before, after) = if closed && (i == 0 || i + 1 == count) {
+                // the ends meet, so they head the same way
+                (points[count - 2], points[1])
+            } else {
+                (points[i.saturating_sub(1)], points[(i + 1).min(count - 1)])
+            };
+            after - before
+        })
+        .collect();
+    let step = count.div_ceil(MAX_FIT_POINTS);
+    let mut fit = vec![];
+    for i in (0..count).step_by(step).chain(std::iter::once(count - 1)) {
+        fit.push(points[i] + headings[i].normalize()?.perp() * shift);
+    }
+    let props = drawable.properties()?;
+    Spline::through_fit(&fit, Some(headings[0]), Some(headings[count - 1]), *props)
+}
+
+/// Copies of `drawable` running `distance` away from it on the side `toward`
+/// is on. Lines, arcs and circles stay the same kind of shape, polylines
+/// keep their arcs, and ellipses and splines are followed by a spline.
+pub fn offset(
+    drawable: &Drawable,
+    distance: f32,
+    toward: Point,
+    tolerance: f32,
+) -> Option<Vec<Drawable>> {
+    if distance <= f32::EPSILON {
+        return None;
+    }
+    let shift = side_of(drawable, toward, tolerance)? * distance;
+    let offsets = match drawable {
+        Drawable::Line(line) => {
+            let left = (line.end - line.start).normalize()?.perp() * shift;
+            vec![Drawable::Line(Line::new(
+                line.start + left,
+                line.end + left,
+                line.props,
+            ))]
+        }
+        Drawable::Arc(arc) => {
+            // drawn counter-clockwise, so the left is the inside
+            let radius = arc.radius - shift;
+            if radius <= f32::EPSILON {
+                return None;
+            }
+            vec![Drawable::Arc(Arc { radius, ..*arc })]
+        }
+        Drawable::Circle(circle) => vec![Drawable::Circle(Circle::new(
+            circle.center,
+            circle.radius - shift,
+            circle.props,
+        )?)],
+        Drawable::Polyline(polyline) => offset_polyline(polyline, shift, tolerance)
+            .into_iter()
+            .map(Drawable::Polyline)
+            .collect(),
+        Drawable::Ellipse(_) | Drawable::Spline(_) => {
+            vec![Drawable::Spline(offset_curve(drawable, shift, tolerance)?)]
+        }
+        _ => return None,
+    };
+    if offsets.is_empty() {
+        None
+    } else {
+        Some(offsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, polyline, props};
+    use super::offset;
+    use crate::components::{Arc, Circle, Drawable, Line, Point};
+
+    const TOLERANCE: f32 = 1e-3;
+
+    fn line(ax: f32, ay: f32, bx: f32, by: f32) -> Drawable {
+        Drawable::Line(Line::new(Point::new(ax, ay), Point::new(bx, by), props()))
+    }
+
+    #[test]
+    fn a_line_moves_toward_the_side_picked() {
+        let drawable = line(0.0, 0.0, 4.0, 0.0);
+        let above = offset(&drawable, 1.0, Point::new(2.0, 5.0), TOLERANCE);
+        assert_eq!(above, Some(vec![line(0.0, 1.0, 4.0, 1.0)]));
+        let below = offset(&drawable, 1.0, Point::new(2.0, -5.0), TOLERANCE);
+        assert_eq!(below, Some(vec![line(0.0, -1.0, 4.0, -1.0)]));
+    }
+
+    #[test]
+    fn no_side_or_no_distance_gives_nothing() {
+        let drawable = line(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(
+            offset(&drawable, 1.0, Point::new(2.0, 0.0), TOLERANCE),
+            None
+        );
+        assert_eq!(
+            offset(&drawable, 0.0, Point::new(2.0, 1.0), TOLERANCE),
+            None
+        );
+        let dot = line(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(offset(&dot, 1.0, Point::new(2.0, 2.0), TOLERANCE), None);
+    }
+
+    #[test]
+    fn circles_and_arcs_grow_or_shrink() {
+        let circle = Drawable::Circle(Circle::new(Point::new(0.0, 0.0), 2.0, props()).unwrap());
+        match offset(&circle, 1.0, Point::new(5.0, 0.0), TOLERANCE).as_deref() {
+            Some([Drawable::Circle(grown)]) => assert!((grown.radius - 3.0).abs() < 1e-5),
+            other => panic!("expected a circle, got {:?}", other),
+        }
+        let arc = Drawable::Arc(Arc::new(Point::new(0.0, 0.0), 2.0, 0.0, 1.0, props()));
+        match offset(&arc, 1.0, Point::new(0.5, 0.1), TOLERANCE).as_deref() {
+            Some([Drawable::Arc(shrunk)]) => assert!((shrunk.radius - 1.0).abs() < 1e-5),
+            other => panic!("expected an arc, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn shrinking_past_the_center_gives_nothing() {
+        let circle = Drawable::Circle(Circle::new(Point::new(0.0, 0.0), 2.0, props()).unwrap());
+        assert_eq!(offset(&circle, 2.0, Point::new(0.5, 0.0), TOLERANCE), None);
+        let arc = Drawable::Arc(Arc::new(Point::new(0.0, 0.0), 2.0, 0.0, 1.0, props()));
+        assert_eq!(offset(&arc, 3.0, Point::new(0.5, 0.1), TOLERANCE), None);
+    }
+
+    #[test]
+    fn a_square_grows_and_shrinks_with_sharp_corners() {
+        let square = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], true);
+        let square = Drawable::Polyline(square);
+        for (toward, length, corner) in [
+            (Point::new(8.0, 2.0), 24.0, Point::new(-1.0, -1.0)),
+            (Point::new(2.0, 2.0), 8.0, Point::new(1.0, 1.0)),
+        ] {
+            match offset(&square, 1.0, toward, TOLERANCE).as_deref() {
+                Some([Drawable::Polyline(moved)]) => {
+                    assert!(moved.closed);
+                    assert!((moved.length() - length).abs() < 1e-3);
+                    assert!(moved.vertices.iter().any(|v| near(v.position, corner)));
+                }
+                other => panic!("expected one polyline, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn a_square_shrunk_away_to_nothing() {
+        let square = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], true);
+        let square = Drawable::Polyline(square);
+        assert_eq!(offset(&square, 3.0, Point::new(2.0, 2.0), TOLERANCE), None);
+    }
+
+    #[test]
+    fn an_open_polyline_keeps_its_ends_square() {
+        let open = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)], false);
+        match offset(
+            &Drawable::Polyline(open),
+            1.0,
+            Point::new(2.0, 1.0),
+            TOLERANCE,
+        )
+        .as_deref()
+        {
+            Some([Drawable::Polyline(moved)]) => {
+                let ends = (moved.vertices[0].position, moved.vertices[2].position);
+                assert!(near(ends.0, Point::new(0.0, 1.0)) && near(ends.1, Point::new(3.0, 4.0)));
+                assert!(near(moved.vertices[1].position, Point::new(3.0, 1.0)));
+            }
+            other => panic!("expected one polyline, got {:?}", other),
+        }
+    }
+}
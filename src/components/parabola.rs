@@ -0,0 +1,179 @@
+use super::{connect, Affine, Point, Properties, MAX_DIVISIONS};
+
+/// A bounded piece of a parabola, kept exactly as a quadratic bezier running
+/// from `start` to `end` pulled towards `control`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parabola {
+    pub start: Point,
+    pub control: Point,
+    pub end: Point,
+    pub props: Properties,
+}
+
+impl Parabola {
+    /// the parabola with its vertex at `vertex` opening towards `focus`, running
+    /// out either side as far across its axis as `extent`
+    pub fn vertex_focus(
+        vertex: Point,
+        focus: Point,
+        extent: Point,
+        props: Properties,
+    ) -> Option<Self> {
+        let axis = focus - vertex;
+        let focal_length = axis.length();
+        let axis = axis.normalize()?;
+        let across = axis.perp();
+        let half_width = (extent - vertex).dot(across).abs();
+        if half_width <= f32::EPSILON {
+            return None;
+        }
+        // y = x^2 / 4f along the axis, whose end tangents cross as far behind
+        // the vertex as the ends are in front of it
+        let depth = half_width * half_width / (4.0 * focal_length);
+        let local = |x: f32, y: f32| vertex + across * x + axis * y;
+        Some(Parabola {
+            start: local(-half_width, depth),
+            control: local(0.0, -depth),
+            end: local(half_width, depth),
+            props,
+        })
+    }
+    /// the parabola from `start` to `end` passing through `middle` halfway along
+    pub fn through_points(
+        start: Point,
+        middle: Point,
+        end: Point,
+        props: Properties,
+    ) -> Option<Self> {
+        if (middle - start).cross(end - start).abs() <= f32::EPSILON {
+            return None;
+        }
+        Some(Parabola {
+            start,
+            control: middle * 2.0 - (start + end) * 0.5,
+            end,
+            props,
+        })
+    }
+    pub fn point_at(&self, t: f32) -> Point {
+        let it = 1.0 - t;
+        self.start * (it * it) + self.control * (2.0 * it * t) + self.end * (t * t)
+    }
+    /// the same parabola moved by `transform`, which maps beziers exactly
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        Parabola {
+            start: transform.apply(self.start),
+            control: transform.apply(self.control),
+            end: transform.apply(self.end),
+            props: self.props,
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        // a quadratic bezier strays from its chords by at most a quarter of
+        // how far its control point bends it, over the pieces squared
+        let bend = (self.start - self.control * 2.0 + self.end).length();
+        let divisions = ((bend / (4.0 * tolerance)).sqrt().ceil() as usize).clamp(1, MAX_DIVISIONS);
+        let points: Vec<Point> = (0..=divisions)
+            .map(|i| self.point_at(i as f32 / divisions as f32))
+            .collect();
+        connect(&points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::Parabola;
+    use crate::components::{Affine, Point};
+
+    #[test]
+    fn through_points_passes_through_the_middle_halfway() {
+        let (start, middle, end) = (
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 3.0),
+            Point::new(5.0, 1.0),
+        );
+        let parabola = Parabola::through_points(start, middle, end, props()).unwrap();
+        assert!(near(parabola.point_at(0.0), start));
+        assert!(near(parabola.point_at(0.5), middle));
+        assert!(near(parabola.point_at(1.0), end));
+    }
+
+    #[test]
+    fn points_in_a_row_make_no_parabola() {
+        let (start, middle, end) = (
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(3.0, 3.0),
+        );
+        assert_eq!(Parabola::through_points(start, middle, end, props()), None);
+    }
+
+    #[test]
+    fn every_point_is_as_far_from_the_focus_as_the_directrix() {
+        let (vertex, focus) = (Point::new(1.0, 1.0), Point::new(1.0, 2.0));
+        let parabola =
+            Parabola::vertex_focus(vertex, focus, Point::new(5.0, 0.0), props()).unwrap();
+        assert!(near(parabola.point_at(0.5), vertex));
+        // the directrix runs across at y = 0, as far behind the vertex as the focus is in front
+        for i in 0..=10 {
+            let p = parabola.point_at(i as f32 / 10.0);
+            assert!((p.distance(focus) - p.y).abs() < 1e-3);
+        }
+        // running out as far either side as the extent is from the axis
+        let (low, high) = (
+            parabola.start.x.min(parabola.end.x),
+            parabola.start.x.max(parabola.end.x),
+        );
+        assert!((low + 3.0).abs() < 1e-4 && (high - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn no_focal_length_or_no_width_is_no_parabola() {
+        let vertex = Point::new(0.0, 0.0);
+        let extent = Point::new(2.0, 0.0);
+        assert_eq!(
+            Parabola::vertex_focus(vertex, vertex, extent, props()),
+            None
+        );
+        let on_axis = Point::new(0.0, 5.0);
+        assert_eq!(
+            Parabola::vertex_focus(vertex, Point::new(0.0, 1.0), on_axis, props()),
+            None
+        );
+    }
+
+    #[test]
+    fn segments_stay_on_the_curve_within_tolerance() {
+        let (vertex, focus) = (Point::new(0.0, 0.0), Point::new(0.0, 0.25));
+        let parabola =
+            Parabola::vertex_focus(vertex, focus, Point::new(3.0, 0.0), props()).unwrap();
+        let segments = parabola.segments(1e-2);
+        assert!(segments.len() > 1);
+        for (a, b) in segments {
+            // y = x^2 here, and the curve bows furthest from a chord at its middle x
+            let middle = (a + b) * 0.5;
+            assert!((middle.y - middle.x * middle.x).abs() <= 1e-2 + 1e-4);
+        }
+    }
+
+    #[test]
+    fn mirrored_it_is_still_the_mirrored_curve() {
+        let (start, middle, end) = (
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 3.0),
+            Point::new(5.0, 1.0),
+        );
+        let parabola = Parabola::through_points(start, middle, end, props()).unwrap();
+        let mirror = Affine::mirror(Point::new(0.0, 0.0), Point::new(1.0, 0.0)).unwrap();
+        let mirrored = parabola.transformed(&mirror);
+        for i in 0..=4 {
+            let t = i as f32 / 4.0;
+            assert!(near(
+                mirrored.point_at(t),
+                mirror.apply(parabola.point_at(t))
+            ));
+        }
+        assert!(near(mirrored.point_at(0.5), Point::new(2.0, -3.0)));
+    }
+}
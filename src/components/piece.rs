@@ -0,0 +1,231 @@
+use std::{cmp::Ordering, f32::consts::PI};
+
+use super::{polyline::bulge_sweep, Drawable, Point, Primitive};
+
+/// A straight or arc piece of the drawing between two points, bulged the
+/// way polyline spans are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Piece {
+    pub from: Point,
+    pub to: Point,
+    pub bulge: f32,
+}
+
+impl Piece {
+    pub fn new(from: Point, to: Point, bulge: f32) -> Self {
+        Piece { from, to, bulge }
+    }
+    pub fn reversed(self) -> Self {
+        Piece::new(self.to, self.from, -self.bulge)
+    }
+    pub fn sweep(&self) -> f32 {
+        bulge_sweep(self.bulge)
+    }
+    /// the center and radius of the circle an arc piece lies on
+    pub fn circle(&self) -> Option<(Point, f32)> {
+        let chord = self.to - self.from;
+        let half = chord.length() / 2.0;
+        if self.bulge.abs() <= f32::EPSILON || half <= f32::EPSILON {
+            return None;
+        }
+        let offset = half * (1.0 - self.bulge * self.bulge) / (2.0 * self.bulge);
+        let center = self.from + chord * 0.5 + chord.normalize()?.perp() * offset;
+        Some((center, half / (self.sweep() / 2.0).sin().abs()))
+    }
+    pub fn primitive(&self) -> Option<Primitive> {
+        match self.circle() {
+            Some((center, radius)) => Some(Primitive::Circle { center, radius }),
+            None => Some(Primitive::Line {
+                point: self.from,
+                direction: (self.to - self.from).normalize()?,
+            }),
+        }
+    }
+    /// the point `t` of the way along, from 0 at the start to 1 at the end
+    pub fn point_at(&self, t: f32) -> Point {
+        match self.circle() {
+            Some((center, radius)) => {
+                let start = (self.from - center).angle();
+                center + Point::from_angle(start + self.sweep() * t) * radius
+            }
+            None => self.from + (self.to - self.from) * t,
+        }
+    }
+    /// how far along this piece `p` is, if it lies on it within `tolerance`
+    pub fn param_of(&self, p: Point, tolerance: f32) -> Option<f32> {
+        let t = match self.circle() {
+            Some((center, radius)) => {
+                if (p.distance(center) - radius).abs() > tolerance {
+                    return None;
+                }
+                let sweep = self.sweep();
+                let turned = ((p - center).angle() - (self.from - center).angle()) * sweep.signum();
+                let turned = turned.rem_euclid(2.0 * PI);
+                // just short of the start comes round to nearly a whole turn
+                let slack = tolerance / radius;
+                if turned > 2.0 * PI - slack {
+                    0.0
+                } else {
+                    turned / sweep.abs()
+                }
+            }
+            None => {
+                let chord = self.to - self.from;
+                let t = (p - self.from).dot(chord) / chord.dot(chord);
+                if p.distance(self.from + chord * t) > tolerance {
+                    return None;
+                }
+                t
+            }
+        };
+        let slack = tolerance / self.length().max(f32::EPSILON);
+        if t >= -slack && t <= 1.0 + slack {
+            Some(t.clamp(0.0, 1.0))
+        } else {
+            None
+        }
+    }
+    pub fn length(&self) -> f32 {
+        match self.circle() {
+            Some((_, radius)) => radius * self.sweep().abs(),
+            None => self.from.distance(self.to),
+        }
+    }
+    /// the piece between `a` and `b` of the way along
+    pub fn between(&self, a: f32, b: f32) -> Piece {
+        let bulge = (self.sweep() * (b - a) / 4.0).tan();
+        Piece::new(self.point_at(a), self.point_at(b), bulge)
+    }
+    /// which way this piece sets off, and how sharply it turns left doing so
+    pub fn departure(&self) -> (f32, f32) {
+        let heading = (self.to - self.from).rotate(-self.sweep() / 2.0).angle();
+        (heading, self.sweep() / self.length().max(f32::EPSILON))
+    }
+    /// the corners of this piece, followed within `tolerance`, without its end
+    pub fn points(&self, tolerance: f32) -> Vec<Point> {
+        match self.circle() {
+            Some((_, radius)) => {
+                let divisions = super::arc_divisions(radius, self.sweep(), tolerance);
+                (0..divisions)
+                    .map(|i| self.point_at(i as f32 / divisions as f32))
+                    .collect()
+            }
+            None => vec![self.from],
+        }
+    }
+    /// where this piece and `other` cross or touch, as how far along each
+    pub fn crossings(&self, other: &Piece, tolerance: f32) -> Vec<(f32, f32)> {
+        let ((al, ah), (bl, bh)) = (self.bounds(), other.bounds());
+        if al.x > bh.x + tolerance
+            || bl.x > ah.x + tolerance
+            || al.y > bh.y + tolerance
+            || bl.y > ah.y + tolerance
+        {
+            return vec![];
+        }
+        // ends touching the other piece catch overlaps and tees
+        let mut candidates = vec![self.from, self.to, other.from, other.to];
+        if let (Some(a), Some(b)) = (self.primitive(), other.primitive()) {
+            candidates.extend(a.intersect(&b));
+        }
+        candidates
+            .into_iter()
+            .filter_map(|p| Some((self.param_of(p, tolerance)?, other.param_of(p, tolerance)?)))
+            .collect()
+    }
+    fn bounds(&self) -> (Point, Point) {
+        let (low, high) = match self.circle() {
+            Some((center, radius)) => (
+                center - Point::new(radius, radius),
+                center + Point::new(radius, radius),
+            ),
+            None => (self.from, self.to),
+        };
+        (
+            Point::new(low.x.min(high.x), low.y.min(high.y)),
+            Point::new(low.x.max(high.x), low.y.max(high.y)),
+        )
+    }
+}
+
+/// the pieces a drawable is made of, curves that aren't lines
+/// or arcs followed within `tolerance`
+pub fn pieces_of(drawable: &Drawable, tolerance: f32) -> Vec<Piece> {
+    let straight = |segments: Vec<(Point, Point)>| -> Vec<Piece> {
+        segments
+            .into_iter()
+            .map(|(from, to)| Piece::new(from, to, 0.0))
+            .collect()
+    };
+    match drawable {
+        Drawable::Line(line) => vec![Piece::new(line.start, line.end, 0.0)],
+        Drawable::Arc(arc) => {
+            // halved so whole turns still have two different ends
+            let bulge = (arc.sweep() / 8.0).tan();
+            let middle = arc.point_at(arc.start_angle + arc.sweep() / 2.0);
+            vec![
+                Piece::new(arc.start_point(), middle, bulge),
+                Piece::new(middle, arc.end_point(), bulge),
+            ]
+        }
+        Drawable::Circle(circle) => {
+            let across = Point::new(circle.radius, 0.0);
+            vec![
+                Piece::new(circle.center + across, circle.center - across, 1.0),
+                Piece::new(circle.center - across, circle.center + across, 1.0),
+            ]
+        }
+        Drawable::Polyline(polyline) => polyline
+            .spans()
+            .into_iter()
+            .map(|(from, to)| Piece::new(from.position, to, from.bulge))
+            .collect(),
+        Drawable::Ellipse(_)
+        | Drawable::Spline(_)
+        | Drawable::Catenary(_)
+        | Drawable::Parabola(_) => straight(drawable.segments(tolerance)),
+        Drawable::Insert(insert) => insert
+            .copies()
+            .iter()
+            .flat_map(|copy| pieces_of(copy, tolerance))
+            .collect(),
+        Drawable::NamedGroup(group) => group
+            .members()
+            .iter()
+            .flat_map(|member| pieces_of(member, tolerance))
+            .collect(),
+        Drawable::Text(_)
+        | Drawable::MText(_)
+        | Drawable::Dimension(_)
+        | Drawable::Leader(_)
+        | Drawable::Hatch(_)
+        | Drawable::AttDef(_) => vec![],
+    }
+}
+
+/// The pieces cut wherever they cross or touch one another, so that they
+/// only ever meet at their ends.
+pub fn split_pieces(pieces: &[Piece], tolerance: f32) -> Vec<Piece> {
+    let mut cuts: Vec<Vec<f32>> = vec![vec![0.0, 1.0]; pieces.len()];
+    for i in 0..pieces.len() {
+        for j in i + 1..pieces.len() {
+            for (ta, tb) in pieces[i].crossings(&pieces[j], tolerance) {
+                cuts[i].push(ta);
+                cuts[j].push(tb);
+            }
+        }
+    }
+    let mut split = vec![];
+    for (piece, mut cuts) in pieces.iter().zip(cuts) {
+        cuts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let step = tolerance / piece.length().max(f32::EPSILON);
+        let mut from = 0.0;
+        for &t in cuts.iter().skip(1) {
+            if t - from > step {
+                split.push(piece.between(from, t));
+                from = t;
+            }
+        }
+    }
+    split
+}
@@ -0,0 +1,670 @@
+use std::f32::consts::PI;
+
+use super::{
+    arc_divisions, circumcenter, connect, intersect::line_line, Affine, Chamfer, Point, Properties,
+};
+
+/// One corner of a polyline, along with the shape of the segment leaving it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+    pub position: Point,
+    /// tangent of a quarter of the angle swept by the segment leaving this
+    /// vertex, zero for straight, positive turning counter-clockwise
+    pub bulge: f32,
+    pub start_width: f32,
+    pub end_width: f32,
+}
+
+impl Vertex {
+    pub fn new(position: Point) -> Self {
+        Vertex {
+            position,
+            bulge: 0.0,
+            start_width: 0.0,
+            end_width: 0.0,
+        }
+    }
+}
+
+/// A chain of straight and arc segments drawn as a single object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    pub vertices: Vec<Vertex>,
+    /// whether the last vertex joins back up to the first
+    pub closed: bool,
+    pub props: Properties,
+}
+
+impl Polyline {
+    pub fn new(vertices: Vec<Vertex>, closed: bool, props: Properties) -> Self {
+        Polyline {
+            vertices,
+            closed,
+            props,
+        }
+    }
+    /// each segment as the vertex it leaves and the position it arrives at
+    pub fn spans(&self) -> Vec<(Vertex, Point)> {
+        let count = self.vertices.len();
+        let spans = if self.closed && count >= 2 {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        (0..spans)
+            .map(|i| (self.vertices[i], self.vertices[(i + 1) % count].position))
+            .collect()
+    }
+    pub fn length(&self) -> f32 {
+        self.spans()
+            .into_iter()
+            .map(|(from, to)| {
+                let chord = from.position.distance(to);
+                let sweep = bulge_sweep(from.bulge).abs();
+                if sweep <= f32::EPSILON {
+                    chord
+                } else {
+                    chord / (2.0 * (sweep / 2.0).sin()) * sweep
+                }
+            })
+            .sum()
+    }
+    pub fn is_wide(&self) -> bool {
+        self.vertices
+            .iter()
+            .any(|v| v.start_width > 0.0 || v.end_width > 0.0)
+    }
+    /// which way the polyline is heading as it reaches its last vertex
+    pub fn end_heading(&self) -> Option<Point> {
+        let (from, to) = *self.spans().last()?;
+        Some(end_heading(from.position, to, from.bulge))
+    }
+    /// where along its length a point lies closest, as the index of the
+    /// segment and the vertex that segment leaves from
+    pub fn nearest_span(&self, p: Point, tolerance: f32) -> Option<usize> {
+        self.spans()
+            .iter()
+            .enumerate()
+            .map(|(i, (from, to))| {
+                let distance = connect(&span_points(from.position, *to, from.bulge, tolerance))
+                    .into_iter()
+                    .map(|(a, b)| p.distance_to_segment(a, b))
+                    .fold(f32::INFINITY, f32::min);
+                (i, distance)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+    pub fn nearest_vertex(&self, p: Point) -> Option<usize> {
+        self.vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, v.position.distance(p)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+    /// put a new vertex at `p` partway along the segment closest to it, an
+    /// arc segment becoming two arcs passing through `p`, returning where it went
+    pub fn insert_vertex(&mut self, p: Point, tolerance: f32) -> usize {
+        let index = match self.nearest_span(p, tolerance) {
+            Some(index) => index,
+            None => {
+                self.vertices.push(Vertex::new(p));
+                return self.vertices.len() - 1;
+            }
+        };
+        let from = self.vertices[index];
+        let to = self.vertices[(index + 1) % self.vertices.len()].position;
+        let (first, second) = if from.bulge == 0.0 {
+            (0.0, 0.0)
+        } else {
+            match circumcenter(from.position, p, to) {
+                Some(center) => (
+                    bulge_around(center, from.position, p, from.bulge > 0.0),
+                    bulge_around(center, p, to, from.bulge > 0.0),
+                ),
+                None => (0.0, 0.0),
+            }
+        };
+        self.vertices[index].bulge = first;
+        let vertex = Vertex {
+            position: p,
+            bulge: second,
+            ..from
+        };
+        self.vertices.insert(index + 1, vertex);
+        index + 1
+    }
+    /// the point along the polyline nearest `p`, within `tolerance` on arcs
+    pub fn closest_point(&self, p: Point, tolerance: f32) -> Option<Point> {
+        self.spans()
+            .into_iter()
+            .flat_map(|(from, to)| connect(&span_points(from.position, to, from.bulge, tolerance)))
+            .map(|(a, b)| {
+                let ab = b - a;
+                let t = if ab.dot(ab) == 0.0 {
+                    0.0
+                } else {
+                    ((p - a).dot(ab) / ab.dot(ab)).clamp(0.0, 1.0)
+                };
+                a + ab * t
+            })
+            .min_by(|a, b| {
+                a.distance(p)
+                    .partial_cmp(&b.distance(p))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+    /// the pieces left after cutting the polyline where it passes nearest
+    /// `p`, a closed polyline opening up into a single piece
+    pub fn broken_at(&self, p: Point, tolerance: f32) -> Vec<Polyline> {
+        let p = match self.closest_point(p, tolerance) {
+            Some(p) => p,
+            None => return vec![self.clone()],
+        };
+        let mut cut = self.clone();
+        let index = cut.insert_vertex(p, tolerance);
+        if cut.closed {
+            // start and finish at the cut, going all the way around
+            cut.vertices.rotate_left(index);
+            let end = Vertex::new(cut.vertices[0].position);
+            cut.vertices.push(end);
+            cut.closed = false;
+            return vec![cut];
+        }
+        let mut last = cut.vertices[index];
+        last.bulge = 0.0;
+        let mut first_half = cut.vertices[..index].to_vec();
+        first_half.push(last);
+        let second_half = cut.vertices[index..].to_vec();
+        vec![
+            Polyline::new(first_half, false, self.props),
+            Polyline::new(second_half, false, self.props),
+        ]
+        .into_iter()
+        .filter(|piece| piece.length() > f32::EPSILON)
+        .collect()
+    }
+    /// this polyline with each corner between two straight segments rounded
+    /// off by an arc of `radius`, and how many corners were left because a
+    /// segment beside them was too short. A radius of zero takes out arcs
+    /// between straight segments instead, leaving sharp corners.
+    pub fn filleted(&self, radius: f32) -> (Polyline, usize) {
+        if radius <= 0.0 {
+            return self.sharpened();
+        }
+        let (filleted, _, too_short) = self.cut_corners(None, |turn| {
+            let setback = radius * (turn.abs() / 2.0).tan();
+            Some((setback, setback, (turn / 4.0).tan()))
+        });
+        (filleted, too_short)
+    }
+    /// this polyline with each arc between two straight segments taken out
+    /// and the segments carried on or cut back to meet, and how many arcs
+    /// were left because the segments beside them never meet
+    fn sharpened(&self) -> (Polyline, usize) {
+        let count = self.vertices.len();
+        let spans = self.spans();
+        let n = spans.len();
+        if n < 3 {
+            return (self.clone(), 0);
+        }
+        let mut vertices = self.vertices.clone();
+        let mut dropped = vec![false; count];
+        let mut left = 0;
+        for (i, (from, _)) in spans.iter().enumerate() {
+            if from.bulge == 0.0 || !(self.closed || (i > 0 && i + 1 < n)) {
+                continue;
+            }
+            let (before, before_to) = spans[(i + n - 1) % n];
+            let (after, after_to) = spans[(i + 1) % n];
+            if before.bulge != 0.0 || after.bulge != 0.0 {
+                continue;
+            }
+            let headings = (
+                (before_to - before.position).normalize(),
+                (after_to - after.position).normalize(),
+            );
+            let corner = match headings {
+                (Some(into), Some(out)) => line_line(before.position, into, after.position, out),
+                _ => None,
+            };
+            match corner {
+                // the arc's end moves to the corner and its start goes
+                Some(corner) => {
+                    vertices[(i + 1) % count].position = corner;
+                    dropped[i] = true;
+                }
+                None => left += 1,
+            }
+        }
+        let vertices = vertices
+            .into_iter()
+            .zip(dropped)
+            .filter(|(_, dropped)| !dropped)
+            .map(|(vertex, _)| vertex)
+            .collect();
+        (Polyline::new(vertices, self.closed, self.props), left)
+    }
+    /// this polyline with each corner between two straight segments cut
+    /// off by `cut`, the first distance taken along the segment coming
+    /// into the corner, and how many corners couldn't be cut. Without `trim`
+    /// the polyline is left whole and the cuts are given as lines across
+    /// the corners to draw beside it.
+    pub fn chamfered(&self, cut: Chamfer, trim: bool) -> (Polyline, Vec<(Point, Point)>, usize) {
+        let (chamfered, cuts, uncut) = self.cut_corners(None, |turn| {
+            let (into, out) = cut.setbacks(PI - turn.abs())?;
+            Some((into, out, 0.0))
+        });
+        if trim {
+            (chamfered, vec![], uncut)
+        } else {
+            (self.clone(), cuts, uncut)
+        }
+    }
+    /// this polyline with the corner between spans `first` and `second` cut
+    /// off, the first distance taken along `first`, if they meet there.
+    /// Without `trim` the polyline is left whole and the cut is given as a
+    /// line across the corner instead.
+    pub fn chamfered_between(
+        &self,
+        first: usize,
+        second: usize,
+        cut: Chamfer,
+        trim: bool,
+    ) -> Option<(Polyline, Option<(Point, Point)>)> {
+        let spans = self.spans().len();
+        let next = |span: usize| {
+            if self.closed {
+                (span + 1) % spans
+            } else {
+                span + 1
+            }
+        };
+        let (corner, reversed) = if second == next(first) {
+            (second, false)
+        } else if first == next(second) {
+            (first, true)
+        } else {
+            return None;
+        };
+        let (chamfered, cuts, _) = self.cut_corners(Some(corner), |turn| {
+            let (along_first, along_second) = cut.setbacks(PI - turn.abs())?;
+            if reversed {
+                Some((along_second, along_first, 0.0))
+            } else {
+                Some((along_first, along_second, 0.0))
+            }
+        });
+        match cuts.first() {
+            Some(_) if trim => Some((chamfered, None)),
+            Some(cut) => Some((self.clone(), Some(*cut))),
+            None => None,
+        }
+    }
+    /// this polyline with corners between two straight segments replaced by
+    /// a span from `before` back along the segment coming in to `after`
+    /// along the one going out, as `cut` gives them for each turn along with
+    /// the new span's bulge. Only the corner at `only` is cut if it's given;
+    /// otherwise every corner is, each segment giving no more than half its
+    /// length to a corner. Gives where each new span runs and how many
+    /// corners couldn't be cut.
+    fn cut_corners(
+        &self,
+        only: Option<usize>,
+        cut: impl Fn(f32) -> Option<(f32, f32, f32)>,
+    ) -> (Polyline, Vec<(Point, Point)>, usize) {
+        let count = self.vertices.len();
+        if count < 3 {
+            return (self.clone(), vec![], 0);
+        }
+        let share = if only.is_some() { 1.0 } else { 0.5 };
+        let mut vertices = vec![];
+        let mut cuts = vec![];
+        let mut uncut = 0;
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            let before = self.vertices[(i + count - 1) % count];
+            let after = self.vertices[(i + 1) % count].position;
+            let corner = (self.closed || (i > 0 && i + 1 < count))
+                && only.unwrap_or(i) == i
+                && before.bulge == 0.0
+                && vertex.bulge == 0.0;
+            let incoming = vertex.position - before.position;
+            let outgoing = after - vertex.position;
+            let headings = (incoming.normalize(), outgoing.normalize());
+            let (into, out) = match headings {
+                (Some(into), Some(out)) if corner => (into, out),
+                _ => {
+                    vertices.push(*vertex);
+                    continue;
+                }
+            };
+            let turn = into.cross(out).atan2(into.dot(out));
+            if turn.abs() <= f32::EPSILON {
+                vertices.push(*vertex);
+                continue;
+            }
+            let (before, after, bulge) = match cut(turn) {
+                Some(cut) => cut,
+                None => {
+                    uncut += 1;
+                    vertices.push(*vertex);
+                    continue;
+                }
+            };
+            if before <= f32::EPSILON && after <= f32::EPSILON {
+                vertices.push(*vertex);
+                continue;
+            }
+            if before > incoming.length() * share || after > outgoing.length() * share {
+                uncut += 1;
+                vertices.push(*vertex);
+                continue;
+            }
+            let (from, to) = (
+                vertex.position - into * before,
+                vertex.position + out * after,
+            );
+            vertices.push(Vertex {
+                position: from,
+                bulge,
+                ..*vertex
+            });
+            vertices.push(Vertex {
+                position: to,
+                ..*vertex
+            });
+            cuts.push((from, to));
+        }
+        (
+            Polyline::new(vertices, self.closed, self.props),
+            cuts,
+            uncut,
+        )
+    }
+    /// this polyline moved by `transform`, arc segments being broken into
+    /// straight pieces within `tolerance` if the transform doesn't keep their shape
+    pub fn transformed(&self, transform: &Affine, tolerance: f32) -> Self {
+        if let Some(scale) = transform.uniform_scale() {
+            let flip = transform.determinant().signum();
+            let vertices = self
+                .vertices
+                .iter()
+                .map(|v| Vertex {
+                    position: transform.apply(v.position),
+                    bulge: v.bulge * flip,
+                    start_width: v.start_width * scale,
+                    end_width: v.end_width * scale,
+                })
+                .collect();
+            return Polyline::new(vertices, self.closed, self.props);
+        }
+        let scale = transform.determinant().abs().sqrt();
+        let mut vertices = vec![];
+        for (from, to) in self.spans() {
+            let points = span_points(from.position, to, from.bulge, tolerance);
+            let pieces = (points.len() - 1) as f32;
+            let width = |i: usize| {
+                let t = i as f32 / pieces;
+                (from.start_width + (from.end_width - from.start_width) * t) * scale
+            };
+            for (i, p) in points.iter().take(points.len() - 1).enumerate() {
+                vertices.push(Vertex {
+                    position: transform.apply(*p),
+                    bulge: 0.0,
+                    start_width: width(i),
+                    end_width: width(i + 1),
+                });
+            }
+        }
+        // a closed polyline's closing span already came back to the start
+        if !self.closed || self.vertices.len() < 2 {
+            if let Some(last) = self.vertices.last() {
+                vertices.push(Vertex::new(transform.apply(last.position)));
+            }
+        }
+        Polyline::new(vertices, self.closed, self.props)
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        if !self.is_wide() {
+            return self
+                .spans()
+                .into_iter()
+                .flat_map(|(from, to)| {
+                    connect(&span_points(from.position, to, from.bulge, tolerance))
+                })
+                .collect();
+        }
+        // wide polylines are drawn as the outline of each segment
+        let mut segments = vec![];
+        for (from, to) in self.spans() {
+            let points = span_points(from.position, to, from.bulge, tolerance);
+            let last = points.len() - 1;
+            let edge = |side: f32| -> Vec<Point> {
+                points
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        let t = i as f32 / last as f32;
+                        let width = from.start_width + (from.end_width - from.start_width) * t;
+                        let before = points[i.saturating_sub(1)];
+                        let after = points[(i + 1).min(last)];
+                        let along = (after - before).normalize().unwrap_or(Point::new(0.0, 0.0));
+                        *p + along.perp() * (side * width / 2.0)
+                    })
+                    .collect()
+            };
+            let left = edge(1.0);
+            let right = edge(-1.0);
+            segments.push((left[0], right[0]));
+            segments.push((left[last], right[last]));
+            segments.extend(connect(&left));
+            segments.extend(connect(&right));
+        }
+        segments
+    }
+}
+
+/// the signed angle an arc segment sweeps through
+pub fn bulge_sweep(bulge: f32) -> f32 {
+    4.0 * bulge.atan()
+}
+
+/// bulge of the arc segment from `from` to `to` which leaves heading along `direction`
+pub fn bulge_from_heading(from: Point, to: Point, direction: Point) -> f32 {
+    let chord = to - from;
+    let half_sweep = direction.cross(chord).atan2(direction.dot(chord));
+    (half_sweep / 2.0).tan()
+}
+
+/// which way a segment is heading as it arrives at `to`
+pub fn end_heading(from: Point, to: Point, bulge: f32) -> Point {
+    (to - from).rotate(bulge_sweep(bulge) / 2.0)
+}
+
+/// bulge of the arc around `center` from `from` to `to`, turning
+/// counter-clockwise if `ccw`
+fn bulge_around(center: Point, from: Point, to: Point, ccw: bool) -> f32 {
+    let sweep =
+        ((to - center).angle() - (from - center).angle()).rem_euclid(2.0 * std::f32::consts::PI);
+    if ccw {
+        (sweep / 4.0).tan()
+    } else {
+        -((2.0 * std::f32::consts::PI - sweep) / 4.0).tan()
+    }
+}
+
+/// points along one segment, close enough together to stay within `tolerance` of an arc
+fn span_points(from: Point, to: Point, bulge: f32, tolerance: f32) -> Vec<Point> {
+    let chord = to - from;
+    let half = chord.length() / 2.0;
+    if bulge.abs() <= f32::EPSILON || half <= f32::EPSILON {
+        return vec![from, to];
+    }
+    let sweep = bulge_sweep(bulge);
+    let radius = half / (sweep / 2.0).sin().abs();
+    // distance from the middle of the chord to the center, to the left of the chord
+    let offset = half * (1.0 - bulge * bulge) / (2.0 * bulge);
+    let center = from + chord * 0.5 + chord.normalize().unwrap_or(chord).perp() * offset;
+    let start = (from - center).angle();
+    let divisions = arc_divisions(radius, sweep, tolerance);
+    let mut points: Vec<Point> = (0..divisions)
+        .map(|i| center + Point::from_angle(start + sweep * i as f32 / divisions as f32) * radius)
+        .collect();
+    points.push(to);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::super::tests::{near, polyline};
+    use super::{span_points, Affine, Point, Polyline};
+
+    #[test]
+    fn closing_adds_a_span_back_to_the_start() {
+        let open = polyline(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], false);
+        assert_eq!(open.spans().len(), 2);
+        let closed = Polyline {
+            closed: true,
+            ..open
+        };
+        let spans = closed.spans();
+        assert_eq!(spans.len(), 3);
+        assert!(near(spans[2].1, Point::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn too_few_vertices_have_no_spans() {
+        for closed in [false, true] {
+            assert!(polyline(&[], closed).spans().is_empty());
+            assert!(polyline(&[(1.0, 1.0)], closed).spans().is_empty());
+        }
+    }
+
+    #[test]
+    fn length_follows_arcs() {
+        let mut half_circle = polyline(&[(0.0, 0.0), (2.0, 0.0)], false);
+        half_circle.vertices[0].bulge = 1.0;
+        assert!((half_circle.length() - PI).abs() < 1e-4);
+        let square = polyline(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)], true);
+        assert!((square.length() - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn inserting_on_an_arc_keeps_it_round() {
+        let mut half_circle = polyline(&[(0.0, 0.0), (2.0, 0.0)], false);
+        half_circle.vertices[0].bulge = -1.0;
+        let length = half_circle.length();
+        let index = half_circle.insert_vertex(Point::new(1.0, 1.0), 1e-3);
+        assert_eq!(index, 1);
+        assert!((half_circle.length() - length).abs() < 1e-4);
+    }
+
+    #[test]
+    fn breaking_a_closed_polyline_opens_it_up() {
+        let square = polyline(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)], true);
+        let pieces = square.broken_at(Point::new(1.0, -0.1), 1e-3);
+        assert_eq!(pieces.len(), 1);
+        assert!(!pieces[0].closed);
+        assert!(near(pieces[0].vertices[0].position, Point::new(1.0, 0.0)));
+        assert!((pieces[0].length() - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn breaking_at_an_end_leaves_one_piece() {
+        let line = polyline(&[(0.0, 0.0), (2.0, 0.0)], false);
+        assert_eq!(line.broken_at(Point::new(0.0, 0.0), 1e-3).len(), 1);
+    }
+
+    fn square() -> Polyline {
+        polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], true)
+    }
+
+    #[test]
+    fn filleting_rounds_every_corner_of_a_closed_polyline() {
+        let (filleted, too_short) = square().filleted(1.0);
+        assert_eq!(too_short, 0);
+        assert_eq!(filleted.vertices.len(), 8);
+        let expected = 4.0 * 2.0 + 2.0 * PI;
+        assert!((filleted.length() - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn filleting_leaves_the_ends_of_an_open_polyline() {
+        let open = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)], false);
+        let (filleted, too_short) = open.filleted(1.0);
+        assert_eq!(too_short, 0);
+        assert_eq!(filleted.vertices.len(), 4);
+        assert!(near(filleted.vertices[0].position, Point::new(0.0, 0.0)));
+        assert!(near(filleted.vertices[3].position, Point::new(4.0, 4.0)));
+    }
+
+    #[test]
+    fn corners_too_short_for_the_radius_are_counted() {
+        let (filleted, too_short) = square().filleted(3.0);
+        assert_eq!(too_short, 4);
+        assert_eq!(filleted, square());
+    }
+
+    #[test]
+    fn filleting_at_zero_takes_the_arcs_back_out() {
+        for closed in [false, true] {
+            let original = Polyline { closed, ..square() };
+            let (rounded, _) = original.filleted(1.0);
+            let (sharp, left) = rounded.filleted(0.0);
+            assert_eq!(left, 0);
+            assert_eq!(sharp.vertices.len(), original.vertices.len());
+            for (a, b) in sharp.vertices.iter().zip(original.vertices.iter()) {
+                assert!(near(a.position, b.position));
+                assert_eq!(a.bulge, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn arcs_between_parallel_segments_stay_at_zero() {
+        let mut slot = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (0.0, 2.0)], false);
+        slot.vertices[1].bulge = 1.0;
+        let (sharp, left) = slot.filleted(0.0);
+        assert_eq!(left, 1);
+        assert_eq!(sharp, slot);
+    }
+
+    #[test]
+    fn straight_through_vertices_are_left_alone() {
+        let straight = polyline(&[(0.0, 0.0), (2.0, 0.0), (4.0, 0.0)], false);
+        let (filleted, _) = straight.filleted(1.0);
+        assert_eq!(filleted.vertices.len(), 3);
+    }
+
+    #[test]
+    fn stretching_a_closed_two_vertex_polyline_adds_no_chord() {
+        // a circle of radius 1 around the origin, out of two half circles
+        let mut circle = polyline(&[(1.0, 0.0), (-1.0, 0.0)], true);
+        circle.vertices[0].bulge = 1.0;
+        circle.vertices[1].bulge = 1.0;
+        let stretch = Affine {
+            x: Point::new(2.0, 0.0),
+            y: Point::new(0.0, 1.0),
+            offset: Point::new(0.0, 0.0),
+        };
+        let stretched = circle.transformed(&stretch, 1e-3);
+        let expected: usize = circle
+            .spans()
+            .iter()
+            .map(|(from, to)| span_points(from.position, *to, from.bulge, 1e-3).len() - 1)
+            .sum();
+        assert!(stretched.closed);
+        assert_eq!(stretched.vertices.len(), expected);
+        // every vertex on the stretched circle and none doubled up
+        for v in stretched.vertices.iter() {
+            let (x, y) = (v.position.x / 2.0, v.position.y);
+            assert!((x.hypot(y) - 1.0).abs() < 1e-4);
+        }
+        for (from, to) in stretched.spans() {
+            assert!(from.position.distance(to) > 1e-4);
+        }
+    }
+}
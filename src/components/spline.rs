@@ -0,0 +1,556 @@
+use super::{connect, Affine, Point, Polyline, Properties, Vertex};
+
+/// how many times a knot span is halved at most while following the curve
+const MAX_DEPTH: usize = 12;
+
+/// halvings every knot span gets regardless, so wiggles between samples aren't missed
+const MIN_DEPTH: usize = 2;
+
+/// A non-uniform rational b-spline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spline {
+    pub degree: usize,
+    /// non-decreasing, as many as the control points plus the degree plus one
+    pub knots: Vec<f32>,
+    /// one for each control point, all positive
+    pub weights: Vec<f32>,
+    pub control: Vec<Point>,
+    pub props: Properties,
+}
+
+impl Spline {
+    pub fn new(
+        degree: usize,
+        knots: Vec<f32>,
+        weights: Vec<f32>,
+        control: Vec<Point>,
+        props: Properties,
+    ) -> Option<Self> {
+        let valid = degree > 0
+            && control.len() > degree
+            && knots.len() == control.len() + degree + 1
+            && weights.len() == control.len()
+            && knots.windows(2).all(|pair| pair[0] <= pair[1])
+            && knots[degree] < knots[control.len()]
+            && weights.iter().all(|w| *w > 0.0);
+        if valid {
+            Some(Spline {
+                degree,
+                knots,
+                weights,
+                control,
+                props,
+            })
+        } else {
+            None
+        }
+    }
+    /// the clamped, evenly spaced spline pulled towards each control point,
+    /// lowering the degree if there aren't enough of them for it
+    pub fn through_control(degree: usize, control: Vec<Point>, props: Properties) -> Option<Self> {
+        let count = control.len();
+        if count < 2 {
+            return None;
+        }
+        let degree = degree.clamp(1, count - 1);
+        let spans = count - degree;
+        let mut knots = vec![0.0; degree + 1];
+        knots.extend((1..spans).map(|i| i as f32 / spans as f32));
+        knots.extend(vec![1.0; degree + 1]);
+        Spline::new(degree, knots, vec![1.0; count], control, props)
+    }
+    /// the smooth loop pulled towards each control point in turn, wrapping
+    /// around evenly spaced knots instead of clamping at the ends
+    pub fn closed_through_control(
+        degree: usize,
+        control: Vec<Point>,
+        props: Properties,
+    ) -> Option<Self> {
+        let count = control.len();
+        if count < 3 {
+            return None;
+        }
+        let degree = degree.clamp(1, count - 1);
+        let mut wrapped = control.clone();
+        wrapped.extend_from_slice(&control[..degree]);
+        let knots = (0..wrapped.len() + degree + 1)
+            .map(|i| i as f32 / count as f32)
+            .collect();
+        Spline::new(degree, knots, vec![1.0; wrapped.len()], wrapped, props)
+    }
+    /// the cubic passing through every fit point, leaving and arriving along
+    /// the tangents given or ones guessed from the neighbouring points
+    pub fn through_fit(
+        points: &[Point],
+        start_tangent: Option<Point>,
+        end_tangent: Option<Point>,
+        props: Properties,
+    ) -> Option<Self> {
+        let mut fit: Vec<Point> = vec![];
+        for p in points {
+            if fit
+                .last()
+                .map(|last| last.distance(*p) > f32::EPSILON)
+                .unwrap_or(true)
+            {
+                fit.push(*p);
+            }
+        }
+        if fit.len() < 2 {
+            return None;
+        }
+        let n = fit.len() - 1;
+        // parameters spaced by the distance between fit points
+        let total: f32 = fit.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+        let mut params = vec![0.0];
+        for pair in fit.windows(2) {
+            let last = params[params.len() - 1];
+            params.push(last + pair[0].distance(pair[1]) / total);
+        }
+        params[n] = 1.0;
+        // derivatives with respect to the parameter, so as long as the whole curve
+        let start = start_tangent
+            .and_then(Point::normalize)
+            .map(|t| t * total)
+            .unwrap_or_else(|| (fit[1] - fit[0]) * (1.0 / params[1]));
+        let end = end_tangent
+            .and_then(Point::normalize)
+            .map(|t| t * total)
+            .unwrap_or_else(|| (fit[n] - fit[n - 1]) * (1.0 / (1.0 - params[n - 1])));
+
+        let count = n + 3;
+        let mut knots = vec![0.0; 4];
+        knots.extend(&params[1..n]);
+        knots.extend(vec![1.0; 4]);
+        let mut spline = Spline {
+            degree: 3,
+            knots,
+            weights: vec![1.0; count],
+            control: vec![Point::new(0.0, 0.0); count],
+            props,
+        };
+        // the ends and the points beside them follow from the tangents, the
+        // rest from making the curve pass through each interior fit point
+        let mut rows = vec![vec![0.0; count]; count];
+        let mut targets = vec![fit[0]; count];
+        rows[0][0] = 1.0;
+        rows[1][1] = 1.0;
+        targets[1] = fit[0] + start * (spline.knots[4] / 3.0);
+        for k in 1..n {
+            let span = spline.find_span(params[k]);
+            for (j, value) in spline.basis(span, params[k]).into_iter().enumerate() {
+                rows[k + 1][span - 3 + j] = value;
+            }
+            targets[k + 1] = fit[k];
+        }
+        rows[count - 2][count - 2] = 1.0;
+        targets[count - 2] = fit[n] - end * ((1.0 - spline.knots[count - 1]) / 3.0);
+        rows[count - 1][count - 1] = 1.0;
+        targets[count - 1] = fit[n];
+        spline.control = solve(rows, targets)?;
+        Some(spline)
+    }
+    /// the range of parameters the curve is defined over
+    pub fn domain(&self) -> (f32, f32) {
+        (self.knots[self.degree], self.knots[self.control.len()])
+    }
+    /// which knot span `u` falls in
+    fn find_span(&self, u: f32) -> usize {
+        let n = self.control.len() - 1;
+        if u >= self.knots[n + 1] {
+            return n;
+        }
+        let (mut low, mut high) = (self.degree, n + 1);
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if u < self.knots[middle] {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        low
+    }
+    /// the basis functions which aren't zero at `u`, for the control points
+    /// `span - degree` to `span`
+    fn basis(&self, span: usize, u: f32) -> Vec<f32> {
+        let p = self.degree;
+        let mut values = vec![1.0; p + 1];
+        let mut left = vec![0.0; p + 1];
+        let mut right = vec![0.0; p + 1];
+        for j in 1..=p {
+            left[j] = u - self.knots[span + 1 - j];
+            right[j] = self.knots[span + j] - u;
+            let mut saved = 0.0;
+            for r in 0..j {
+                let denominator = right[r + 1] + left[j - r];
+                let temp = if denominator == 0.0 {
+                    0.0
+                } else {
+                    values[r] / denominator
+                };
+                values[r] = saved + right[r + 1] * temp;
+                saved = left[j - r] * temp;
+            }
+            values[j] = saved;
+        }
+        values
+    }
+    pub fn point_at(&self, u: f32) -> Point {
+        let (start, end) = self.domain();
+        let u = u.clamp(start, end);
+        let span = self.find_span(u);
+        let mut sum = Point::new(0.0, 0.0);
+        let mut weight = 0.0;
+        for (j, value) in self.basis(span, u).into_iter().enumerate() {
+            let i = span - self.degree + j;
+            sum = sum + self.control[i] * (value * self.weights[i]);
+            weight += value * self.weights[i];
+        }
+        sum * (1.0 / weight)
+    }
+    pub fn start_point(&self) -> Point {
+        self.point_at(self.domain().0)
+    }
+    pub fn end_point(&self) -> Point {
+        self.point_at(self.domain().1)
+    }
+    /// the same curve with one more knot at `u`
+    fn insert_knot(&self, u: f32) -> Spline {
+        let p = self.degree;
+        let k = self.find_span(u);
+        let mut control = Vec::with_capacity(self.control.len() + 1);
+        let mut weights = Vec::with_capacity(self.control.len() + 1);
+        for i in 0..=self.control.len() {
+            if i + p <= k {
+                control.push(self.control[i]);
+                weights.push(self.weights[i]);
+            } else if i > k {
+                control.push(self.control[i - 1]);
+                weights.push(self.weights[i - 1]);
+            } else {
+                // blend neighbouring points in homogeneous coordinates
+                let alpha = (u - self.knots[i]) / (self.knots[i + p] - self.knots[i]);
+                let (w0, w1) = (self.weights[i - 1], self.weights[i]);
+                let weight = alpha * w1 + (1.0 - alpha) * w0;
+                let point = (self.control[i] * (alpha * w1)
+                    + self.control[i - 1] * ((1.0 - alpha) * w0))
+                    * (1.0 / weight);
+                control.push(point);
+                weights.push(weight);
+            }
+        }
+        let mut knots = self.knots.clone();
+        knots.insert(k + 1, u);
+        Spline {
+            degree: p,
+            knots,
+            weights,
+            control,
+            props: self.props,
+        }
+    }
+    /// the two curves either side of `u`, if it's inside the curve
+    pub fn split(&self, u: f32) -> Option<(Spline, Spline)> {
+        let (start, end) = self.domain();
+        if u <= start || u >= end {
+            return None;
+        }
+        let p = self.degree;
+        let existing = self.knots.iter().filter(|k| **k == u).count();
+        let mut spline = self.clone();
+        for _ in existing..p {
+            spline = spline.insert_knot(u);
+        }
+        // u now appears degree times, the control point before the first of
+        // them lying on the curve and shared by both halves
+        let first = spline.knots.iter().position(|k| *k == u)?;
+        let mut left_knots = spline.knots[..first + p].to_vec();
+        left_knots.push(u);
+        let mut right_knots = vec![u];
+        right_knots.extend(&spline.knots[first..]);
+        let left = Spline::new(
+            p,
+            left_knots,
+            spline.weights[..first].to_vec(),
+            spline.control[..first].to_vec(),
+            self.props,
+        )?;
+        let right = Spline::new(
+            p,
+            right_knots,
+            spline.weights[first - 1..].to_vec(),
+            spline.control[first - 1..].to_vec(),
+            self.props,
+        )?;
+        Some((left, right))
+    }
+    /// parameters and points along the curve close enough together that the
+    /// straight pieces between them stay within `tolerance` of it
+    pub fn samples(&self, tolerance: f32) -> Vec<(f32, Point)> {
+        let (start, end) = self.domain();
+        let mut samples = vec![(start, self.point_at(start))];
+        let mut breaks: Vec<f32> = self
+            .knots
+            .iter()
+            .cloned()
+            .filter(|k| *k > start && *k < end)
+            .collect();
+        breaks.dedup();
+        breaks.push(end);
+        let mut from = start;
+        for to in breaks {
+            let (a, b) = ((from, self.point_at(from)), (to, self.point_at(to)));
+            self.refine(a, b, 0, tolerance, &mut samples);
+            from = to;
+        }
+        samples
+    }
+    fn refine(
+        &self,
+        a: (f32, Point),
+        b: (f32, Point),
+        depth: usize,
+        tolerance: f32,
+        out: &mut Vec<(f32, Point)>,
+    ) {
+        let u = (a.0 + b.0) / 2.0;
+        let middle = (u, self.point_at(u));
+        let strays = middle.1.distance_to_segment(a.1, b.1) > tolerance;
+        if depth < MAX_DEPTH && (depth < MIN_DEPTH || strays) {
+            self.refine(a, middle, depth + 1, tolerance, out);
+            self.refine(middle, b, depth + 1, tolerance, out);
+        } else {
+            out.push(b);
+        }
+    }
+    /// parameter of the point on the curve closest to `p`
+    pub fn closest_parameter(&self, p: Point, tolerance: f32) -> f32 {
+        let samples = self.samples(tolerance);
+        let mut best = (f32::INFINITY, samples[0].0);
+        for pair in samples.windows(2) {
+            let ((u0, a), (u1, b)) = (pair[0], pair[1]);
+            let ab = b - a;
+            let t = if ab.dot(ab) == 0.0 {
+                0.0
+            } else {
+                ((p - a).dot(ab) / ab.dot(ab)).clamp(0.0, 1.0)
+            };
+            let distance = p.distance(a + ab * t);
+            if distance < best.0 {
+                best = (distance, u0 + (u1 - u0) * t);
+            }
+        }
+        best.1
+    }
+    pub fn to_polyline(&self, tolerance: f32) -> Polyline {
+        let vertices = self
+            .samples(tolerance)
+            .into_iter()
+            .map(|(_, p)| Vertex::new(p))
+            .collect();
+        Polyline::new(vertices, false, self.props)
+    }
+    /// the same spline moved by `transform`, which maps splines exactly
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        Spline {
+            control: self.control.iter().map(|p| transform.apply(*p)).collect(),
+            ..self.clone()
+        }
+    }
+    pub fn segments(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let points: Vec<Point> = self
+            .samples(tolerance)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        connect(&points)
+    }
+}
+
+/// solve the square system `rows * x = targets` for the points `x`, by
+/// elimination in double precision
+fn solve(rows: Vec<Vec<f32>>, targets: Vec<Point>) -> Option<Vec<Point>> {
+    let n = rows.len();
+    let mut rows: Vec<Vec<f64>> = rows
+        .into_iter()
+        .map(|row| row.into_iter().map(f64::from).collect())
+        .collect();
+    let mut targets: Vec<(f64, f64)> = targets
+        .into_iter()
+        .map(|p| (f64::from(p.x), f64::from(p.y)))
+        .collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            rows[*a][column]
+                .abs()
+                .partial_cmp(&rows[*b][column].abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        if rows[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        rows.swap(column, pivot);
+        targets.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = rows[row][column] / rows[column][column];
+            if factor == 0.0 {
+                continue;
+            }
+            let pivot_row = rows[column].clone();
+            for (value, above) in rows[row].iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * above;
+            }
+            targets[row].0 -= factor * targets[column].0;
+            targets[row].1 -= factor * targets[column].1;
+        }
+    }
+    let mut solution = vec![(0.0, 0.0); n];
+    for row in (0..n).rev() {
+        let (mut x, mut y) = targets[row];
+        for k in row + 1..n {
+            x -= rows[row][k] * solution[k].0;
+            y -= rows[row][k] * solution[k].1;
+        }
+        solution[row] = (x / rows[row][row], y / rows[row][row]);
+    }
+    Some(
+        solution
+            .into_iter()
+            .map(|(x, y)| Point::new(x as f32, y as f32))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::Spline;
+    use crate::components::Point;
+
+    fn wiggle() -> Spline {
+        let control = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 2.0),
+            Point::new(3.0, -1.0),
+            Point::new(4.0, 1.0),
+            Point::new(6.0, 0.0),
+        ];
+        Spline::through_control(3, control, props()).unwrap()
+    }
+
+    #[test]
+    fn clamped_splines_start_and_end_on_their_control_points() {
+        let spline = wiggle();
+        assert!(near(spline.start_point(), Point::new(0.0, 0.0)));
+        assert!(near(spline.end_point(), Point::new(6.0, 0.0)));
+    }
+
+    #[test]
+    fn too_few_control_points_lower_the_degree() {
+        let control = vec![Point::new(0.0, 0.0), Point::new(2.0, 2.0)];
+        let spline = Spline::through_control(3, control, props()).unwrap();
+        assert_eq!(spline.degree, 1);
+        assert!(near(spline.point_at(0.5), Point::new(1.0, 1.0)));
+        assert_eq!(
+            Spline::through_control(3, vec![Point::new(0.0, 0.0)], props()),
+            None
+        );
+    }
+
+    #[test]
+    fn weights_pull_a_quadratic_onto_a_circle() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let quarter = Spline::new(
+            2,
+            vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            vec![1.0, half, 1.0],
+            vec![
+                Point::new(1.0, 0.0),
+                Point::new(1.0, 1.0),
+                Point::new(0.0, 1.0),
+            ],
+            props(),
+        )
+        .unwrap();
+        for i in 0..=8 {
+            let p = quarter.point_at(i as f32 / 8.0);
+            assert!((p.length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn mismatched_knots_or_weights_are_refused() {
+        let control = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 0.0),
+        ];
+        let knots = vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        let new = |knots: Vec<f32>, weights: Vec<f32>| {
+            Spline::new(2, knots, weights, control.clone(), props())
+        };
+        assert!(new(knots.clone(), vec![1.0; 3]).is_some());
+        assert!(new(knots[1..].to_vec(), vec![1.0; 3]).is_none());
+        assert!(new(vec![0.0, 0.0, 1.0, 0.5, 1.0, 1.0], vec![1.0; 3]).is_none());
+        assert!(new(knots.clone(), vec![1.0, 0.0, 1.0]).is_none());
+        assert!(new(vec![0.0; 6], vec![1.0; 3]).is_none());
+    }
+
+    #[test]
+    fn fit_splines_pass_through_every_fit_point() {
+        let fit = [
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(3.0, 3.0),
+            Point::new(5.0, 2.0),
+        ];
+        let spline = Spline::through_fit(&fit, None, None, props()).unwrap();
+        for p in fit.iter() {
+            let u = spline.closest_parameter(*p, 1e-4);
+            assert!(spline.point_at(u).distance(*p) < 1e-3, "misses {:?}", p);
+        }
+    }
+
+    #[test]
+    fn repeated_fit_points_are_one_point() {
+        let p = Point::new(1.0, 1.0);
+        assert_eq!(Spline::through_fit(&[p, p, p], None, None, props()), None);
+        let spline = Spline::through_fit(&[p, p, Point::new(3.0, 1.0)], None, None, props());
+        assert!(spline.is_some());
+    }
+
+    #[test]
+    fn split_halves_meet_and_follow_the_curve() {
+        let spline = wiggle();
+        let u = 0.37;
+        let (left, right) = spline.split(u).unwrap();
+        let at = spline.point_at(u);
+        assert!(near(left.end_point(), at) && near(right.start_point(), at));
+        assert!(near(left.start_point(), spline.start_point()));
+        assert!(near(right.end_point(), spline.end_point()));
+        // each half still runs along the same curve
+        assert!(near(left.point_at(0.2), spline.point_at(0.2)));
+        assert!(near(right.point_at(0.8), spline.point_at(0.8)));
+    }
+
+    #[test]
+    fn splitting_at_an_end_leaves_nothing_to_split() {
+        let spline = wiggle();
+        let (start, end) = spline.domain();
+        assert_eq!(spline.split(start), None);
+        assert_eq!(spline.split(end), None);
+    }
+
+    #[test]
+    fn closed_splines_come_back_round() {
+        let control = vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ];
+        let spline = Spline::closed_through_control(3, control, props()).unwrap();
+        assert!(near(spline.start_point(), spline.end_point()));
+    }
+}
@@ -0,0 +1,487 @@
+use super::{connect, font, Affine, Point, Properties};
+use crate::{common::GenerationID, resources::TextStyle};
+
+/// how far apart the baselines of multiline text are, in text heights
+const LINE_SPACING: f32 = 5.0 / 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Horizontal {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Vertical {
+    Baseline,
+    Bottom,
+    Middle,
+    Top,
+}
+
+/// which point of a block of text its insertion point is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Justify {
+    pub horizontal: Horizontal,
+    pub vertical: Vertical,
+}
+
+impl Justify {
+    pub fn new(horizontal: Horizontal, vertical: Vertical) -> Self {
+        Justify {
+            horizontal,
+            vertical,
+        }
+    }
+    /// the justification typed as a keyword: `l`, `c`, `r` or `m` along the
+    /// baseline, or `t`, `m` or `b` followed by `l`, `c` or `r`
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        let horizontal = |c: char| match c {
+            'l' => Some(Horizontal::Left),
+            'c' => Some(Horizontal::Center),
+            'r' => Some(Horizontal::Right),
+            _ => None,
+        };
+        let mut chars = keyword.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some('m'), None, None) => Some(Justify::new(Horizontal::Center, Vertical::Middle)),
+            (Some(h), None, None) => Some(Justify::new(horizontal(h)?, Vertical::Baseline)),
+            (Some(v), Some(h), None) => {
+                let vertical = match v {
+                    't' => Vertical::Top,
+                    'm' => Vertical::Middle,
+                    'b' => Vertical::Bottom,
+                    _ => return None,
+                };
+                Some(Justify::new(horizontal(h)?, vertical))
+            }
+            _ => None,
+        }
+    }
+    /// the justification keeping text mirrored along or across its baseline
+    /// on the same side of its insertion point as its mirror image
+    fn flipped(self, along: bool) -> Self {
+        if along {
+            let horizontal = match self.horizontal {
+                Horizontal::Left => Horizontal::Right,
+                Horizontal::Center => Horizontal::Center,
+                Horizontal::Right => Horizontal::Left,
+            };
+            Justify::new(horizontal, self.vertical)
+        } else {
+            let vertical = match self.vertical {
+                Vertical::Baseline | Vertical::Bottom => Vertical::Top,
+                Vertical::Middle => Vertical::Middle,
+                Vertical::Top => Vertical::Bottom,
+            };
+            Justify::new(self.horizontal, vertical)
+        }
+    }
+    /// how much of a line's width lies before the insertion point
+    fn lead(self) -> f32 {
+        match self.horizontal {
+            Horizontal::Left => 0.0,
+            Horizontal::Center => 0.5,
+            Horizontal::Right => 1.0,
+        }
+    }
+    /// how far above the insertion point the first baseline of `lines` lines sits
+    fn rise(self, lines: usize) -> f32 {
+        let last = -((lines.max(1) - 1) as f32) * LINE_SPACING;
+        match self.vertical {
+            Vertical::Baseline => 0.0,
+            Vertical::Bottom => font::DESCENT - last,
+            Vertical::Middle => -(1.0 + last) / 2.0,
+            Vertical::Top => -1.0,
+        }
+    }
+}
+
+/// How text in a style is lettered, copied from the style onto each text
+/// so it can be drawn without looking the style up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lettering {
+    pub style: GenerationID<TextStyle>,
+    /// how much wider than usual each character is drawn
+    pub width_factor: f32,
+    /// how far characters lean forwards, in radians
+    pub oblique: f32,
+    pub backwards: bool,
+    pub upside_down: bool,
+}
+
+impl Lettering {
+    /// lettering in the style `id`, as it looks now
+    pub fn from_style(id: GenerationID<TextStyle>, style: &TextStyle) -> Self {
+        Lettering {
+            style: id,
+            width_factor: style.width_factor,
+            oblique: style.oblique,
+            backwards: style.backwards,
+            upside_down: style.upside_down,
+        }
+    }
+    /// the transform taking text heights along and up from the insertion
+    /// point to where characters are drawn, leaning and flipped as lettered
+    fn placement(&self, upright: &Affine) -> Affine {
+        let x = if self.backwards {
+            upright.x * -1.0
+        } else {
+            upright.x
+        };
+        let y = if self.upside_down {
+            upright.y * -1.0
+        } else {
+            upright.y
+        };
+        Affine {
+            x,
+            y: y + upright.x * (self.oblique.tan() / self.width_factor),
+            offset: upright.offset,
+        }
+    }
+}
+
+/// Text on a single line, drawn with strokes so it zooms and prints along
+/// with everything else.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    pub content: String,
+    pub position: Point,
+    pub height: f32,
+    pub rotation: f32,
+    pub justify: Justify,
+    pub lettering: Lettering,
+    pub props: Properties,
+}
+
+impl Text {
+    pub fn new(
+        content: &str,
+        position: Point,
+        height: f32,
+        rotation: f32,
+        justify: Justify,
+        lettering: Lettering,
+        props: Properties,
+    ) -> Option<Self> {
+        if content.trim().is_empty() || height <= 0.0 {
+            return None;
+        }
+        Some(Text {
+            content: content.to_string(),
+            position,
+            height,
+            rotation,
+            justify,
+            lettering,
+            props,
+        })
+    }
+    fn upright(&self) -> Affine {
+        upright(
+            self.position,
+            self.height,
+            self.rotation,
+            self.lettering.width_factor,
+        )
+    }
+    /// where the baseline starts, whatever the justification
+    pub fn baseline_start(&self) -> Point {
+        let width = font::line_width(&self.content);
+        let origin = Point::new(-self.justify.lead() * width, self.justify.rise(1));
+        self.lettering.placement(&self.upright()).apply(origin)
+    }
+    /// where the line after this one would be inserted
+    pub fn next_line(&self) -> Point {
+        self.upright().apply(Point::new(0.0, -LINE_SPACING))
+    }
+    /// this text looking the way its style now does
+    pub fn restyle(&mut self, style: &TextStyle) {
+        self.lettering = Lettering::from_style(self.lettering.style, style);
+        if let Some(height) = style.fixed_height() {
+            self.height = height;
+        }
+    }
+    /// this text moved by `transform`, turned back to read forwards if it
+    /// was mirrored
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        let frame = Frame::moved(&self.upright(), transform);
+        Text {
+            position: transform.apply(self.position),
+            height: frame.height,
+            rotation: frame.rotation,
+            justify: frame.justify(self.justify),
+            lettering: Lettering {
+                width_factor: frame.width_factor,
+                ..self.lettering
+            },
+            ..self.clone()
+        }
+    }
+    pub fn segments(&self) -> Vec<(Point, Point)> {
+        let placement = self.lettering.placement(&self.upright());
+        strokes(&[self.content.as_str()], self.justify, &placement)
+    }
+}
+
+/// Text running over several lines, each paragraph wrapping to fit within
+/// `width` when it's more than zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MText {
+    /// paragraphs, one to a line
+    pub content: String,
+    pub position: Point,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+    pub justify: Justify,
+    pub lettering: Lettering,
+    pub props: Properties,
+}
+
+impl MText {
+    pub fn new(
+        content: &str,
+        position: Point,
+        width: f32,
+        height: f32,
+        justify: Justify,
+        lettering: Lettering,
+        props: Properties,
+    ) -> Option<Self> {
+        if content.trim().is_empty() || height <= 0.0 {
+            return None;
+        }
+        Some(MText {
+            content: content.to_string(),
+            position,
+            width: width.max(0.0),
+            height,
+            rotation: 0.0,
+            justify,
+            lettering,
+            props,
+        })
+    }
+    fn upright(&self) -> Affine {
+        upright(
+            self.position,
+            self.height,
+            self.rotation,
+            self.lettering.width_factor,
+        )
+    }
+    /// the lines the paragraphs break into, words wrapping onto the next
+    /// line when they'd run past the width
+    pub fn lines(&self) -> Vec<String> {
+        let limit = self.width / (self.height * self.lettering.width_factor);
+        let mut lines = vec![];
+        for paragraph in self.content.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let longer = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if self.width > 0.0 && !line.is_empty() && font::line_width(&longer) > limit {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                } else {
+                    line = longer;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    pub fn restyle(&mut self, style: &TextStyle) {
+        self.lettering = Lettering::from_style(self.lettering.style, style);
+        if let Some(height) = style.fixed_height() {
+            self.height = height;
+        }
+    }
+    pub fn transformed(&self, transform: &Affine) -> Self {
+        let frame = Frame::moved(&self.upright(), transform);
+        let stretch =
+            frame.height * frame.width_factor / (self.height * self.lettering.width_factor);
+        MText {
+            position: transform.apply(self.position),
+            width: self.width * stretch,
+            height: frame.height,
+            rotation: frame.rotation,
+            justify: frame.justify(self.justify),
+            lettering: Lettering {
+                width_factor: frame.width_factor,
+                ..self.lettering
+            },
+            ..self.clone()
+        }
+    }
+    pub fn segments(&self) -> Vec<(Point, Point)> {
+        let lines = self.lines();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let placement = self.lettering.placement(&self.upright());
+        strokes(&lines, self.justify, &placement)
+    }
+}
+
+/// the transform taking text heights along and up from the insertion
+/// point to where they are in the drawing, for text standing straight up
+fn upright(position: Point, height: f32, rotation: f32, width_factor: f32) -> Affine {
+    let along = Point::from_angle(rotation);
+    Affine {
+        x: along * (height * width_factor),
+        y: along.perp() * height,
+        offset: position,
+    }
+}
+
+/// how text sits once its upright placement has been moved, text keeping square
+/// to its baseline and reading forwards whatever the move did to it
+struct Frame {
+    height: f32,
+    rotation: f32,
+    width_factor: f32,
+    /// mirrored along its baseline if true, across it if false
+    mirrored: Option<bool>,
+}
+
+impl Frame {
+    fn moved(upright: &Affine, transform: &Affine) -> Self {
+        let mut along = transform.apply_vector(upright.x);
+        let up = transform.apply_vector(upright.y);
+        let turned = along.cross(up);
+        let stretch = along.length();
+        let height = turned.abs() / stretch;
+        let mirrored = if turned >= 0.0 {
+            None
+        } else if along.dot(upright.x) < 0.0 {
+            along = along * -1.0;
+            Some(true)
+        } else {
+            Some(false)
+        };
+        Frame {
+            height,
+            rotation: along.angle(),
+            width_factor: stretch / height,
+            mirrored,
+        }
+    }
+    fn justify(&self, justify: Justify) -> Justify {
+        match self.mirrored {
+            Some(along) => justify.flipped(along),
+            None => justify,
+        }
+    }
+}
+
+/// the strokes of `lines` laid out one under another and placed in the drawing
+fn strokes(lines: &[&str], justify: Justify, placement: &Affine) -> Vec<(Point, Point)> {
+    let rise = justify.rise(lines.len());
+    let mut segments = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        let start = -justify.lead() * font::line_width(line);
+        let baseline = rise - row as f32 * LINE_SPACING;
+        for (column, c) in line.chars().enumerate() {
+            let origin = Point::new(start + column as f32 * font::ADVANCE, baseline);
+            for stroke in font::glyph(c) {
+                let points: Vec<Point> = stroke
+                    .into_iter()
+                    .map(|p| placement.apply(origin + p))
+                    .collect();
+                segments.extend(connect(&points));
+            }
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{near, props};
+    use super::{font, Horizontal, Justify, Lettering, MText, Text, Vertical};
+    use crate::components::{Affine, Point};
+    use crate::resources::{TextStyle, TextStyles};
+
+    fn lettering() -> Lettering {
+        let style = TextStyle::new("standard");
+        let id = TextStyles::new().push(style.clone());
+        Lettering::from_style(id, &style)
+    }
+
+    fn text(content: &str, justify: Justify) -> Text {
+        let position = Point::new(10.0, 5.0);
+        Text::new(content, position, 2.0, 0.0, justify, lettering(), props()).unwrap()
+    }
+
+    /// whether `moved` is `segments` shifted along by `by`, stroke for stroke
+    fn shifted(segments: &[(Point, Point)], moved: &[(Point, Point)], by: Point) -> bool {
+        segments.len() == moved.len()
+            && segments
+                .iter()
+                .zip(moved)
+                .all(|((a, b), (c, d))| near(*a + by, *c) && near(*b + by, *d))
+    }
+
+    #[test]
+    fn justification_keywords() {
+        let baseline = |h| Some(Justify::new(h, Vertical::Baseline));
+        assert_eq!(Justify::from_keyword("l"), baseline(Horizontal::Left));
+        assert_eq!(Justify::from_keyword("r"), baseline(Horizontal::Right));
+        let middle = Some(Justify::new(Horizontal::Center, Vertical::Middle));
+        assert_eq!(Justify::from_keyword("m"), middle);
+        let top_right = Some(Justify::new(Horizontal::Right, Vertical::Top));
+        assert_eq!(Justify::from_keyword("tr"), top_right);
+        for bad in ["", "x", "tx", "xl", "tlc"] {
+            assert_eq!(Justify::from_keyword(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn blank_text_or_no_height_is_no_text() {
+        let justify = Justify::new(Horizontal::Left, Vertical::Baseline);
+        let at = Point::new(0.0, 0.0);
+        assert!(Text::new("  ", at, 1.0, 0.0, justify, lettering(), props()).is_none());
+        assert!(Text::new("a", at, 0.0, 0.0, justify, lettering(), props()).is_none());
+    }
+
+    #[test]
+    fn right_justified_text_ends_at_its_insertion_point() {
+        let left = text("HI", Justify::new(Horizontal::Left, Vertical::Baseline));
+        let right = text("HI", Justify::new(Horizontal::Right, Vertical::Baseline));
+        let width = font::line_width("HI") * 2.0;
+        assert!(near(left.baseline_start(), Point::new(10.0, 5.0)));
+        assert!(near(right.baseline_start(), Point::new(10.0 - width, 5.0)));
+        let by = Point::new(-width, 0.0);
+        assert!(shifted(&left.segments(), &right.segments(), by));
+    }
+
+    #[test]
+    fn mirrored_text_still_reads_forwards() {
+        let original = text("HI", Justify::new(Horizontal::Left, Vertical::Baseline));
+        let mirror = Affine::mirror(Point::new(0.0, 0.0), Point::new(0.0, 1.0)).unwrap();
+        let mirrored = original.transformed(&mirror);
+        assert!(near(mirrored.position, Point::new(-10.0, 5.0)));
+        assert_eq!(mirrored.justify.horizontal, Horizontal::Right);
+        assert!((mirrored.height - 2.0).abs() < 1e-4);
+        // the same strokes moved over, not turned back to front
+        let by = mirrored.baseline_start() - original.baseline_start();
+        assert!(shifted(&original.segments(), &mirrored.segments(), by));
+    }
+
+    #[test]
+    fn paragraphs_wrap_to_the_width() {
+        let justify = Justify::new(Horizontal::Left, Vertical::Top);
+        let at = Point::new(0.0, 0.0);
+        let content = "one two three\nfour";
+        let narrow = MText::new(content, at, 8.0, 1.0, justify, lettering(), props()).unwrap();
+        assert_eq!(narrow.lines(), vec!["one two", "three", "four"]);
+        let unlimited = MText::new(content, at, 0.0, 1.0, justify, lettering(), props()).unwrap();
+        assert_eq!(unlimited.lines(), vec!["one two three", "four"]);
+        // a word longer than the width still gets a line of its own
+        let tight = MText::new("wordy", at, 1.0, 1.0, justify, lettering(), props()).unwrap();
+        assert_eq!(tight.lines(), vec!["wordy"]);
+    }
+}
@@ -0,0 +1,484 @@
+use std::{cmp::Ordering, f32::consts::PI};
+
+use super::{
+    piece::{pieces_of, Piece},
+    Arc, Drawable, Line, Point, Polyline, Vertex,
+};
+
+/// where `drawable` crosses or touches any of `edges`
+pub fn crossings(drawable: &Drawable, edges: &[Drawable], tolerance: f32) -> Vec<Point> {
+    let own = pieces_of(drawable, tolerance);
+    let mut points = vec![];
+    for edge in edges {
+        for other in pieces_of(edge, tolerance) {
+            for piece in own.iter() {
+                for (t, _) in piece.crossings(&other, tolerance) {
+                    points.push(piece.point_at(t));
+                }
+            }
+        }
+    }
+    points
+}
+
+/// the parameters a drawable that can be trimmed runs between, and whether
+/// it comes back round to where it started
+fn extent(drawable: &Drawable) -> Option<(f32, f32, bool)> {
+    match drawable {
+        Drawable::Line(_) => Some((0.0, 1.0, false)),
+        Drawable::Arc(arc) => Some((0.0, arc.sweep(), false)),
+        Drawable::Circle(_) => Some((0.0, 2.0 * PI, true)),
+        Drawable::Polyline(polyline) => Some((0.0, polyline.spans().len() as f32, polyline.closed)),
+        Drawable::Spline(spline) => {
+            let (start, end) = spline.domain();
+            Some((start, end, false))
+        }
+        _ => None,
+    }
+}
+
+/// how far along `drawable` the point nearest `p` is: a fraction of a line,
+/// the angle turned from the start of an arc or circle, the segment of a
+/// polyline and the fraction along it, or a spline's own parameter
+fn along(drawable: &Drawable, p: Point, tolerance: f32) -> Option<f32> {
+    match drawable {
+        Drawable::Line(line) => {
+            let chord = line.end - line.start;
+            let length = chord.dot(chord);
+            if length <= f32::EPSILON {
+                return None;
+            }
+            Some((p - line.start).dot(chord) / length)
+        }
+        Drawable::Arc(arc) => {
+            Some(((p - arc.center).angle() - arc.start_angle).rem_euclid(2.0 * PI))
+        }
+        Drawable::Circle(circle) => Some((p - circle.center).angle().rem_euclid(2.0 * PI)),
+        Drawable::Polyline(polyline) => {
+            let index = polyline.nearest_span(p, tolerance)?;
+            let (from, to) = polyline.spans()[index];
+            let on = polyline.closest_point(p, tolerance)?;
+            // a little slack so points on the curve itself always land
+            let t = Piece::new(from.position, to, from.bulge)
+                .param_of(on, tolerance * 2.0)
+                .unwrap_or(0.0);
+            Some(index as f32 + t)
+        }
+        Drawable::Spline(spline) => Some(spline.closest_parameter(p, tolerance)),
+        _ => None,
+    }
+}
+
+/// the point `t` along `drawable`, measured the way `along` measures it
+fn point_along(drawable: &Drawable, t: f32) -> Option<Point> {
+    match drawable {
+        Drawable::Line(line) => Some(line.start + (line.end - line.start) * t),
+        Drawable::Arc(arc) => Some(arc.point_at(arc.start_angle + t)),
+        Drawable::Circle(circle) => Some(circle.center + Point::from_angle(t) * circle.radius),
+        Drawable::Polyline(polyline) => {
+            let spans = polyline.spans();
+            let index = (t.floor().max(0.0) as usize).min(spans.len().checked_sub(1)?);
+            let (vertex, next) = spans[index];
+            Some(Piece::new(vertex.position, next, vertex.bulge).point_at(t - index as f32))
+        }
+        Drawable::Spline(spline) => Some(spline.point_at(t)),
+        _ => None,
+    }
+}
+
+/// the part of `drawable` between parameters `from` and `to`, going past
+/// the end and round again on closed shapes
+fn part(drawable: &Drawable, from: f32, to: f32) -> Option<Drawable> {
+    match drawable {
+        Drawable::Line(line) => {
+            let chord = line.end - line.start;
+            Some(Drawable::Line(Line::new(
+                line.start + chord * from,
+                line.start + chord * to,
+                line.props,
+            )))
+        }
+        Drawable::Arc(arc) => Some(Drawable::Arc(Arc::new(
+            arc.center,
+            arc.radius,
+            arc.start_angle + from,
+            arc.start_angle + to,
+            arc.props,
+        ))),
+        Drawable::Circle(circle) => Some(Drawable::Arc(Arc::new(
+            circle.center,
+            circle.radius,
+            from,
+            to,
+            circle.props,
+        ))),
+        Drawable::Polyline(polyline) => {
+            let spans = polyline.spans();
+            let mut vertices = vec![];
+            let mut end = None;
+            let mut index = from.floor().max(0.0) as usize;
+            while (index as f32) < to {
+                let (vertex, next) = spans[index % spans.len()];
+                let (a, b) = ((from - index as f32).max(0.0), (to - index as f32).min(1.0));
+                if b > a {
+                    let piece = Piece::new(vertex.position, next, vertex.bulge).between(a, b);
+                    vertices.push(Vertex {
+                        position: piece.from,
+                        bulge: piece.bulge,
+                        ..vertex
+                    });
+                    end = Some(piece.to);
+                }
+                index += 1;
+            }
+            vertices.push(Vertex::new(end?));
+            Some(Drawable::Polyline(Polyline::new(
+                vertices,
+                false,
+                polyline.props,
+            )))
+        }
+        Drawable::Spline(spline) => {
+            let (start, end) = spline.domain();
+            let mut spline = spline.clone();
+            if from > start {
+                spline = spline.split(from)?.1;
+            }
+            if to < end {
+                spline = spline.split(to)?.0;
+            }
+            Some(Drawable::Spline(spline))
+        }
+        _ => None,
+    }
+}
+
+/// What's left of `drawable` once the stretch of it around `pick` is cut
+/// out, between the nearest of `cuts` on either side. Open shapes may keep
+/// a piece either side; closed ones open up into one piece and need cutting
+/// in two places.
+pub fn trim(
+    drawable: &Drawable,
+    pick: Point,
+    cuts: &[Point],
+    tolerance: f32,
+) -> Option<Vec<Drawable>> {
+    let (start, end, closed) = extent(drawable)?;
+    let at = along(drawable, pick, tolerance)?;
+    let ends = [point_along(drawable, start)?, point_along(drawable, end)?];
+    let mut cuts: Vec<f32> = cuts
+        .iter()
+        // cutting right at an open end would leave nothing behind
+        .filter(|p| closed || ends.iter().all(|end| end.distance(**p) > tolerance))
+        .filter_map(|p| along(drawable, *p, tolerance))
+        .filter(|t| *t >= start && *t <= end)
+        .collect();
+    cuts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let before = cuts.iter().rev().find(|t| **t < at).copied();
+    let after = cuts.iter().find(|t| **t > at).copied();
+    if closed {
+        let before = before.or_else(|| cuts.last().copied())?;
+        let after = after.or_else(|| cuts.first().copied())?;
+        if (before - after).abs() <= f32::EPSILON {
+            return None;
+        }
+        let round = if before < after { end - start } else { 0.0 };
+        return Some(vec![part(drawable, after, before + round)?]);
+    }
+    if before.is_none() && after.is_none() {
+        return None;
+    }
+    let mut left = vec![];
+    if let Some(before) = before {
+        left.push(part(drawable, start, before)?);
+    }
+    if let Some(after) = after {
+        left.push(part(drawable, after, end)?);
+    }
+    Some(left)
+}
+
+/// `piece` carried on past its end, straight on or round the same circle,
+/// to the nearest place it meets one of `edges`
+fn extend_piece(piece: Piece, edges: &[Piece], tolerance: f32) -> Option<Piece> {
+    let primitive = piece.primitive()?;
+    let circle = piece.circle();
+    let sweep = piece.sweep();
+    let mut nearest: Option<(f32, Point)> = None;
+    for edge in edges {
+        let mut candidates = vec![edge.from, edge.to];
+        if let Some(other) = edge.primitive() {
+            candidates.extend(primitive.intersect(&other));
+        }
+        for p in candidates {
+            if edge.param_of(p, tolerance).is_none()
+                || primitive.closest_point(p).distance(p) > tolerance
+            {
+                continue;
+            }
+            // how much further it goes to get there, in length or in angle
+            let further = match circle {
+                Some((center, radius)) => {
+                    let turned =
+                        ((p - center).angle() - (piece.to - center).angle()) * sweep.signum();
+                    let turned = turned.rem_euclid(2.0 * PI);
+                    // going round onto itself isn't getting anywhere
+                    if turned * radius <= tolerance || turned >= 2.0 * PI - sweep.abs() {
+                        continue;
+                    }
+                    turned
+                }
+                None => {
+                    let heading = (piece.to - piece.from).normalize()?;
+                    let further = (p - piece.to).dot(heading);
+                    if further <= tolerance {
+                        continue;
+                    }
+                    further
+                }
+            };
+            if nearest.map(|(least, _)| further < least).unwrap_or(true) {
+                nearest = Some((further, p));
+            }
+        }
+    }
+    let (further, to) = nearest?;
+    let bulge = match circle {
+        Some(_) => ((sweep.abs() + further) * sweep.signum() / 4.0).tan(),
+        None => 0.0,
+    };
+    Some(Piece::new(piece.from, to, bulge))
+}
+
+/// `drawable` stretched from the end nearest `pick` to where it first meets
+/// one of `edges`. Lines, arcs and open polylines can be extended.
+pub fn extend(
+    drawable: &Drawable,
+    pick: Point,
+    edges: &[Drawable],
+    tolerance: f32,
+) -> Option<Drawable> {
+    let (start, end, _) = extent(drawable)?;
+    let at = along(drawable, pick, tolerance)?;
+    let from_start = at - start < end - at;
+    let edges: Vec<Piece> = edges
+        .iter()
+        .flat_map(|edge| pieces_of(edge, tolerance))
+        .collect();
+    // the piece at the end being extended, heading out through that end
+    let outward = |piece: Piece| {
+        if from_start {
+            piece.reversed()
+        } else {
+            piece
+        }
+    };
+    match drawable {
+        Drawable::Line(line) => {
+            let piece = extend_piece(
+                outward(Piece::new(line.start, line.end, 0.0)),
+                &edges,
+                tolerance,
+            )?;
+            let piece = outward(piece);
+            Some(Drawable::Line(Line::new(piece.from, piece.to, line.props)))
+        }
+        Drawable::Arc(arc) => {
+            if arc.sweep() >= 2.0 * PI - f32::EPSILON {
+                return None;
+            }
+            let bulge = (arc.sweep() / 4.0).tan();
+            let piece = Piece::new(arc.start_point(), arc.end_point(), bulge);
+            let piece = outward(extend_piece(outward(piece), &edges, tolerance)?);
+            let (start, end) = (
+                (piece.from - arc.center).angle(),
+                (piece.to - arc.center).angle(),
+            );
+            Some(Drawable::Arc(Arc::new(
+                arc.center, arc.radius, start, end, arc.props,
+            )))
+        }
+        Drawable::Polyline(polyline) if !polyline.closed => {
+            let spans = polyline.spans();
+            let mut extended = polyline.clone();
+            let count = extended.vertices.len();
+            if from_start {
+                let (vertex, next) = *spans.first()?;
+                let piece = Piece::new(vertex.position, next, vertex.bulge);
+                let piece = outward(extend_piece(outward(piece), &edges, tolerance)?);
+                extended.vertices[0].position = piece.from;
+                extended.vertices[0].bulge = piece.bulge;
+            } else {
+                let (vertex, next) = *spans.last()?;
+                let piece = extend_piece(
+                    Piece::new(vertex.position, next, vertex.bulge),
+                    &edges,
+                    tolerance,
+                )?;
+                extended.vertices[count - 2].bulge = piece.bulge;
+                extended.vertices[count - 1].position = piece.to;
+            }
+            Some(Drawable::Polyline(extended))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::super::tests::{near, polyline, props};
+    use super::{crossings, extend, trim};
+    use crate::components::{Arc, Circle, Drawable, Line, Point};
+
+    const TOLERANCE: f32 = 1e-3;
+
+    fn line(ax: f32, ay: f32, bx: f32, by: f32) -> Drawable {
+        Drawable::Line(Line::new(Point::new(ax, ay), Point::new(bx, by), props()))
+    }
+
+    #[test]
+    fn lines_cross_where_they_meet() {
+        let met = crossings(
+            &line(0.0, 0.0, 4.0, 0.0),
+            &[line(1.0, -1.0, 1.0, 1.0)],
+            TOLERANCE,
+        );
+        assert_eq!(met.len(), 1);
+        assert!(near(met[0], Point::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn parallel_or_short_lines_dont_cross() {
+        let drawable = line(0.0, 0.0, 4.0, 0.0);
+        assert!(crossings(&drawable, &[line(0.0, 1.0, 4.0, 1.0)], TOLERANCE).is_empty());
+        assert!(crossings(&drawable, &[line(1.0, 1.0, 1.0, 2.0)], TOLERANCE).is_empty());
+    }
+
+    #[test]
+    fn a_tangent_line_touches_a_circle() {
+        let circle = Drawable::Circle(Circle::new(Point::new(0.0, 0.0), 1.0, props()).unwrap());
+        let met = crossings(&circle, &[line(-2.0, 1.0, 2.0, 1.0)], TOLERANCE);
+        assert!(!met.is_empty());
+        assert!(met.iter().all(|p| p.distance(Point::new(0.0, 1.0)) < 0.05));
+    }
+
+    #[test]
+    fn trimming_the_middle_of_a_line_leaves_both_ends() {
+        let cuts = [Point::new(1.0, 0.0), Point::new(3.0, 0.0)];
+        let left = trim(
+            &line(0.0, 0.0, 4.0, 0.0),
+            Point::new(2.0, 0.0),
+            &cuts,
+            TOLERANCE,
+        );
+        assert_eq!(
+            left,
+            Some(vec![line(0.0, 0.0, 1.0, 0.0), line(3.0, 0.0, 4.0, 0.0)])
+        );
+    }
+
+    #[test]
+    fn trimming_an_end_of_a_line_leaves_the_rest() {
+        let cuts = [Point::new(1.0, 0.0)];
+        let left = trim(
+            &line(0.0, 0.0, 4.0, 0.0),
+            Point::new(0.5, 0.0),
+            &cuts,
+            TOLERANCE,
+        );
+        assert_eq!(left, Some(vec![line(1.0, 0.0, 4.0, 0.0)]));
+    }
+
+    #[test]
+    fn nothing_to_trim_back_to() {
+        let drawable = line(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(trim(&drawable, Point::new(2.0, 0.0), &[], TOLERANCE), None);
+        // a cut right on the end doesn't count
+        let at_end = [Point::new(4.0, 0.0)];
+        assert_eq!(
+            trim(&drawable, Point::new(2.0, 0.0), &at_end, TOLERANCE),
+            None
+        );
+    }
+
+    #[test]
+    fn a_circle_opens_up_into_an_arc() {
+        let circle = Drawable::Circle(Circle::new(Point::new(0.0, 0.0), 1.0, props()).unwrap());
+        let cuts = [Point::new(1.0, 0.0), Point::new(-1.0, 0.0)];
+        let left = trim(&circle, Point::new(0.0, 1.0), &cuts, TOLERANCE).unwrap();
+        assert_eq!(left.len(), 1);
+        match &left[0] {
+            Drawable::Arc(arc) => {
+                assert!((arc.sweep() - PI).abs() < 1e-4);
+                assert!(near(
+                    arc.point_at(arc.start_angle + PI / 2.0),
+                    Point::new(0.0, -1.0)
+                ));
+            }
+            other => panic!("expected an arc, got {:?}", other),
+        }
+        // and it needs cutting twice
+        let once = [Point::new(1.0, 0.0)];
+        assert_eq!(trim(&circle, Point::new(0.0, 1.0), &once, TOLERANCE), None);
+    }
+
+    #[test]
+    fn trimming_a_closed_polyline_opens_it_up() {
+        let square = polyline(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], true);
+        let cuts = [Point::new(1.0, 0.0), Point::new(3.0, 0.0)];
+        let drawable = Drawable::Polyline(square);
+        let left = trim(&drawable, Point::new(2.0, 0.0), &cuts, TOLERANCE).unwrap();
+        match &left[..] {
+            [Drawable::Polyline(open)] => {
+                assert!(!open.closed);
+                assert!((open.length() - 14.0).abs() < 1e-3);
+            }
+            other => panic!("expected one polyline, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_line_is_extended_to_the_nearest_edge() {
+        let edges = [line(6.0, -1.0, 6.0, 1.0), line(8.0, -1.0, 8.0, 1.0)];
+        let extended = extend(
+            &line(0.0, 0.0, 4.0, 0.0),
+            Point::new(3.0, 0.0),
+            &edges,
+            TOLERANCE,
+        );
+        assert_eq!(extended, Some(line(0.0, 0.0, 6.0, 0.0)));
+        let back = extend(
+            &line(0.0, 0.0, 4.0, 0.0),
+            Point::new(1.0, 0.0),
+            &edges,
+            TOLERANCE,
+        );
+        assert_eq!(back, None);
+    }
+
+    #[test]
+    fn a_parallel_edge_is_never_reached() {
+        let edges = [line(0.0, 1.0, 10.0, 1.0)];
+        let drawable = line(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(
+            extend(&drawable, Point::new(3.0, 0.0), &edges, TOLERANCE),
+            None
+        );
+    }
+
+    #[test]
+    fn an_arc_is_extended_round_its_circle() {
+        let quarter = Arc::new(Point::new(0.0, 0.0), 1.0, 0.0, PI / 2.0, props());
+        let edges = [line(-2.0, 0.0, 0.0, 0.0)];
+        let pick = Point::from_angle(PI / 3.0);
+        match extend(&Drawable::Arc(quarter), pick, &edges, TOLERANCE) {
+            Some(Drawable::Arc(arc)) => {
+                assert!(near(arc.start_point(), Point::new(1.0, 0.0)));
+                assert!(near(arc.end_point(), Point::new(-1.0, 0.0)));
+            }
+            other => panic!("expected an arc, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Writing the fields of every block reference out as comma separated
+//! values, which spreadsheets read as a bill of materials.
+
+use specs::prelude::*;
+
+use crate::{
+    components::{Drawable, Insert, Preview},
+    resources::Layers,
+};
+
+/// a row for each block reference in the world, with a column for every
+/// field tag any of them has
+pub fn write_attributes(w: &World) -> String {
+    let layers = w.read_resource::<Layers>();
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    let inserts: Vec<&Insert> = (&drawables, !&previews)
+        .join()
+        .filter_map(|(drawable, _)| match drawable {
+            Drawable::Insert(insert) => Some(insert),
+            _ => None,
+        })
+        .collect();
+    let mut tags: Vec<String> = vec![];
+    for field in inserts.iter().flat_map(|insert| insert.fields()) {
+        if !tags.contains(&field.tag) {
+            tags.push(field.tag.clone());
+        }
+    }
+    let mut header = vec!["BLOCK", "COUNT", "X", "Y", "LAYER"];
+    header.extend(tags.iter().map(|tag| tag.as_str()));
+    let mut text = row(&header);
+    for insert in inserts {
+        let layer = layers
+            .get(insert.props.layer)
+            .map_or_else(String::new, |layer| layer.name.clone());
+        let mut values = vec![
+            insert.definition.name.clone(),
+            insert.array.map_or(1, |a| a.columns * a.rows).to_string(),
+            insert.position.x.to_string(),
+            insert.position.y.to_string(),
+            layer,
+        ];
+        let fields = insert.fields();
+        for tag in tags.iter() {
+            let value = fields
+                .iter()
+                .find(|field| field.tag == *tag)
+                .map_or_else(String::new, |field| insert.value(field));
+            values.push(value);
+        }
+        let values: Vec<&str> = values.iter().map(|value| value.as_str()).collect();
+        text.push_str(&row(&values));
+    }
+    text
+}
+
+/// one line of the file, quoting values that need it
+fn row(values: &[&str]) -> String {
+    let quoted: Vec<String> = values
+        .iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", quoted.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use super::{row, write_attributes};
+    use crate::components::{
+        AttDef, Attribute, Color, Drawable, Group, Horizontal, Insert, Justify, Lettering, Point,
+        Preview, Properties, Text, Vertical,
+    };
+    use crate::resources::{Blocks, Layer, Layers, LineType, LineTypes, TextStyle, TextStyles};
+
+    #[test]
+    fn values_with_commas_quotes_or_newlines_are_quoted() {
+        assert_eq!(row(&["plain", "1.5", ""]), "plain,1.5,\n");
+        assert_eq!(row(&["a,b"]), "\"a,b\"\n");
+        assert_eq!(row(&["6\" pipe", "x"]), "\"6\"\" pipe\",x\n");
+        assert_eq!(row(&["two\nlines"]), "\"two\nlines\"\n");
+    }
+
+    #[test]
+    fn every_insert_gets_a_row_under_every_tag() {
+        let mut w = World::new();
+        w.register::<Drawable>();
+        w.register::<Preview>();
+        let mut line_types = LineTypes::new();
+        let line_type = line_types.push(LineType {
+            draw_line: LineType::line_type_continous,
+            period: 0.0,
+        });
+        let mut layers = Layers::new();
+        let layer = layers.push(Layer {
+            name: "parts, misc".to_string(),
+            color: Color::Fixed(7),
+            line_type,
+            hidden: false,
+            frozen: false,
+            locked: false,
+        });
+        let props = Properties::from_layer(layer, layers.get(layer).unwrap());
+        w.insert(layers);
+        let style = TextStyle::new("standard");
+        let lettering = Lettering::from_style(TextStyles::new().push(style.clone()), &style);
+        let field = |tag: &str, default: &str| {
+            let justify = Justify::new(Horizontal::Left, Vertical::Baseline);
+            let at = Point::new(0.0, 0.0);
+            let text = Text::new(tag, at, 1.0, 0.0, justify, lettering, props).unwrap();
+            Drawable::AttDef(AttDef::new(tag, default, false, false, text).unwrap())
+        };
+        let definition = Group::new(
+            "valve",
+            Point::new(0.0, 0.0),
+            vec![field("SIZE", "2\""), field("MAKER", "acme")],
+        );
+        let block = Blocks::new().push(definition.clone());
+        let at = Point::new(3.0, 4.0);
+        let insert = Insert::new(block, definition, at, Point::new(1.0, 1.0), 0.0, props)
+            .unwrap()
+            .with_attributes(vec![Attribute::new("MAKER", "smith, jones")]);
+        w.create_entity()
+            .with(Drawable::Insert(insert.clone()))
+            .build();
+        // previews of an insert being placed aren't counted
+        w.create_entity()
+            .with(Drawable::Insert(insert))
+            .with(Preview)
+            .build();
+        assert_eq!(
+            write_attributes(&w),
+            "BLOCK,COUNT,X,Y,LAYER,SIZE,MAKER\n\
+             valve,1,3,4,\"parts, misc\",\"2\"\"\",\"smith, jones\"\n"
+        );
+    }
+}
@@ -0,0 +1,425 @@
+//! Writing the drawing out as an ascii dxf file other cad programs can open.
+
+use std::{f32::consts::PI, fmt::Display};
+
+use specs::prelude::*;
+
+use crate::{
+    components::{
+        Affine, Color, Drawable, Group, Horizontal, Justify, Lettering, Point, Preview, Properties,
+        Vertex, Vertical,
+    },
+    resources::{Layers, TextStyles},
+};
+
+/// how closely curves dxf has no entity for are followed, relative to their length
+const EXPORT_TOLERANCE: f32 = 1e-4;
+
+/// every drawable in the world as the entities section of a dxf file
+pub fn write_drawing(w: &World) -> String {
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    let visible = (&drawables, !&previews)
+        .join()
+        .map(|(drawable, _)| drawable);
+    write_entities(w, visible)
+}
+
+/// a block definition as a drawing of its own, its base point at the origin
+pub fn write_block(w: &World, block: &Group) -> String {
+    let to_origin = Affine::translation(Point::new(0.0, 0.0) - block.base);
+    let members: Vec<Drawable> = block
+        .members()
+        .iter()
+        .map(|member| member.transformed(&to_origin))
+        .collect();
+    write_entities(w, members.iter())
+}
+
+fn write_entities<'d>(w: &World, drawables: impl Iterator<Item = &'d Drawable>) -> String {
+    let layers = w.read_resource::<Layers>();
+    let text_styles = w.read_resource::<TextStyles>();
+    let mut dxf = Dxf {
+        text: String::new(),
+        layers: &layers,
+        text_styles: &text_styles,
+    };
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "ENTITIES");
+    for drawable in drawables {
+        dxf.drawable(drawable, None);
+    }
+    dxf.pair(0, "ENDSEC");
+    dxf.pair(0, "EOF");
+    dxf.text
+}
+
+struct Dxf<'a> {
+    text: String,
+    layers: &'a Layers,
+    text_styles: &'a TextStyles,
+}
+
+impl<'a> Dxf<'a> {
+    fn pair(&mut self, code: u16, value: impl Display) {
+        self.text.push_str(&format!("{:>3}\n{}\n", code, value));
+    }
+    fn point(&mut self, code: u16, p: Point) {
+        self.pair(code, p.x);
+        self.pair(code + 10, p.y);
+    }
+    /// start an entity, with the layer and color of `props` or of whatever
+    /// group it's part of
+    fn entity(&mut self, kind: &str, props: Option<&Properties>) {
+        self.pair(0, kind);
+        let layer = props
+            .and_then(|props| self.layers.get(props.layer))
+            .map(|layer| layer.name.clone())
+            .unwrap_or_else(|| "0".to_string());
+        self.pair(8, layer);
+        match props.map(|props| props.color) {
+            Some(Color::Fixed(index)) => self.pair(62, index),
+            Some(Color::Full(c)) => {
+                self.pair(420, (c.r as u32) << 16 | (c.g as u32) << 8 | c.b as u32)
+            }
+            None => (),
+        }
+    }
+    fn style(&mut self, lettering: &Lettering) {
+        let name = self
+            .text_styles
+            .get(lettering.style)
+            .map(|style| style.name.clone())
+            .unwrap_or_else(|| "Standard".to_string());
+        self.pair(7, name);
+    }
+    fn polyline(&mut self, vertices: &[Vertex], closed: bool, props: Option<&Properties>) {
+        self.entity("LWPOLYLINE", props);
+        self.pair(90, vertices.len());
+        self.pair(70, if closed { 1 } else { 0 });
+        for vertex in vertices {
+            self.point(10, vertex.position);
+            self.pair(40, vertex.start_width);
+            self.pair(41, vertex.end_width);
+            self.pair(42, vertex.bulge);
+        }
+    }
+    fn drawable(&mut self, drawable: &Drawable, group: Option<&Properties>) {
+        let props = drawable.properties().or(group);
+        match drawable {
+            Drawable::Line(line) => {
+                self.entity("LINE", props);
+                self.point(10, line.start);
+                self.point(11, line.end);
+            }
+            Drawable::Arc(arc) => {
+                self.entity("ARC", props);
+                self.point(10, arc.center);
+                self.pair(40, arc.radius);
+                self.pair(50, arc.start_angle.to_degrees());
+                self.pair(51, arc.end_angle.to_degrees());
+            }
+            Drawable::Circle(circle) => {
+                self.entity("CIRCLE", props);
+                self.point(10, circle.center);
+                self.pair(40, circle.radius);
+            }
+            Drawable::Ellipse(ellipse) => {
+                let start = ellipse.start.rem_euclid(2.0 * PI);
+                self.entity("ELLIPSE", props);
+                self.point(10, ellipse.center);
+                self.point(11, ellipse.major);
+                self.pair(40, ellipse.ratio);
+                self.pair(41, start);
+                self.pair(42, start + ellipse.sweep());
+            }
+            Drawable::Polyline(polyline) => {
+                self.polyline(&polyline.vertices, polyline.closed, props)
+            }
+            Drawable::Parabola(parabola) => {
+                // exactly a clamped degree two spline with the bezier's control points
+                self.entity("SPLINE", props);
+                self.pair(70, 8);
+                self.pair(71, 2);
+                self.pair(72, 6);
+                self.pair(73, 3);
+                self.pair(74, 0);
+                for knot in &[0, 0, 0, 1, 1, 1] {
+                    self.pair(40, knot);
+                }
+                for p in &[parabola.start, parabola.control, parabola.end] {
+                    self.point(10, *p);
+                    self.pair(30, 0.0);
+                }
+            }
+            Drawable::Spline(spline) => {
+                let rational = spline.weights.iter().any(|w| *w != 1.0);
+                self.entity("SPLINE", props);
+                self.pair(70, if rational { 8 | 4 } else { 8 });
+                self.pair(71, spline.degree);
+                self.pair(72, spline.knots.len());
+                self.pair(73, spline.control.len());
+                self.pair(74, 0);
+                for knot in &spline.knots {
+                    self.pair(40, knot);
+                }
+                if rational {
+                    for weight in &spline.weights {
+                        self.pair(41, weight);
+                    }
+                }
+                for p in &spline.control {
+                    self.point(10, *p);
+                    self.pair(30, 0.0);
+                }
+            }
+            Drawable::Catenary(_) => {
+                let segments = drawable.segments(drawable.length() * EXPORT_TOLERANCE);
+                let mut vertices: Vec<Vertex> =
+                    segments.iter().map(|(a, _)| Vertex::new(*a)).collect();
+                vertices.extend(segments.last().map(|(_, b)| Vertex::new(*b)));
+                self.polyline(&vertices, false, props);
+            }
+            Drawable::Text(text) => {
+                let (horizontal, vertical) = alignment(text.justify);
+                self.entity("TEXT", props);
+                self.point(10, text.baseline_start());
+                self.pair(40, text.height);
+                self.pair(1, &text.content);
+                self.pair(50, text.rotation.to_degrees());
+                self.pair(41, text.lettering.width_factor);
+                self.pair(51, text.lettering.oblique.to_degrees());
+                self.style(&text.lettering);
+                let backwards = if text.lettering.backwards { 2 } else { 0 };
+                let upside_down = if text.lettering.upside_down { 4 } else { 0 };
+                self.pair(71, backwards | upside_down);
+                self.pair(72, horizontal);
+                self.point(11, text.position);
+                self.pair(73, vertical);
+            }
+            Drawable::MText(mtext) => {
+                let (horizontal, vertical) = alignment(mtext.justify);
+                // attachment points count left to right then top to bottom
+                let attachment = match vertical {
+                    3 => 1,
+                    2 => 4,
+                    _ => 7,
+                } + horizontal;
+                let mut content = mtext.content.replace('\n', "\\P");
+                if mtext.lettering.width_factor != 1.0 {
+                    content = format!("\\W{};{}", mtext.lettering.width_factor, content);
+                }
+                self.entity("MTEXT", props);
+                self.point(10, mtext.position);
+                self.pair(40, mtext.height);
+                self.pair(41, mtext.width);
+                self.pair(71, attachment);
+                self.pair(72, 1);
+                self.pair(1, content);
+                self.style(&mtext.lettering);
+                self.point(11, Point::from_angle(mtext.rotation));
+            }
+            Drawable::Dimension(dimension) => {
+                // written out as the lines and text it's drawn with
+                for (start, end) in dimension.lines(dimension.look.arrow_size * EXPORT_TOLERANCE) {
+                    self.entity("LINE", props);
+                    self.point(10, start);
+                    self.point(11, end);
+                }
+                if let Some(text) = dimension.text() {
+                    self.drawable(&Drawable::Text(text), props);
+                }
+            }
+            Drawable::Leader(leader) => {
+                for (start, end) in leader.lines(leader.look.arrow_size * EXPORT_TOLERANCE) {
+                    self.entity("LINE", props);
+                    self.point(10, start);
+                    self.point(11, end);
+                }
+                if let Some(note) = &leader.note {
+                    self.drawable(&Drawable::MText(note.clone()), props);
+                }
+            }
+            Drawable::Hatch(hatch) => {
+                // written out as the lines it's drawn with
+                let size: f32 = hatch.loops.iter().map(|boundary| boundary.length()).sum();
+                for (start, end) in hatch.segments(size * EXPORT_TOLERANCE) {
+                    self.entity("LINE", props);
+                    self.point(10, start);
+                    self.point(11, end);
+                }
+            }
+            Drawable::AttDef(field) => {
+                // written out as the tag it's drawn as
+                self.drawable(&Drawable::Text(field.text.clone()), props);
+            }
+            Drawable::Insert(insert) => {
+                // written out as the drawables of each copy
+                for copy in insert.copies() {
+                    self.drawable(&copy, props);
+                }
+            }
+            Drawable::NamedGroup(group) => {
+                for member in group.members() {
+                    self.drawable(member, props);
+                }
+            }
+        }
+    }
+}
+
+/// the codes dxf uses for text alignment along and across the baseline
+fn alignment(justify: Justify) -> (u8, u8) {
+    let horizontal = match justify.horizontal {
+        Horizontal::Left => 0,
+        Horizontal::Center => 1,
+        Horizontal::Right => 2,
+    };
+    let vertical = match justify.vertical {
+        Vertical::Baseline => 0,
+        Vertical::Bottom => 1,
+        Vertical::Middle => 2,
+        Vertical::Top => 3,
+    };
+    (horizontal, vertical)
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use super::{write_block, write_drawing};
+    use crate::components::{
+        Color, Drawable, Group, Line, Parabola, Point, Polyline, Preview, Properties, Vertex,
+    };
+    use crate::resources::{Layer, Layers, LineType, LineTypes, TextStyles};
+
+    /// a world with one layer, and properties on it
+    fn world() -> (World, Properties) {
+        let mut w = World::new();
+        w.register::<Drawable>();
+        w.register::<Preview>();
+        let mut line_types = LineTypes::new();
+        let line_type = line_types.push(LineType {
+            draw_line: LineType::line_type_continous,
+            period: 0.0,
+        });
+        let mut layers = Layers::new();
+        let layer = layers.push(Layer {
+            name: "walls".to_string(),
+            color: Color::Fixed(3),
+            line_type,
+            hidden: false,
+            frozen: false,
+            locked: false,
+        });
+        let props = Properties::from_layer(layer, layers.get(layer).unwrap());
+        w.insert(layers);
+        w.insert(TextStyles::new());
+        (w, props)
+    }
+
+    /// the group codes and values of a dxf file, read back in
+    fn pairs(dxf: &str) -> Vec<(u16, String)> {
+        let lines: Vec<&str> = dxf.lines().collect();
+        assert_eq!(lines.len() % 2, 0, "a code without a value");
+        lines
+            .chunks(2)
+            .map(|pair| (pair[0].trim().parse().unwrap(), pair[1].to_string()))
+            .collect()
+    }
+
+    /// the values of each entity, by group code
+    fn entities(dxf: &str) -> Vec<Vec<(u16, String)>> {
+        let pairs = pairs(dxf);
+        assert_eq!(pairs[..2], [(0, "SECTION".into()), (2, "ENTITIES".into())]);
+        assert_eq!(
+            pairs[pairs.len() - 2..],
+            [(0, "ENDSEC".into()), (0, "EOF".into())]
+        );
+        let mut entities: Vec<Vec<(u16, String)>> = vec![];
+        for pair in &pairs[2..pairs.len() - 2] {
+            if pair.0 == 0 {
+                entities.push(vec![]);
+            }
+            entities
+                .last_mut()
+                .expect("codes before an entity")
+                .push(pair.clone());
+        }
+        entities
+    }
+
+    /// the numbers an entity has for `code`, in order
+    fn numbers(entity: &[(u16, String)], code: u16) -> Vec<f32> {
+        entity
+            .iter()
+            .filter(|(c, _)| *c == code)
+            .map(|(_, value)| value.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lines_are_written_with_their_layer_and_color() {
+        let (mut w, props) = world();
+        let line = Line::new(Point::new(1.0, 2.0), Point::new(3.5, -4.0), props);
+        w.create_entity().with(Drawable::Line(line)).build();
+        // what's only being previewed isn't part of the drawing
+        w.create_entity()
+            .with(Drawable::Line(line))
+            .with(Preview)
+            .build();
+        let entities = entities(&write_drawing(&w));
+        assert_eq!(entities.len(), 1);
+        let line = &entities[0];
+        assert_eq!(line[0], (0, "LINE".into()));
+        assert!(line.contains(&(8, "walls".into())));
+        assert_eq!(numbers(line, 62), vec![3.0]);
+        assert_eq!(numbers(line, 10), vec![1.0]);
+        assert_eq!(numbers(line, 20), vec![2.0]);
+        assert_eq!(numbers(line, 11), vec![3.5]);
+        assert_eq!(numbers(line, 21), vec![-4.0]);
+    }
+
+    #[test]
+    fn parabolas_are_quadratic_splines() {
+        let (mut w, props) = world();
+        let parabola = Parabola::through_points(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 0.0),
+            props,
+        )
+        .unwrap();
+        w.create_entity().with(Drawable::Parabola(parabola)).build();
+        let entities = entities(&write_drawing(&w));
+        let spline = &entities[0];
+        assert_eq!(spline[0], (0, "SPLINE".into()));
+        assert_eq!(numbers(spline, 71), vec![2.0]);
+        assert_eq!(numbers(spline, 40), vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        assert_eq!(numbers(spline, 10), vec![0.0, 1.0, 2.0]);
+        assert_eq!(numbers(spline, 20), vec![0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn blocks_are_written_from_their_base_point() {
+        let (w, props) = world();
+        let vertices = vec![
+            Vertex::new(Point::new(10.0, 10.0)),
+            Vertex::new(Point::new(12.0, 10.0)),
+        ];
+        let polyline = Polyline::new(vertices, true, props);
+        let block = Group::new(
+            "plate",
+            Point::new(10.0, 10.0),
+            vec![Drawable::Polyline(polyline)],
+        );
+        let entities = entities(&write_block(&w, &block));
+        let polyline = &entities[0];
+        assert_eq!(polyline[0], (0, "LWPOLYLINE".into()));
+        assert_eq!(numbers(polyline, 90), vec![2.0]);
+        assert_eq!(numbers(polyline, 70), vec![1.0]);
+        assert_eq!(numbers(polyline, 10), vec![0.0, 2.0]);
+        assert_eq!(numbers(polyline, 20), vec![0.0, 0.0]);
+    }
+}
@@ -15,6 +15,9 @@ use amethyst::{
 mod commands;
 mod common;
 mod components;
+mod csv;
+mod dxf;
+mod pat;
 mod resources;
 mod states;
 mod systems;
@@ -30,9 +33,11 @@ fn main() {
 
 fn run_app() -> amethyst::Result<()> {
     let app_root = amethyst::utils::application_root_dir()?;
-    let mut logger: LoggerConfig = Default::default();
-    logger.log_file = Some(app_root.join("log.txt"));
-    logger.stdout = StdoutLog::Off;
+    let logger = LoggerConfig {
+        log_file: Some(app_root.join("log.txt")),
+        stdout: StdoutLog::Off,
+        ..Default::default()
+    };
     amethyst::start_logger(logger);
 
     let assets_path = app_root.join("assets/");
@@ -55,7 +60,17 @@ fn run_app() -> amethyst::Result<()> {
     };
 
     let game_data = GameDataBuilder::default()
-        // .with(ExampleLinesSystem::new(), "example_lines_system", &[])
+        .with(
+            systems::DependencySystem::default(),
+            "dependency_system",
+            &[],
+        )
+        .with(
+            systems::DrawableRenderSystem,
+            "drawable_render_system",
+            &["dependency_system"],
+        )
+        .with(systems::LogDisplaySystem, "log_display_system", &[])
         .with_bundle(TransformBundle::new())?
         .with_bundle(UiBundle::<StringBindings>::new())?
         .with_bundle(InputBundle::<StringBindings>::new())?
@@ -0,0 +1,134 @@
+//! Reading hatch patterns from the .pat files other cad programs use.
+
+use std::path::Path;
+
+use crate::components::{Pattern, PatternLine, Point};
+
+/// where the hatch patterns are kept, from the application root
+pub const PATTERN_FILE: &str = "assets/hatch.pat";
+
+/// the patterns in the pattern file under `root`
+pub fn load(root: &Path) -> Result<Vec<Pattern>, String> {
+    let path = root.join(PATTERN_FILE);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", PATTERN_FILE, e))
+}
+
+/// Every pattern in `text`. Each starts with a `*NAME, description` line and
+/// has a line for each of its families of parallel lines, giving the angle in
+/// degrees, the origin, the offset from one line to the next and any dashes.
+pub fn parse(text: &str) -> Result<Vec<Pattern>, String> {
+    let mut patterns: Vec<Pattern> = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = match line.find(';') {
+            Some(comment) => &line[..comment],
+            None => line,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('*') {
+            let name = header.split(',').next().unwrap_or("").trim();
+            if name.is_empty() {
+                return Err(format!("line {}: pattern has no name", number + 1));
+            }
+            patterns.push(Pattern {
+                name: name.to_string(),
+                lines: vec![],
+            });
+            continue;
+        }
+        let values: Result<Vec<f32>, _> = line.split(',').map(|v| v.trim().parse()).collect();
+        let values = match values {
+            Ok(values) if values.len() >= 5 && values.iter().all(|v| v.is_finite()) => values,
+            _ => {
+                return Err(format!(
+                    "line {}: expected at least five numbers",
+                    number + 1
+                ))
+            }
+        };
+        let pattern = match patterns.last_mut() {
+            Some(pattern) => pattern,
+            None => return Err(format!("line {}: line before any pattern", number + 1)),
+        };
+        pattern.lines.push(PatternLine {
+            angle: values[0].to_radians(),
+            origin: Point::new(values[1], values[2]),
+            offset: Point::new(values[3], values[4]),
+            dashes: values[5..].to_vec(),
+        });
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn patterns_with_and_without_dashes() {
+        let text = "\
+; patterns for testing
+*ANSI31, ANSI Iron, Brick, Stone masonry
+45, 0,0, 0,.125
+
+*DASH, Dashed lines ; one family
+0, 0,0, 0,.125, .125,-.0625
+";
+        let patterns = parse(text).unwrap();
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[0].name, "ANSI31");
+        assert_eq!(patterns[0].lines.len(), 1);
+        let line = &patterns[0].lines[0];
+        assert!((line.angle - 45f32.to_radians()).abs() < 1e-6);
+        assert_eq!((line.offset.x, line.offset.y), (0.0, 0.125));
+        assert!(line.dashes.is_empty());
+        assert_eq!(patterns[1].name, "DASH");
+        assert_eq!(patterns[1].lines[0].dashes, vec![0.125, -0.0625]);
+    }
+
+    #[test]
+    fn a_header_on_its_own_is_an_empty_pattern() {
+        let patterns = parse("*SOLID\n").unwrap();
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].name, "SOLID");
+        assert!(patterns[0].lines.is_empty());
+    }
+
+    #[test]
+    fn nothing_but_comments_is_no_patterns() {
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse(";; nothing here\n\n   ; or here\n")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn too_few_numbers_is_an_error_naming_the_line() {
+        let error = parse("*A, a\n45, 0,0, 0\n").unwrap_err();
+        assert!(error.starts_with("line 2"), "{}", error);
+    }
+
+    #[test]
+    fn words_or_non_finite_numbers_are_errors() {
+        assert!(parse("*A, a\n45, 0,zero, 0,1\n").is_err());
+        assert!(parse("*A, a\n45, 0,0, 0,inf\n").is_err());
+        assert!(parse("*A, a\nNaN, 0,0, 0,1\n").is_err());
+        assert!(parse("*A, a\n45,, 0,0, 0,1\n").is_err());
+    }
+
+    #[test]
+    fn lines_before_any_header_are_errors() {
+        let error = parse("45, 0,0, 0,.125\n").unwrap_err();
+        assert!(error.starts_with("line 1"), "{}", error);
+    }
+
+    #[test]
+    fn a_header_without_a_name_is_an_error() {
+        assert!(parse("*, no name\n").is_err());
+        assert!(parse("*\n").is_err());
+    }
+}
@@ -1,7 +1,10 @@
 use crate::common::{
-    GenerationID, GenerationVec, ScreenSize, ScreenTranslation, WorldPos, WorldScaleFactor,
+    GenerationID, GenerationVec, ScreenPos, ScreenSize, ScreenTranslation, WorldPos,
+    WorldScaleFactor,
+};
+use crate::components::{
+    Arrowhead, Chamfer, Color, DimLook, Group, LeaderLook, Lettering, Pattern, Point, Properties,
 };
-use crate::components::Color;
 use amethyst::{prelude::*, renderer::camera::Projection};
 
 use specs::prelude::*;
@@ -11,6 +14,15 @@ pub type Layers = GenerationVec<Layer>;
 
 pub type LineTypes = GenerationVec<LineType>;
 
+pub type TextStyles = GenerationVec<TextStyle>;
+
+pub type DimStyles = GenerationVec<DimStyle>;
+
+pub type LeaderStyles = GenerationVec<LeaderStyle>;
+
+/// block definitions, which inserts draw copies of
+pub type Blocks = GenerationVec<Group>;
+
 #[derive(Debug, PartialEq)]
 pub struct Layer {
     pub name: String,
@@ -21,9 +33,244 @@ pub struct Layer {
     pub locked: bool,
 }
 
-#[derive(Debug, PartialEq)]
+/// the layer new drawables are placed on
+pub struct ActiveLayer {
+    pub id: GenerationID<Layer>,
+}
+
+/// A named way of lettering text. Texts keep a copy of how their style
+/// looks, which is brought up to date whenever the style is changed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub name: String,
+    /// the font file the style names, every style being drawn with the
+    /// built in stroke font for now
+    pub font: String,
+    /// the height of all text in this style, or zero to ask for each text
+    pub height: f32,
+    pub width_factor: f32,
+    /// how far characters lean forwards, in radians
+    pub oblique: f32,
+    pub backwards: bool,
+    pub upside_down: bool,
+}
+
+impl TextStyle {
+    pub fn new(name: &str) -> Self {
+        TextStyle {
+            name: name.to_string(),
+            font: "txt".to_string(),
+            height: 0.0,
+            width_factor: 1.0,
+            oblique: 0.0,
+            backwards: false,
+            upside_down: false,
+        }
+    }
+    /// the height text in this style is always drawn at, if it fixes one
+    pub fn fixed_height(&self) -> Option<f32> {
+        if self.height > 0.0 {
+            Some(self.height)
+        } else {
+            None
+        }
+    }
+}
+
+/// the text style new text is written in
+pub struct ActiveTextStyle {
+    pub id: GenerationID<TextStyle>,
+}
+
+/// How dimensions are drawn. Like text with its style, dimensions keep a
+/// copy of their style's settings, refreshed whenever the style changes.
+/// Sizes are in drawing units, the defaults reading well at the starting zoom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DimStyle {
+    pub name: String,
+    pub arrow_size: f32,
+    pub text_height: f32,
+    /// space left between the dimension line and its text
+    pub text_gap: f32,
+    /// space left between a measured point and its extension line
+    pub extension_offset: f32,
+    /// how far extension lines reach past the dimension line
+    pub extension_beyond: f32,
+    pub text_style: GenerationID<TextStyle>,
+}
+
+impl DimStyle {
+    pub fn new(name: &str, text_style: GenerationID<TextStyle>) -> Self {
+        DimStyle {
+            name: name.to_string(),
+            arrow_size: 10.0,
+            text_height: 10.0,
+            text_gap: 2.5,
+            extension_offset: 2.5,
+            extension_beyond: 5.0,
+            text_style,
+        }
+    }
+}
+
+/// the dimension style new dimensions are drawn in
+pub struct ActiveDimStyle {
+    pub id: GenerationID<DimStyle>,
+}
+
+/// How leaders are drawn, copied onto each leader like dimension styles are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeaderStyle {
+    pub name: String,
+    pub arrowhead: Arrowhead,
+    pub arrow_size: f32,
+    /// length of the level line between the arms and the note
+    pub landing: f32,
+    pub text_height: f32,
+    /// space left between the end of the landing and the note
+    pub text_gap: f32,
+    pub text_style: GenerationID<TextStyle>,
+}
+
+impl LeaderStyle {
+    pub fn new(name: &str, text_style: GenerationID<TextStyle>) -> Self {
+        LeaderStyle {
+            name: name.to_string(),
+            arrowhead: Arrowhead::ClosedFilled,
+            arrow_size: 10.0,
+            landing: 10.0,
+            text_height: 10.0,
+            text_gap: 2.5,
+            text_style,
+        }
+    }
+}
+
+/// the leader style new leaders are drawn in
+pub struct ActiveLeaderStyle {
+    pub id: GenerationID<LeaderStyle>,
+}
+
+/// the hatch patterns loaded at startup
+#[derive(Default)]
+pub struct HatchPatterns {
+    pub patterns: Vec<Pattern>,
+}
+
+impl HatchPatterns {
+    pub fn find(&self, name: &str) -> Option<&Pattern> {
+        self.patterns
+            .iter()
+            .find(|pattern| pattern.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitFormat {
+    Decimal,
+    /// whole units and a fraction
+    Fractional,
+    /// feet and fractional inches, a drawing unit being an inch
+    Architectural,
+}
+
+/// how lengths in the drawing are written out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    pub format: UnitFormat,
+    /// decimal places, or for fractions the power of two they're rounded to
+    pub precision: usize,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units {
+            format: UnitFormat::Decimal,
+            precision: 2,
+        }
+    }
+}
+
+impl Units {
+    pub fn format(&self, value: f32) -> String {
+        match self.format {
+            UnitFormat::Decimal => {
+                let text = format!("{:.*}", self.precision, value);
+                // small negatives round to zero, which has no sign
+                if text.parse::<f32>() == Ok(0.0) {
+                    format!("{:.*}", self.precision, 0.0)
+                } else {
+                    text
+                }
+            }
+            UnitFormat::Fractional => self.fraction(value),
+            UnitFormat::Architectural => {
+                let denominator = (1u64 << self.precision.min(8)) as f32;
+                let inches = (value.abs() * denominator).round() / denominator;
+                let sign = if value < 0.0 && inches > 0.0 { "-" } else { "" };
+                let feet = (inches / 12.0).floor();
+                format!(
+                    "{}{}'-{}\"",
+                    sign,
+                    feet,
+                    self.fraction(inches - feet * 12.0)
+                )
+            }
+        }
+    }
+    /// an angle given in radians, written in degrees
+    pub fn format_angle(&self, radians: f32) -> String {
+        format!("{:.*}\u{b0}", self.precision, radians.to_degrees())
+    }
+    /// `value` as whole units and a reduced fraction
+    fn fraction(&self, value: f32) -> String {
+        let denominator = 1u64 << self.precision.min(8);
+        let parts = (value.abs() * denominator as f32).round() as u64;
+        let sign = if value < 0.0 && parts > 0 { "-" } else { "" };
+        let (whole, mut numerator) = (parts / denominator, parts % denominator);
+        let mut denominator = denominator;
+        while numerator > 0 && numerator % 2 == 0 {
+            numerator /= 2;
+            denominator /= 2;
+        }
+        match (whole, numerator) {
+            (whole, 0) => format!("{}{}", sign, whole),
+            (0, numerator) => format!("{}{}/{}", sign, numerator, denominator),
+            (whole, numerator) => format!("{}{} {}/{}", sign, whole, numerator, denominator),
+        }
+    }
+}
+
+/// where the last line or arc drawn ended and which way it was heading,
+/// so the next one can carry on from there
+#[derive(Default)]
+pub struct LastSegment {
+    pub tangent: Option<(Point, Point)>,
+}
+
+/// how the last chamfer was cut, so the next can be cut the same way
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChamferSettings {
+    pub cut: Chamfer,
+    /// whether the lines are trimmed back to the cut
+    pub trim: bool,
+}
+
+impl Default for ChamferSettings {
+    fn default() -> Self {
+        ChamferSettings {
+            cut: Chamfer::Distances(1.0, 1.0),
+            trim: true,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct LineType {
     pub draw_line: fn(f32, f32) -> bool,
+    /// how far along the pattern repeats at a scale of one, zero when it
+    /// never breaks
+    pub period: f32,
 }
 
 impl LineType {
@@ -64,7 +311,102 @@ impl CommandList {
     }
 }
 
-pub type CommandFunc = Box<fn(&mut World, &[InputDesc]) -> SimpleTrans>;
+pub type CommandFunc = Box<fn(&mut World, &CommandContext, &[CapturedInput]) -> CommandResult>;
+
+/// what a command needs to know about the drawing besides its own inputs
+#[derive(Clone)]
+pub struct CommandContext {
+    /// properties for new drawables, on the active layer
+    pub properties: Properties,
+    /// lettering for new text, in the active text style
+    pub lettering: Lettering,
+    /// the height the active text style fixes all its text at, if any
+    pub text_height: Option<f32>,
+    /// how new dimensions look, in the active dimension style
+    pub dim_look: DimLook,
+    /// how new leaders look, in the active leader style
+    pub leader_look: LeaderLook,
+    pub units: Units,
+    pub view: ViewInfo,
+}
+
+impl CommandContext {
+    pub fn from_world(w: &World) -> Self {
+        let active = w.read_resource::<ActiveLayer>();
+        let layers = w.read_resource::<Layers>();
+        let layer = layers.get(active.id).expect("active layer does not exist");
+        let active_style = w.read_resource::<ActiveTextStyle>();
+        let styles = w.read_resource::<TextStyles>();
+        let style = styles
+            .get(active_style.id)
+            .expect("active text style does not exist");
+        CommandContext {
+            properties: Properties::from_layer(active.id, layer),
+            lettering: Lettering::from_style(active_style.id, style),
+            text_height: style.fixed_height(),
+            dim_look: dim_look(w),
+            leader_look: leader_look(w),
+            units: *w.read_resource::<Units>(),
+            view: (*w.read_resource::<ViewInfo>()).clone(),
+        }
+    }
+}
+
+/// the block definition called `name`, if there is one
+pub fn block_named(w: &World, name: &str) -> Option<(GenerationID<Group>, Group)> {
+    w.read_resource::<Blocks>()
+        .iter()
+        .find(|(_, block)| block.name.eq_ignore_ascii_case(name))
+        .map(|(id, block)| (id, block.clone()))
+}
+
+/// lettering in the text style `id`, or the active one if it's gone
+pub fn lettering_in(w: &World, id: GenerationID<TextStyle>) -> Lettering {
+    let text_styles = w.read_resource::<TextStyles>();
+    match text_styles.get(id) {
+        Some(text_style) => Lettering::from_style(id, text_style),
+        None => {
+            let id = w.read_resource::<ActiveTextStyle>().id;
+            let text_style = text_styles
+                .get(id)
+                .expect("active text style does not exist");
+            Lettering::from_style(id, text_style)
+        }
+    }
+}
+
+/// how new dimensions look, in the active dimension style and lettered
+/// in whatever text style it uses
+fn dim_look(w: &World) -> DimLook {
+    let active = w.read_resource::<ActiveDimStyle>();
+    let dim_styles = w.read_resource::<DimStyles>();
+    let style = dim_styles
+        .get(active.id)
+        .expect("active dimension style does not exist");
+    DimLook::from_style(active.id, style, lettering_in(w, style.text_style))
+}
+
+/// how new leaders look, in the active leader style
+fn leader_look(w: &World) -> LeaderLook {
+    let active = w.read_resource::<ActiveLeaderStyle>();
+    let leader_styles = w.read_resource::<LeaderStyles>();
+    let style = leader_styles
+        .get(active.id)
+        .expect("active leader style does not exist");
+    LeaderLook::from_style(active.id, style, lettering_in(w, style.text_style))
+}
+
+/// how running a command turned out
+pub enum CommandResult {
+    Done,
+    /// run the command again, starting with these inputs already collected
+    Repeat(Vec<CapturedInput>),
+    /// collect one more input with this prompt, then run the command again
+    /// with everything collected so far
+    More(InputDesc, String),
+    Failed(String),
+    Quit,
+}
 
 // Box<dyn Command>
 // trait CommandFunc {
@@ -74,6 +416,8 @@ pub type CommandFunc = Box<fn(&mut World, &[InputDesc]) -> SimpleTrans>;
 pub struct CommandDescBuilder {
     name: Option<String>,
     inputs: Vec<InputDesc>,
+    prompts: Vec<String>,
+    options: Vec<(String, CommandDesc)>,
     exec: Option<CommandFunc>,
 }
 
@@ -82,26 +426,42 @@ impl CommandDescBuilder {
         CommandDescBuilder {
             name: Some(name.to_string()),
             inputs: vec![],
+            prompts: vec![],
+            options: vec![],
             exec: None,
         }
     }
     pub fn with_input(mut self, input: InputDesc) -> Self {
+        self.prompts.push(input.prompt().to_string());
         self.inputs.push(input);
         self
     }
+    /// add an input which asks the user for it with a specific prompt
+    pub fn with_prompted_input(mut self, input: InputDesc, prompt: &str) -> Self {
+        self.prompts.push(prompt.to_string());
+        self.inputs.push(input);
+        self
+    }
+    /// another way of running the command, chosen by typing `keyword`
+    /// while its inputs are being collected
+    pub fn with_option(mut self, keyword: &str, option: CommandDesc) -> Self {
+        self.options.push((keyword.to_string(), option));
+        self
+    }
     pub fn with_function(mut self, func: CommandFunc) -> Self {
         self.exec = Some(func);
         self
     }
     pub fn build(self) -> CommandDesc {
-        if self.name.is_none() || self.exec.is_none() {
-            panic!("tried to build an incomplete command");
-        } else {
-            CommandDesc {
-                name: self.name.unwrap(),
+        match (self.name, self.exec) {
+            (Some(name), Some(exec)) => CommandDesc {
+                name,
                 inputs: self.inputs,
-                exec: self.exec.unwrap(),
-            }
+                prompts: self.prompts,
+                options: self.options,
+                exec,
+            },
+            _ => panic!("tried to build an incomplete command"),
         }
     }
 }
@@ -110,20 +470,57 @@ impl CommandDescBuilder {
 pub struct CommandDesc {
     pub name: String,
     pub inputs: Vec<InputDesc>,
+    pub prompts: Vec<String>,
+    pub options: Vec<(String, CommandDesc)>,
     pub exec: CommandFunc,
 }
 
+impl CommandDesc {
+    pub fn option(&self, keyword: &str) -> Option<&CommandDesc> {
+        self.options
+            .iter()
+            .find(|(key, _)| key == keyword)
+            .map(|(_, option)| option)
+    }
+}
+
 #[derive(Clone)]
 pub enum InputDesc {
     Point,
     Select,
     Multiselect,
+    Distance,
+    /// only typed words, clicks are ignored
+    Keyword,
+    /// a line of typed text, spaces and all
+    Text,
+}
+
+impl InputDesc {
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            InputDesc::Point => "pick a point",
+            InputDesc::Select => "select an object",
+            InputDesc::Multiselect => "select objects, enter when done",
+            InputDesc::Distance => "enter or pick a distance",
+            InputDesc::Keyword => "enter an option",
+            InputDesc::Text => "enter text",
+        }
+    }
 }
 
 pub enum CapturedInput {
     Point(f64, f64),
-    Select(Entity),
+    /// the selected entity and where it was picked
+    Select(Entity, f64, f64),
     Multiselect(Vec<Entity>),
+    Distance(f64),
+    /// anything typed which isn't a value of the kind asked for
+    Keyword(String),
+    /// typed text exactly as entered
+    Text(String),
+    /// enter pressed with nothing typed, for inputs asked for with `More`
+    Confirm,
 }
 
 // impl From<InputDesc> for CapturedInput {
@@ -132,6 +529,7 @@ pub enum CapturedInput {
 //     }
 // }
 
+#[derive(Clone)]
 pub struct ViewInfo {
     zoom_level: WorldScaleFactor,
     depth: f32,
@@ -175,6 +573,28 @@ impl ViewInfo {
 
         Projection::orthographic(left, right, bottom, top, z_near, z_far)
     }
+    /// size of one screen pixel in world units
+    pub fn pixel_size(&self) -> f32 {
+        self.zoom_level.factor
+    }
+    /// how far displayed curves may stray from their true shape
+    pub fn tolerance(&self) -> f32 {
+        self.pixel_size() * 0.5
+    }
+    /// the corners of the part of the drawing on screen, lowest first
+    pub fn bounds(&self) -> (Point, Point) {
+        let half = Point::new(self.width() / 2.0, self.height() / 2.0);
+        let center = Point::new(self.origin.x as f32, self.origin.y as f32);
+        (center - half, center + half)
+    }
+    pub fn screen_to_world(&self, pos: ScreenPos) -> WorldPos {
+        let factor = self.zoom_level.factor as f64;
+        WorldPos {
+            x: self.origin.x + (pos.x - self.screen.width / 2.0) * factor,
+            y: self.origin.y - (pos.y - self.screen.height / 2.0) * factor,
+            z: self.origin.z,
+        }
+    }
     pub fn pan(&mut self, delta: ScreenTranslation) {
         self.origin = self.origin - (self.zoom_level * delta);
     }
@@ -188,11 +608,11 @@ impl ViewInfo {
     }
 
     // pub fn reset_camera(&self, cam: &mut Camera) {
-        // world.exec(|(mut cameras, mut view_info, active_camera): (WriteStorage<Camera>, WriteExpect<ViewInfo>, ReadStorage<ActiveCamera>)| {
-            // for (cam, _) in (&mut cameras, &active_camera).join() {
-        // cam.set_projection(self.projection());
-            // }
-        // });
+    // world.exec(|(mut cameras, mut view_info, active_camera): (WriteStorage<Camera>, WriteExpect<ViewInfo>, ReadStorage<ActiveCamera>)| {
+    // for (cam, _) in (&mut cameras, &active_camera).join() {
+    // cam.set_projection(self.projection());
+    // }
+    // });
     // }
     pub fn resize(&mut self, width: f64, height: f64) {
         self.screen.width = width;
@@ -209,4 +629,81 @@ impl LogHistory {
     pub fn new() -> Self {
         Self::default()
     }
-}
\ No newline at end of file
+    pub fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+    pub fn last(&self) -> Option<&str> {
+        self.lines.last().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UnitFormat, Units};
+
+    fn units(format: UnitFormat, precision: usize) -> Units {
+        Units { format, precision }
+    }
+
+    #[test]
+    fn decimals_round_to_the_precision() {
+        let decimal = units(UnitFormat::Decimal, 2);
+        assert_eq!(decimal.format(1.005), "1.00");
+        assert_eq!(decimal.format(2.5), "2.50");
+        assert_eq!(decimal.format(-2.5), "-2.50");
+        assert_eq!(decimal.format(0.0), "0.00");
+        assert_eq!(decimal.format(-0.001), "0.00");
+    }
+
+    #[test]
+    fn fractions_are_reduced() {
+        let sixteenths = units(UnitFormat::Fractional, 4);
+        assert_eq!(sixteenths.format(2.5), "2 1/2");
+        assert_eq!(sixteenths.format(0.0625), "1/16");
+        assert_eq!(sixteenths.format(0.375), "3/8");
+        assert_eq!(sixteenths.format(3.0), "3");
+    }
+
+    #[test]
+    fn fractions_round_up_into_the_next_whole() {
+        let sixteenths = units(UnitFormat::Fractional, 4);
+        assert_eq!(sixteenths.format(1.99), "2");
+        assert_eq!(sixteenths.format(0.99), "1");
+    }
+
+    #[test]
+    fn negative_fractions_and_zero() {
+        let sixteenths = units(UnitFormat::Fractional, 4);
+        assert_eq!(sixteenths.format(-2.5), "-2 1/2");
+        assert_eq!(sixteenths.format(-0.25), "-1/4");
+        assert_eq!(sixteenths.format(0.0), "0");
+        assert_eq!(sixteenths.format(-0.001), "0");
+    }
+
+    #[test]
+    fn feet_and_inches() {
+        let sixteenths = units(UnitFormat::Architectural, 4);
+        assert_eq!(sixteenths.format(30.5), "2'-6 1/2\"");
+        assert_eq!(sixteenths.format(12.0), "1'-0\"");
+        assert_eq!(sixteenths.format(0.75), "0'-3/4\"");
+    }
+
+    #[test]
+    fn inches_rounding_up_carry_into_feet() {
+        // 11 63/64" is nearer 12" than 11 15/16" in sixteenths
+        let sixteenths = units(UnitFormat::Architectural, 4);
+        assert_eq!(sixteenths.format(11.0 + 63.0 / 64.0), "1'-0\"");
+        let sixty_fourths = units(UnitFormat::Architectural, 6);
+        assert_eq!(sixty_fourths.format(11.0 + 63.0 / 64.0), "0'-11 63/64\"");
+        assert_eq!(sixteenths.format(23.99), "2'-0\"");
+    }
+
+    #[test]
+    fn negative_feet_and_inches_and_zero() {
+        let sixteenths = units(UnitFormat::Architectural, 4);
+        assert_eq!(sixteenths.format(-30.5), "-2'-6 1/2\"");
+        assert_eq!(sixteenths.format(-6.0), "-0'-6\"");
+        assert_eq!(sixteenths.format(0.0), "0'-0\"");
+        assert_eq!(sixteenths.format(-0.001), "0'-0\"");
+    }
+}
@@ -9,7 +9,10 @@ use amethyst::{
 use std::fmt::Write;
 use winit::WindowEvent;
 
-use crate::{resources::CommandList, states::InputCollectionState};
+use crate::{
+    resources::{CommandContext, CommandList, LogHistory},
+    states::{finish_command, InputCollectionState},
+};
 
 pub struct CommandEntryState {
     pub command: String,
@@ -73,7 +76,7 @@ impl SimpleState for CommandEntryState {
         let w = data.world;
         match &ev {
             StateEvent::Window(event) => {
-                if is_close_requested(&event) {
+                if is_close_requested(event) {
                     return Trans::Quit;
                 }
                 if let winit::Event::WindowEvent {
@@ -171,20 +174,21 @@ fn instantiate_command(w: &mut World, name: &str) -> SimpleTrans {
         let commands = w.read_resource::<CommandList>();
         command = commands.get(name).cloned();
     }
-
-    if let Some(command) = command {
-        if command.inputs.is_empty() {
-            return (command.exec)(w, &command.inputs);
-        } else {
-            let new_state = InputCollectionState {
-                command,
-                current_input: 0,
-                found_inputs: vec![],
-            };
-            return Trans::Switch(Box::new(new_state));
+    {
+        let mut history = w.write_resource::<LogHistory>();
+        match &command {
+            Some(command) => history.push(command.name.clone()),
+            None => history.push(format!("unknown command: {}", name)),
         }
     }
 
-    Trans::Pop
+    match command {
+        Some(command) if command.inputs.is_empty() => {
+            let context = CommandContext::from_world(w);
+            let result = (command.exec)(w, &context, &[]);
+            finish_command(w, &command, result)
+        }
+        Some(command) => Trans::Switch(Box::new(InputCollectionState::new(command, vec![]))),
+        None => Trans::Pop,
+    }
 }
-
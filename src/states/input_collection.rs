@@ -1,19 +1,289 @@
-use amethyst::prelude::*;
+use amethyst::{
+    assets::{AssetStorage, Loader},
+    ecs::prelude::*,
+    input::{is_close_requested, InputHandler, StringBindings, VirtualKeyCode},
+    prelude::*,
+    ui::{get_default_font, Anchor, FontAsset, UiText, UiTransform},
+    window::ScreenDimensions,
+};
+use winit::WindowEvent;
 
-use crate::resources::{CapturedInput, CommandDesc};
+use crate::{
+    common::{is_confirmation, ScreenPos, WorldPos},
+    components::{Drawable, Point, Preview, Selected},
+    resources::{
+        CapturedInput, CommandContext, CommandDesc, CommandResult, InputDesc, Layers, LogHistory,
+        ViewInfo,
+    },
+};
+
+/// how close a click has to be to a drawable to select it, in pixels
+const PICK_APERTURE: f32 = 5.0;
+
+/// how close a click has to be to a snap point to land on it, in pixels
+const SNAP_APERTURE: f32 = 8.0;
 
 pub struct InputCollectionState {
     pub command: CommandDesc,
     pub current_input: usize,
     pub found_inputs: Vec<CapturedInput>,
+    /// an input the command asked for after running, beyond those it declared
+    pub extra: Option<(InputDesc, String)>,
+    pub cursor: (f64, f64),
+    pub selection: Vec<Entity>,
+    /// text typed in place of clicking
+    pub entry: String,
+    /// first end of a distance being picked with two clicks
+    pub distance_from: Option<(f64, f64)>,
+    pub prompt_ui: Option<Entity>,
+}
+
+impl InputCollectionState {
+    /// collect the inputs for `command` which come after those already found
+    pub fn new(command: CommandDesc, found_inputs: Vec<CapturedInput>) -> Self {
+        InputCollectionState {
+            command,
+            current_input: found_inputs.len(),
+            found_inputs,
+            extra: None,
+            cursor: (0.0, 0.0),
+            selection: vec![],
+            entry: String::new(),
+            distance_from: None,
+            prompt_ui: None,
+        }
+    }
+    /// the kind of input being collected right now
+    fn current(&self) -> Option<&InputDesc> {
+        self.command
+            .inputs
+            .get(self.current_input)
+            .or_else(|| self.extra.as_ref().map(|(desc, _)| desc))
+    }
+    /// whether typing goes into text, where spaces don't confirm
+    fn taking_text(&self) -> bool {
+        matches!(self.current(), Some(InputDesc::Text))
+    }
+    fn prompt_text(&self) -> String {
+        let prompt = match self.command.prompts.get(self.current_input) {
+            Some(prompt) => prompt,
+            None => match &self.extra {
+                Some((_, prompt)) => prompt,
+                None => return self.command.name.clone(),
+            },
+        };
+        if self.command.options.is_empty() {
+            format!("{}: {}> {}", self.command.name, prompt, self.entry)
+        } else {
+            let keywords: Vec<&str> = self
+                .command
+                .options
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .collect();
+            format!(
+                "{}: {} [{}]> {}",
+                self.command.name,
+                prompt,
+                keywords.join("/"),
+                self.entry
+            )
+        }
+    }
+    fn update_prompt(&self, w: &mut World) {
+        if let Some(ui) = self.prompt_ui {
+            let prompt = self.prompt_text();
+            w.exec(|mut ui_text: WriteStorage<UiText>| {
+                let text = ui_text.get_mut(ui).expect("failed to find UiText");
+                text.text = prompt;
+            });
+        }
+    }
+    fn cursor_world(&self, w: &World) -> WorldPos {
+        let (x, y) = self.cursor;
+        w.read_resource::<ViewInfo>()
+            .screen_to_world(ScreenPos { x, y })
+    }
+    /// record one input, running the command once all of them are in
+    fn accept(&mut self, w: &mut World, input: CapturedInput) -> SimpleTrans {
+        self.found_inputs.push(input);
+        self.current_input += 1;
+        self.entry.clear();
+        self.distance_from = None;
+        if self.current_input >= self.command.inputs.len() {
+            clear_preview(w);
+            let context = CommandContext::from_world(w);
+            let result = (self.command.exec)(w, &context, &self.found_inputs);
+            if let CommandResult::More(desc, prompt) = result {
+                self.extra = Some((desc, prompt));
+                self.update_prompt(w);
+                return Trans::None;
+            }
+            return finish_command(w, &self.command, result);
+        }
+        self.update_prompt(w);
+        Trans::None
+    }
+    /// the most recent point collected, which relative coordinates and
+    /// picked distances are measured from
+    fn last_point(&self) -> Option<(f64, f64)> {
+        self.found_inputs
+            .iter()
+            .rev()
+            .find_map(|input| match input {
+                CapturedInput::Point(x, y) => Some((*x, *y)),
+                _ => None,
+            })
+    }
+    /// turn typed text into the kind of value being asked for, falling back
+    /// to passing it along as a keyword
+    fn parse_entry(&self, text: &str) -> CapturedInput {
+        let keyword = CapturedInput::Keyword(text.to_lowercase());
+        match self.current() {
+            Some(InputDesc::Point) => {
+                let (relative, coords) = match text.strip_prefix('@') {
+                    Some(rest) => (true, rest),
+                    None => (false, text),
+                };
+                let mut parts = coords.split(',').map(|part| part.trim().parse::<f64>());
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(Ok(x)), Some(Ok(y)), None) => {
+                        let (ox, oy) = if relative {
+                            self.last_point().unwrap_or((0.0, 0.0))
+                        } else {
+                            (0.0, 0.0)
+                        };
+                        CapturedInput::Point(ox + x, oy + y)
+                    }
+                    _ => keyword,
+                }
+            }
+            Some(InputDesc::Distance) => match text.trim().parse::<f64>() {
+                Ok(distance) => CapturedInput::Distance(distance),
+                Err(_) => keyword,
+            },
+            Some(InputDesc::Text) => CapturedInput::Text(text.to_string()),
+            _ => keyword,
+        }
+    }
+    fn click(&mut self, w: &mut World) -> SimpleTrans {
+        let pos = self.cursor_world(w);
+        let at = Point::new(pos.x as f32, pos.y as f32);
+        // points and distances go to the nearest snap point if there is one
+        let (x, y) = match snap_point(w, at) {
+            Some(p) => (p.x as f64, p.y as f64),
+            None => (pos.x, pos.y),
+        };
+        match self.current() {
+            Some(InputDesc::Point) => self.accept(w, CapturedInput::Point(x, y)),
+            Some(InputDesc::Select) => match pick_drawable(w, at) {
+                Some(entity) => {
+                    mark_selected(w, entity, true);
+                    self.accept(w, CapturedInput::Select(entity, pos.x, pos.y))
+                }
+                None => Trans::None,
+            },
+            Some(InputDesc::Distance) => match self.distance_from.or_else(|| self.last_point()) {
+                Some((fx, fy)) => {
+                    let distance = (x - fx).hypot(y - fy);
+                    self.accept(w, CapturedInput::Distance(distance))
+                }
+                None => {
+                    self.distance_from = Some((x, y));
+                    Trans::None
+                }
+            },
+            Some(InputDesc::Multiselect) => {
+                if let Some(entity) = pick_drawable(w, at) {
+                    if let Some(index) = self.selection.iter().position(|e| *e == entity) {
+                        self.selection.remove(index);
+                        mark_selected(w, entity, false);
+                    } else {
+                        self.selection.push(entity);
+                        mark_selected(w, entity, true);
+                    }
+                }
+                Trans::None
+            }
+            Some(InputDesc::Keyword) | Some(InputDesc::Text) | None => Trans::None,
+        }
+    }
+    fn confirm(&mut self, w: &mut World) -> SimpleTrans {
+        if !self.entry.is_empty() {
+            let entry = std::mem::take(&mut self.entry);
+            let option = if self.taking_text() {
+                None
+            } else {
+                self.command.option(&entry.to_lowercase()).cloned()
+            };
+            if let Some(option) = option {
+                // start over collecting the inputs the chosen option asks for
+                w.write_storage::<Selected>().clear();
+                *self = InputCollectionState {
+                    prompt_ui: self.prompt_ui,
+                    cursor: self.cursor,
+                    ..InputCollectionState::new(option, vec![])
+                };
+                self.update_prompt(w);
+                return Trans::None;
+            }
+            let input = self.parse_entry(&entry);
+            return self.accept(w, input);
+        }
+        match self.current() {
+            Some(InputDesc::Multiselect) => {
+                let selection = self.selection.drain(..).collect();
+                self.accept(w, CapturedInput::Multiselect(selection))
+            }
+            Some(_) if self.extra.is_some() => self.accept(w, CapturedInput::Confirm),
+            _ => Trans::Pop,
+        }
+    }
 }
 
 impl SimpleState for InputCollectionState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-        
+        let world = data.world;
+        {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            let hidpi = world.read_resource::<ScreenDimensions>().hidpi_factor();
+            if let Some((x, y)) = input.mouse_position() {
+                self.cursor = (x as f64 / hidpi, y as f64 / hidpi);
+            }
+        }
+        let font = {
+            let loader = world.read_resource::<Loader>();
+            let font_store = world.read_resource::<AssetStorage<FontAsset>>();
+            get_default_font(&loader, &font_store)
+        };
+        let prompt_entity = world
+            .create_entity()
+            .with(UiText::new(
+                font,
+                self.prompt_text(),
+                [0.5, 0.5, 0.5, 1.0],
+                20.0,
+            ))
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::BottomMiddle,
+                Anchor::BottomMiddle,
+                0.0,
+                0.0,
+                0.0,
+                400.0,
+                40.0,
+            ))
+            .build();
+        self.prompt_ui = Some(prompt_entity);
     }
     fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-        
+        if let Some(prompt_ui) = self.prompt_ui.take() {
+            // a nested state may have cleared it away already
+            let _ = data.world.delete_entity(prompt_ui);
+        }
+        data.world.write_storage::<Selected>().clear();
+        clear_preview(data.world);
     }
     fn handle_event(
         &mut self,
@@ -21,11 +291,144 @@ impl SimpleState for InputCollectionState {
         ev: StateEvent,
     ) -> SimpleTrans {
         let w = data.world;
-        match &ev {
-            StateEvent::Window(_event) => (),
-            StateEvent::Ui(_event) => (),
-            StateEvent::Input(_event) => (),
+        if let StateEvent::Window(event) = &ev {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+            if let winit::Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CursorMoved { position, .. } => {
+                        self.cursor = (position.x, position.y);
+                    }
+                    WindowEvent::MouseInput {
+                        state: winit::ElementState::Pressed,
+                        button: winit::MouseButton::Left,
+                        ..
+                    } => {
+                        return self.click(w);
+                    }
+                    WindowEvent::ReceivedCharacter(c)
+                        if !c.is_control()
+                            && (*c == ' ' && self.taking_text() || !c.is_whitespace()) =>
+                    {
+                        self.entry.push(*c);
+                        self.update_prompt(w);
+                    }
+                    WindowEvent::KeyboardInput {
+                        input:
+                            winit::KeyboardInput {
+                                virtual_keycode: Some(key),
+                                state: winit::ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    } if !(*key == VirtualKeyCode::Space && self.taking_text()) => {
+                        match is_confirmation(*key) {
+                            Some(true) => return self.confirm(w),
+                            Some(false) => return Trans::Pop,
+                            None if *key == VirtualKeyCode::Back => {
+                                self.entry.pop();
+                                self.update_prompt(w);
+                            }
+                            None => (),
+                        }
+                    }
+                    _ => (),
+                }
+            }
         }
         Trans::None
     }
-}
\ No newline at end of file
+}
+
+/// turn the result of running a command into the state transition that follows it
+pub fn finish_command(w: &mut World, command: &CommandDesc, result: CommandResult) -> SimpleTrans {
+    match result {
+        CommandResult::Done => Trans::Pop,
+        CommandResult::Repeat(found_inputs) => {
+            let next = InputCollectionState::new(command.clone(), found_inputs);
+            Trans::Switch(Box::new(next))
+        }
+        CommandResult::More(desc, prompt) => {
+            let next = InputCollectionState {
+                extra: Some((desc, prompt)),
+                ..InputCollectionState::new(command.clone(), vec![])
+            };
+            Trans::Switch(Box::new(next))
+        }
+        CommandResult::Failed(reason) => {
+            let mut history = w.write_resource::<LogHistory>();
+            history.push(format!("{}: {}", command.name, reason));
+            Trans::Pop
+        }
+        CommandResult::Quit => Trans::Quit,
+    }
+}
+
+/// the closest selectable drawable within the pick aperture of `at`
+fn pick_drawable(w: &World, at: Point) -> Option<Entity> {
+    let (aperture, tolerance) = {
+        let view = w.read_resource::<ViewInfo>();
+        (PICK_APERTURE * view.pixel_size(), view.tolerance())
+    };
+    let layers = w.read_resource::<Layers>();
+    let entities = w.entities();
+    let drawables = w.read_storage::<Drawable>();
+    (&entities, &drawables)
+        .join()
+        .filter(|(_, drawable)| {
+            drawable
+                .properties()
+                .and_then(|props| layers.get(props.layer))
+                .map(|layer| !(layer.hidden || layer.frozen || layer.locked))
+                .unwrap_or(true)
+        })
+        .map(|(entity, drawable)| (entity, drawable.distance_to(at, tolerance)))
+        .filter(|(_, distance)| *distance <= aperture)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(entity, _)| entity)
+}
+
+/// the closest snap point of a visible drawable within the snap aperture of `at`
+fn snap_point(w: &World, at: Point) -> Option<Point> {
+    let aperture = SNAP_APERTURE * w.read_resource::<ViewInfo>().pixel_size();
+    let layers = w.read_resource::<Layers>();
+    let drawables = w.read_storage::<Drawable>();
+    let previews = w.read_storage::<Preview>();
+    (&drawables, !&previews)
+        .join()
+        .filter(|(drawable, _)| {
+            drawable
+                .properties()
+                .and_then(|props| layers.get(props.layer))
+                .map(|layer| !(layer.hidden || layer.frozen))
+                .unwrap_or(true)
+        })
+        .flat_map(|(drawable, _)| drawable.snap_points())
+        .map(|p| (p, p.distance(at)))
+        .filter(|(_, distance)| *distance <= aperture)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(p, _)| p)
+}
+
+/// delete whatever the command showed while it was collecting inputs
+fn clear_preview(w: &mut World) {
+    let previews: Vec<Entity> = {
+        let entities = w.entities();
+        let previews = w.read_storage::<Preview>();
+        (&entities, &previews).join().map(|(e, _)| e).collect()
+    };
+    w.delete_entities(&previews)
+        .expect("failed to delete preview");
+}
+
+fn mark_selected(w: &mut World, entity: Entity, selected: bool) {
+    let mut storage = w.write_storage::<Selected>();
+    if selected {
+        storage
+            .insert(entity, Selected)
+            .expect("selected a dead entity");
+    } else {
+        storage.remove(entity);
+    }
+}
@@ -4,6 +4,6 @@ mod pan_state;
 mod root_state;
 
 pub use command_state::CommandEntryState;
-pub use input_collection::InputCollectionState;
+pub use input_collection::{finish_command, InputCollectionState};
 pub use pan_state::PanState;
 pub use root_state::RootState;
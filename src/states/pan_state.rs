@@ -9,16 +9,12 @@ use winit::WindowEvent;
 use crate::{common::ScreenTranslation, components::ActiveCamera, resources::ViewInfo};
 
 pub struct PanState {
-    pub initial: (f64, f64),
     pub last: (f64, f64),
 }
 
 impl PanState {
     pub fn new(initial: (f64, f64)) -> Self {
-        PanState {
-            initial,
-            last: initial,
-        }
+        PanState { last: initial }
     }
     fn move_camera(&mut self, w: &mut World, dx: f32, dy: f32) {
         w.exec(
@@ -48,7 +44,7 @@ impl SimpleState for PanState {
         let w = data.world;
         match &ev {
             StateEvent::Window(event) => {
-                if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                if is_close_requested(event) || is_key_down(event, VirtualKeyCode::Escape) {
                     return Trans::Quit;
                 }
 
@@ -73,12 +69,9 @@ impl SimpleState for PanState {
                             WindowEvent::MouseInput { state, button, .. } => {
                                 use winit::ElementState::*;
                                 use winit::MouseButton::*;
-                                match (button, state) {
-                                    (Middle, Released) => {
-                                        // println!("ended drag, mouse moved {}, {}", dx, dy);
-                                        return Trans::Pop;
-                                    }
-                                    _ => {}
+                                if let (Middle, Released) = (button, state) {
+                                    // println!("ended drag, mouse moved {}, {}", dx, dy);
+                                    return Trans::Pop;
                                 }
                             }
                             _ => {
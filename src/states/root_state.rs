@@ -1,4 +1,5 @@
 use amethyst::{
+    assets::{AssetStorage, Loader},
     core::transform::Transform,
     input::{is_close_requested, is_key_down, VirtualKeyCode},
     prelude::*,
@@ -8,15 +9,22 @@ use amethyst::{
         palette::Srgba,
     },
     // window::ScreenDimensions,
+    ui::{get_default_font, Anchor, FontAsset, UiText, UiTransform},
 };
 use winit::WindowEvent;
 
 use crate::{
     commands,
     common::reset_camera,
-    components::{ActiveCamera, Color, Drawable, FullColor},
-    resources::{Layer, Layers, LineType, LineTypes, ViewInfo, LogHistory},
+    components::{ActiveCamera, Color, Dependent, Drawable, Preview, Selected},
+    pat,
+    resources::{
+        ActiveDimStyle, ActiveLayer, ActiveLeaderStyle, ActiveTextStyle, Blocks, ChamferSettings,
+        DimStyle, DimStyles, HatchPatterns, LastSegment, Layer, Layers, LeaderStyle, LeaderStyles,
+        LineType, LineTypes, LogHistory, TextStyle, TextStyles, Units, ViewInfo,
+    },
     states::{CommandEntryState, PanState},
+    systems::LOG_UI_ID,
 };
 
 pub struct RootState {
@@ -27,18 +35,25 @@ impl SimpleState for RootState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let w = data.world;
         w.register::<Drawable>();
+        w.register::<Dependent>();
         w.register::<ActiveCamera>();
+        w.register::<Selected>();
+        w.register::<Preview>();
 
         w.insert(DebugLines::new());
         w.insert(DebugLinesParams { line_width: 0.5 });
         w.insert(ViewInfo::default());
         w.insert(LogHistory::new());
+        w.insert(LastSegment::default());
+        w.insert(ChamferSettings::default());
 
         let continous_line = LineType {
             draw_line: LineType::line_type_continous,
+            period: 0.0,
         };
         let hidden_line = LineType {
             draw_line: LineType::line_type_hidden,
+            period: 2.0,
         };
         let mut line_types = LineTypes::new();
         let linetype_id = line_types.push(continous_line);
@@ -48,20 +63,51 @@ impl SimpleState for RootState {
 
         let first_layer = Layer {
             name: "Zero".to_string(),
-            color: Color::Full(FullColor { r: 0, g: 0, b: 0 }),
+            color: Color::Fixed(7),
             line_type: linetype_id,
             hidden: false,
             frozen: false,
             locked: false,
         };
         let mut layers = Layers::new();
-        let _layer_id = layers.push(first_layer);
+        let layer_id = layers.push(first_layer);
 
         w.insert(layers);
         // for _ in 0..99 {
         //     let (a, b) = Line::create(linetype_id, layer_id);
         //     w.create_entity().with(a).with(b).build();
         // }
+        w.insert(ActiveLayer { id: layer_id });
+
+        let mut text_styles = TextStyles::new();
+        let style_id = text_styles.push(TextStyle::new("Standard"));
+        w.insert(text_styles);
+        w.insert(ActiveTextStyle { id: style_id });
+
+        let mut dim_styles = DimStyles::new();
+        let dim_style_id = dim_styles.push(DimStyle::new("Standard", style_id));
+        w.insert(dim_styles);
+        w.insert(ActiveDimStyle { id: dim_style_id });
+
+        let mut leader_styles = LeaderStyles::new();
+        let leader_style_id = leader_styles.push(LeaderStyle::new("Standard", style_id));
+        w.insert(leader_styles);
+        w.insert(ActiveLeaderStyle {
+            id: leader_style_id,
+        });
+        w.insert(Units::default());
+        w.insert(Blocks::new());
+        let patterns = amethyst::utils::application_root_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|root| pat::load(&root));
+        match patterns {
+            Ok(patterns) => w.insert(HatchPatterns { patterns }),
+            Err(e) => {
+                w.write_resource::<LogHistory>()
+                    .push(format!("no hatch patterns loaded, {}", e));
+                w.insert(HatchPatterns::default());
+            }
+        }
         let commands = commands::register_commands();
         w.insert(commands);
 
@@ -95,6 +141,30 @@ impl SimpleState for RootState {
 
         w.create_entity().with(debug_lines_component).build();
 
+        let font = {
+            let loader = w.read_resource::<Loader>();
+            let font_store = w.read_resource::<AssetStorage<FontAsset>>();
+            get_default_font(&loader, &font_store)
+        };
+        w.create_entity()
+            .with(UiText::new(
+                font,
+                "".to_string(),
+                [0.4, 0.4, 0.4, 1.0],
+                16.0,
+            ))
+            .with(UiTransform::new(
+                LOG_UI_ID.to_string(),
+                Anchor::BottomMiddle,
+                Anchor::BottomMiddle,
+                0.0,
+                40.0,
+                0.0,
+                400.0,
+                30.0,
+            ))
+            .build();
+
         let mut local_transform = Transform::default();
         local_transform.set_translation_xyz(10.0 / 2.0, 10.0 / 2.0, 10.0);
         w.create_entity()
@@ -117,7 +187,7 @@ impl SimpleState for RootState {
         let w = data.world;
         match &ev {
             StateEvent::Window(event) => {
-                if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+                if is_close_requested(event) || is_key_down(event, VirtualKeyCode::Escape) {
                     return Trans::Quit;
                 }
 
@@ -157,12 +227,9 @@ impl SimpleState for RootState {
                             WindowEvent::MouseInput { state, button, .. } => {
                                 use winit::ElementState::*;
                                 use winit::MouseButton::*;
-                                match (button, state) {
-                                    (Middle, Pressed) => {
-                                        let pan_state = PanState::new(self.cursor);
-                                        return Trans::Push(Box::new(pan_state));
-                                    }
-                                    _ => {}
+                                if let (Middle, Pressed) = (button, state) {
+                                    let pan_state = PanState::new(self.cursor);
+                                    return Trans::Push(Box::new(pan_state));
                                 }
                             }
                             WindowEvent::KeyboardInput { input, .. } => {
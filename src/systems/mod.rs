@@ -1,14 +1,304 @@
-/*
-struct LineSyncSystem {}
+use amethyst::{
+    renderer::{debug_drawing::DebugLines, palette::Srgba},
+    ui::{UiText, UiTransform},
+};
+use specs::prelude::*;
 
-impl<'a> System<'a> for LineSyncSystem {
-    type SystemData = (Write<'a, DebugLines>, ReadStorage<'a, Drawable>);
+use crate::{
+    components::{Anchor, Color, Dependent, Drawable, FullColor, Point, Selected},
+    resources::{Layers, LineType, LineTypes, LogHistory, ViewInfo},
+};
+
+/// id of the UiTransform which shows the latest log line
+pub const LOG_UI_ID: &str = "log";
+
+/// drawables sit in front of the background grid
+const DRAWING_DEPTH: f32 = 2.0;
+
+/// default color for drawables which don't carry their own properties
+const DEFAULT_COLOR: (f32, f32, f32) = (1.0, 1.0, 1.0);
+
+/// color of drawables picked by the running command
+const HIGHLIGHT_COLOR: Color = Color::Full(FullColor {
+    r: 255,
+    g: 160,
+    b: 0,
+});
+
+/// Pushes every visible drawable into the DebugLines resource each frame.
+pub struct DrawableRenderSystem;
+
+impl<'a> System<'a> for DrawableRenderSystem {
+    type SystemData = (
+        WriteExpect<'a, DebugLines>,
+        ReadExpect<'a, Layers>,
+        ReadExpect<'a, LineTypes>,
+        ReadExpect<'a, ViewInfo>,
+        ReadStorage<'a, Drawable>,
+        ReadStorage<'a, Selected>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut debug_lines, layers, line_types, view_info, drawables, selected) = data;
+        let pixel = view_info.pixel_size();
+        let view = view_info.bounds();
+        for (drawable, selected) in (&drawables, selected.maybe()).join() {
+            let segments = drawable.segments(view_info.tolerance());
+            if let Some(props) = drawable.properties() {
+                let layer = layers.get(props.layer);
+                if layer.map(|l| l.hidden).unwrap_or(false) {
+                    continue;
+                }
+                let color = match selected {
+                    Some(_) => HIGHLIGHT_COLOR.srgba(),
+                    None => props.color.srgba(),
+                };
+                let line_type = line_types.get(props.linetype);
+                for (start, end) in segments {
+                    let runs = match line_type {
+                        Some(line_type) => {
+                            dash_runs(start, end, line_type, props.scale, pixel, view)
+                        }
+                        None => vec![(start, end)],
+                    };
+                    for (a, b) in runs {
+                        draw_weighted(&mut debug_lines, a, b, props.weight, pixel, color);
+                    }
+                }
+            } else {
+                let (r, g, b) = DEFAULT_COLOR;
+                let color = match selected {
+                    Some(_) => HIGHLIGHT_COLOR.srgba(),
+                    None => Srgba::new(r, g, b, 1.0),
+                };
+                for (start, end) in segments {
+                    draw_weighted(&mut debug_lines, start, end, 1.0, pixel, color);
+                }
+            }
+        }
+    }
+}
+
+/// Works dependents out again when a drawable they follow changes, and lets
+/// go of their sources once one of them is erased.
+#[derive(Default)]
+pub struct DependencySystem {
+    reader: Option<ReaderId<ComponentEvent>>,
+    modified: BitSet,
+    removed: BitSet,
+}
+
+impl<'a> System<'a> for DependencySystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Drawable>,
+        WriteStorage<'a, Dependent>,
+    );
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(WriteStorage::<Drawable>::fetch(world).register_reader());
+    }
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut drawables, mut dependents) = data;
+        self.modified.clear();
+        self.removed.clear();
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("dependency system was never set up");
+        for event in drawables.channel().read(reader) {
+            match event {
+                ComponentEvent::Modified(id) => {
+                    self.modified.add(*id);
+                }
+                ComponentEvent::Removed(id) => {
+                    self.removed.add(*id);
+                }
+                ComponentEvent::Inserted(_) => (),
+            }
+        }
+        let mut detached = vec![];
+        let mut moved = vec![];
+        for (entity, dependent) in (&entities, &dependents).join() {
+            let erased = dependent.links.iter().any(|link| {
+                self.removed.contains(link.source.id()) && drawables.get(link.source).is_none()
+            });
+            if erased {
+                detached.push(entity);
+                continue;
+            }
+            if !dependent
+                .links
+                .iter()
+                .any(|link| self.modified.contains(link.source.id()))
+            {
+                continue;
+            }
+            let anchors: Option<Vec<Anchor>> = dependent
+                .links
+                .iter()
+                .map(|link| drawables.get(link.source)?.feature(link.feature))
+                .collect();
+            match anchors {
+                Some(anchors) => moved.push((entity, anchors)),
+                None => detached.push(entity),
+            }
+        }
+        for (entity, anchors) in moved {
+            let followed = drawables
+                .get_mut(entity)
+                .map(|drawable| drawable.follow(&anchors))
+                .unwrap_or(false);
+            if !followed {
+                detached.push(entity);
+            }
+        }
+        for entity in detached {
+            dependents.remove(entity);
+        }
+    }
+}
+
+/// most times one period of a line type's pattern is read to find its dashes
+const MAX_PATTERN_SAMPLES: f32 = 256.0;
+
+/// Splits a segment into the pieces its line type says to draw, along the
+/// part of it inside `view`. One period of the pattern is read about a pixel
+/// at a time and repeated; once a period is smaller than a pixel the segment
+/// is drawn unbroken.
+fn dash_runs(
+    start: Point,
+    end: Point,
+    line_type: &LineType,
+    scale: f32,
+    pixel: f32,
+    view: (Point, Point),
+) -> Vec<(Point, Point)> {
+    let length = start.distance(end);
+    if length <= f32::EPSILON {
+        return vec![];
+    }
+    // how far along the drawing the pattern repeats
+    let period = line_type.period / scale;
+    if !(period.is_finite() && period >= pixel) {
+        return vec![(start, end)];
+    }
+    let (from, to) = match visible_span(start, end, view) {
+        Some((from, to)) => (from * length, to * length),
+        None => return vec![],
+    };
+    let step = pixel.max(period / MAX_PATTERN_SAMPLES);
+    let samples = (period / step).ceil() as usize;
+    let mut dashes = vec![];
+    let mut dash_start = None;
+    for i in 0..samples {
+        let position = i as f32 * step;
+        let on = (line_type.draw_line)(position, scale);
+        match (on, dash_start) {
+            (true, None) => dash_start = Some(position),
+            (false, Some(from)) => {
+                dashes.push((from, position));
+                dash_start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(from) = dash_start {
+        dashes.push((from, period));
+    }
+    let at = |t: f32| start + (end - start) * (t / length);
+    let mut runs = vec![];
+    let first = (from / period).floor() as i64;
+    let last = (to / period).ceil() as i64;
+    for repeat in first..last {
+        let offset = repeat as f32 * period;
+        for (a, b) in dashes.iter() {
+            let (a, b) = ((offset + a).max(from), (offset + b).min(to));
+            if b > a {
+                runs.push((at(a), at(b)));
+            }
+        }
+    }
+    runs
+}
+
+/// the stretch of the segment from `start` to `end` inside the box between
+/// the corners of `view`, as fractions along it
+fn visible_span(start: Point, end: Point, view: (Point, Point)) -> Option<(f32, f32)> {
+    let (low, high) = view;
+    let d = end - start;
+    let (mut from, mut to) = (0.0f32, 1.0f32);
+    let edges = [
+        (-d.x, start.x - low.x),
+        (d.x, high.x - start.x),
+        (-d.y, start.y - low.y),
+        (d.y, high.y - start.y),
+    ];
+    for (toward, room) in edges.iter() {
+        if toward.abs() <= f32::EPSILON {
+            // running alongside this edge, all in or all out
+            if *room < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = room / toward;
+        if *toward < 0.0 {
+            from = from.max(t);
+        } else {
+            to = to.min(t);
+        }
+    }
+    if from < to {
+        Some((from, to))
+    } else {
+        None
+    }
+}
+
+/// Draws heavier lines as several parallel lines one pixel apart.
+fn draw_weighted(
+    debug_lines: &mut DebugLines,
+    start: Point,
+    end: Point,
+    weight: f32,
+    pixel: f32,
+    color: Srgba,
+) {
+    let length = start.distance(end);
+    let (nx, ny) = if length > 0.0 {
+        (-(end.y - start.y) / length, (end.x - start.x) / length)
+    } else {
+        (0.0, 0.0)
+    };
+    let copies = weight.round().max(1.0) as i32;
+    for i in 0..copies {
+        let offset = (i as f32 - (copies - 1) as f32 / 2.0) * pixel;
+        let (dx, dy) = (nx * offset, ny * offset);
+        debug_lines.draw_line(
+            [start.x + dx, start.y + dy, DRAWING_DEPTH].into(),
+            [end.x + dx, end.y + dy, DRAWING_DEPTH].into(),
+            color,
+        );
+    }
+}
+
+/// Shows the most recent log line above the command line.
+pub struct LogDisplaySystem;
+
+impl<'a> System<'a> for LogDisplaySystem {
+    type SystemData = (
+        Read<'a, LogHistory>,
+        ReadStorage<'a, UiTransform>,
+        WriteStorage<'a, UiText>,
+    );
     fn run(&mut self, data: Self::SystemData) {
-        let (debug_lines, drawables) = data;
-        for drawable in (drawables).join() {
-            //
+        let (history, transforms, mut texts) = data;
+        if let Some(last) = history.last() {
+            for (transform, text) in (&transforms, &mut texts).join() {
+                if transform.id == LOG_UI_ID && text.text != last {
+                    text.text = last.to_string();
+                }
+            }
         }
-        unimplemented!()
     }
 }
-*/